@@ -0,0 +1,9 @@
+-- CreateTable
+CREATE TABLE "timezone_log" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "timezone" TEXT NOT NULL,
+    "start" INTEGER NOT NULL,
+    "deleted_at" INTEGER NOT NULL DEFAULT 0,
+    "created_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    "updated_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000))
+);
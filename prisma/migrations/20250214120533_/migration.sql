@@ -0,0 +1,5 @@
+-- CreateIndex
+CREATE INDEX "activity_deleted_at_start_end_idx" ON "activity"("deleted_at", "start", "end");
+
+-- CreateIndex
+CREATE INDEX "note_deleted_at_start_end_idx" ON "note"("deleted_at", "start", "end");
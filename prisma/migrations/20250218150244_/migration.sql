@@ -0,0 +1,105 @@
+-- CreateTable
+CREATE TABLE "daily_summary" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "date" TEXT NOT NULL,
+    "start" INTEGER NOT NULL,
+    "end" INTEGER NOT NULL,
+    "label_id" INTEGER,
+    "program_id" INTEGER,
+    "total" INTEGER NOT NULL,
+    "clock" TEXT NOT NULL,
+    "created_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    "updated_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    CONSTRAINT "daily_summary_label_id_fkey" FOREIGN KEY ("label_id") REFERENCES "label" ("id") ON DELETE SET NULL ON UPDATE CASCADE,
+    CONSTRAINT "daily_summary_program_id_fkey" FOREIGN KEY ("program_id") REFERENCES "program" ("id") ON DELETE SET NULL ON UPDATE CASCADE
+);
+
+-- CreateTable
+CREATE TABLE "daily_summary_dirty" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "start" INTEGER NOT NULL,
+    "end" INTEGER NOT NULL,
+    "created_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000))
+);
+
+-- CreateIndex
+CREATE INDEX "daily_summary_date_idx" ON "daily_summary"("date");
+
+-- CreateIndex
+CREATE INDEX "daily_summary_start_end_idx" ON "daily_summary"("start", "end");
+
+-- Mark the time of changed notes and activities, so the days they touch are summarized again.
+-- A running note or activity is updated every few seconds, an overlapping range is extended
+-- instead of adding a row every time.
+
+-- CreateTrigger
+CREATE TRIGGER "note_daily_summary_insert" AFTER INSERT ON "note"
+BEGIN
+    UPDATE "daily_summary_dirty" SET "start" = MIN("start", NEW."start"), "end" = MAX("end", NEW."end")
+    WHERE "start" <= NEW."end" AND "end" >= NEW."start";
+    INSERT INTO "daily_summary_dirty" ("start", "end") SELECT NEW."start", NEW."end"
+    WHERE NOT EXISTS (SELECT 1 FROM "daily_summary_dirty" WHERE "start" <= NEW."end" AND "end" >= NEW."start");
+END;
+
+-- CreateTrigger
+CREATE TRIGGER "note_daily_summary_update" AFTER UPDATE OF "start", "end", "label_id", "deleted_at" ON "note"
+BEGIN
+    UPDATE "daily_summary_dirty"
+    SET "start" = MIN("start", OLD."start", NEW."start"), "end" = MAX("end", OLD."end", NEW."end")
+    WHERE "start" <= MAX(OLD."end", NEW."end") AND "end" >= MIN(OLD."start", NEW."start");
+    INSERT INTO "daily_summary_dirty" ("start", "end") SELECT MIN(OLD."start", NEW."start"), MAX(OLD."end", NEW."end")
+    WHERE NOT EXISTS (
+        SELECT 1 FROM "daily_summary_dirty"
+        WHERE "start" <= MAX(OLD."end", NEW."end") AND "end" >= MIN(OLD."start", NEW."start")
+    );
+END;
+
+-- CreateTrigger
+CREATE TRIGGER "note_daily_summary_delete" AFTER DELETE ON "note"
+BEGIN
+    INSERT INTO "daily_summary_dirty" ("start", "end") VALUES (OLD."start", OLD."end");
+END;
+
+-- CreateTrigger
+CREATE TRIGGER "activity_daily_summary_insert" AFTER INSERT ON "activity"
+BEGIN
+    UPDATE "daily_summary_dirty" SET "start" = MIN("start", NEW."start"), "end" = MAX("end", NEW."end")
+    WHERE "start" <= NEW."end" AND "end" >= NEW."start";
+    INSERT INTO "daily_summary_dirty" ("start", "end") SELECT NEW."start", NEW."end"
+    WHERE NOT EXISTS (SELECT 1 FROM "daily_summary_dirty" WHERE "start" <= NEW."end" AND "end" >= NEW."start");
+END;
+
+-- CreateTrigger
+CREATE TRIGGER "activity_daily_summary_update" AFTER UPDATE OF "start", "end", "program_id", "deleted_at" ON "activity"
+BEGIN
+    UPDATE "daily_summary_dirty"
+    SET "start" = MIN("start", OLD."start", NEW."start"), "end" = MAX("end", OLD."end", NEW."end")
+    WHERE "start" <= MAX(OLD."end", NEW."end") AND "end" >= MIN(OLD."start", NEW."start");
+    INSERT INTO "daily_summary_dirty" ("start", "end") SELECT MIN(OLD."start", NEW."start"), MAX(OLD."end", NEW."end")
+    WHERE NOT EXISTS (
+        SELECT 1 FROM "daily_summary_dirty"
+        WHERE "start" <= MAX(OLD."end", NEW."end") AND "end" >= MIN(OLD."start", NEW."start")
+    );
+END;
+
+-- CreateTrigger
+CREATE TRIGGER "activity_daily_summary_delete" AFTER DELETE ON "activity"
+BEGIN
+    INSERT INTO "daily_summary_dirty" ("start", "end") VALUES (OLD."start", OLD."end");
+END;
+
+-- Notes of a deleted label and activities of a deleted program are no longer counted.
+
+-- CreateTrigger
+CREATE TRIGGER "label_daily_summary_delete" AFTER UPDATE OF "deleted_at" ON "label"
+BEGIN
+    INSERT INTO "daily_summary_dirty" ("start", "end")
+    SELECT "start", "end" FROM "note" WHERE "label_id" = NEW."id" AND "deleted_at" = 0;
+END;
+
+-- CreateTrigger
+CREATE TRIGGER "program_daily_summary_delete" AFTER UPDATE OF "deleted_at" ON "program"
+BEGIN
+    INSERT INTO "daily_summary_dirty" ("start", "end")
+    SELECT "start", "end" FROM "activity" WHERE "program_id" = NEW."id" AND "deleted_at" = 0;
+END;
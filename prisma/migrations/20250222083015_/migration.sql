@@ -0,0 +1,14 @@
+-- CreateTable
+CREATE TABLE "goal" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "target_type" INTEGER NOT NULL,
+    "target_id" INTEGER NOT NULL,
+    "period" INTEGER NOT NULL,
+    "duration" INTEGER NOT NULL,
+    "at_most" BOOLEAN NOT NULL DEFAULT false,
+    "start" INTEGER NOT NULL,
+    "sort" INTEGER NOT NULL DEFAULT 0,
+    "deleted_at" INTEGER NOT NULL DEFAULT 0,
+    "created_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    "updated_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000))
+);
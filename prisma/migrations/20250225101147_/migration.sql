@@ -0,0 +1,25 @@
+-- CreateTable
+CREATE TABLE "usage_limit" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "target_type" INTEGER NOT NULL,
+    "target_id" INTEGER NOT NULL,
+    "period" INTEGER NOT NULL,
+    "duration" INTEGER NOT NULL,
+    "warning_percent" INTEGER NOT NULL DEFAULT 80,
+    "sort" INTEGER NOT NULL DEFAULT 0,
+    "deleted_at" INTEGER NOT NULL DEFAULT 0,
+    "created_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    "updated_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000))
+);
+
+-- CreateTable
+CREATE TABLE "usage_limit_snooze" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "usage_limit_id" INTEGER NOT NULL,
+    "start" INTEGER NOT NULL,
+    "end" INTEGER NOT NULL,
+    "deleted_at" INTEGER NOT NULL DEFAULT 0,
+    "created_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    "updated_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    CONSTRAINT "usage_limit_snooze_usage_limit_id_fkey" FOREIGN KEY ("usage_limit_id") REFERENCES "usage_limit" ("id") ON DELETE RESTRICT ON UPDATE CASCADE
+);
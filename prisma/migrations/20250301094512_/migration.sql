@@ -0,0 +1,14 @@
+-- CreateTable
+CREATE TABLE "time_block" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "label_id" INTEGER NOT NULL,
+    "start" INTEGER NOT NULL,
+    "end" INTEGER NOT NULL,
+    "recurrence" INTEGER NOT NULL DEFAULT 0,
+    "recurrence_end" INTEGER,
+    "sort" INTEGER NOT NULL DEFAULT 0,
+    "deleted_at" INTEGER NOT NULL DEFAULT 0,
+    "created_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    "updated_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    CONSTRAINT "time_block_label_id_fkey" FOREIGN KEY ("label_id") REFERENCES "label" ("id") ON DELETE RESTRICT ON UPDATE CASCADE
+);
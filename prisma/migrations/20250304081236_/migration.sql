@@ -0,0 +1,38 @@
+-- AlterTable
+ALTER TABLE "note" ADD COLUMN "note_template_id" INTEGER;
+ALTER TABLE "note" ADD COLUMN "occurrence" INTEGER;
+
+-- CreateTable
+CREATE TABLE "note_template" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "label_id" INTEGER NOT NULL,
+    "start" INTEGER NOT NULL,
+    "end" INTEGER NOT NULL,
+    "rrule" TEXT,
+    "materialized_until" INTEGER NOT NULL DEFAULT 0,
+    "sort" INTEGER NOT NULL DEFAULT 0,
+    "deleted_at" INTEGER NOT NULL DEFAULT 0,
+    "created_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    "updated_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    CONSTRAINT "note_template_label_id_fkey" FOREIGN KEY ("label_id") REFERENCES "label" ("id") ON DELETE RESTRICT ON UPDATE CASCADE
+);
+
+-- CreateTable
+CREATE TABLE "note_template_exception" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "note_template_id" INTEGER NOT NULL,
+    "occurrence" INTEGER NOT NULL,
+    "skip" BOOLEAN NOT NULL DEFAULT false,
+    "start" INTEGER,
+    "end" INTEGER,
+    "deleted_at" INTEGER NOT NULL DEFAULT 0,
+    "created_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    "updated_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    CONSTRAINT "note_template_exception_note_template_id_fkey" FOREIGN KEY ("note_template_id") REFERENCES "note_template" ("id") ON DELETE RESTRICT ON UPDATE CASCADE
+);
+
+-- CreateIndex
+CREATE UNIQUE INDEX "note_note_template_id_occurrence_key" ON "note"("note_template_id", "occurrence");
+
+-- CreateIndex
+CREATE UNIQUE INDEX "note_template_exception_note_template_id_occurrence_deleted_at_key" ON "note_template_exception"("note_template_id", "occurrence", "deleted_at");
@@ -0,0 +1,12 @@
+-- CreateTable
+CREATE TABLE "program_alias" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "program_id" INTEGER NOT NULL,
+    "pattern" TEXT NOT NULL,
+    "kind" INTEGER NOT NULL DEFAULT 0,
+    "sort" INTEGER NOT NULL DEFAULT 0,
+    "deleted_at" INTEGER NOT NULL DEFAULT 0,
+    "created_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    "updated_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    CONSTRAINT "program_alias_program_id_fkey" FOREIGN KEY ("program_id") REFERENCES "program" ("id") ON DELETE RESTRICT ON UPDATE CASCADE
+);
@@ -0,0 +1,5 @@
+-- AlterTable
+ALTER TABLE "label" ADD COLUMN "parent_id" INTEGER;
+
+-- CreateIndex
+CREATE INDEX "label_parent_id_idx" ON "label"("parent_id");
@@ -0,0 +1,26 @@
+-- CreateTable
+CREATE TABLE "change" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "action" TEXT NOT NULL,
+    "target_id" INTEGER,
+    "deleted_at" INTEGER NOT NULL DEFAULT 0,
+    "created_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    "updated_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000))
+);
+
+-- CreateTable
+CREATE TABLE "change_entry" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "change_id" INTEGER NOT NULL,
+    "table_name" TEXT NOT NULL,
+    "row_id" INTEGER NOT NULL,
+    "before" TEXT,
+    "after" TEXT,
+    "deleted_at" INTEGER NOT NULL DEFAULT 0,
+    "created_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    "updated_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    CONSTRAINT "change_entry_change_id_fkey" FOREIGN KEY ("change_id") REFERENCES "change" ("id") ON DELETE RESTRICT ON UPDATE CASCADE
+);
+
+-- CreateIndex
+CREATE INDEX "change_entry_change_id_idx" ON "change_entry"("change_id");
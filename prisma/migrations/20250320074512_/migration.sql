@@ -0,0 +1,2 @@
+-- AlterTable
+ALTER TABLE "note" ADD COLUMN "title" TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,10 @@
+-- Notes and activities recorded before the daily summary was added are summarized on the next refresh.
+
+-- InsertData
+INSERT INTO "daily_summary_dirty" ("start", "end")
+SELECT MIN("start"), MAX("end") FROM (
+    SELECT "start", "end" FROM "note" WHERE "deleted_at" = 0
+    UNION ALL
+    SELECT "start", "end" FROM "activity" WHERE "deleted_at" = 0
+)
+HAVING COUNT(*) > 0;
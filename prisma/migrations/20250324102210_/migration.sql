@@ -0,0 +1,34 @@
+-- RedefineTables
+COMMIT TRANSACTION;
+PRAGMA foreign_keys=OFF;
+BEGIN TRANSACTION;
+CREATE TABLE "new_time_block" (
+    "id" INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    "label_id" INTEGER NOT NULL,
+    "start" INTEGER NOT NULL,
+    "end" INTEGER NOT NULL,
+    "rrule" TEXT,
+    "sort" INTEGER NOT NULL DEFAULT 0,
+    "deleted_at" INTEGER NOT NULL DEFAULT 0,
+    "created_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    "updated_at" INTEGER NOT NULL DEFAULT (ROUND((julianday('now') - 2440587.5) * 86400000)),
+    CONSTRAINT "time_block_label_id_fkey" FOREIGN KEY ("label_id") REFERENCES "label" ("id") ON DELETE RESTRICT ON UPDATE CASCADE
+);
+-- 1 was daily, 2 weekdays and 3 weekly, the exclusive end becomes the last second before it
+INSERT INTO "new_time_block" ("created_at", "deleted_at", "end", "id", "label_id", "rrule", "sort", "start", "updated_at")
+SELECT "created_at", "deleted_at", "end", "id", "label_id",
+    CASE "recurrence"
+        WHEN 1 THEN 'FREQ=DAILY'
+        WHEN 2 THEN 'FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR'
+        WHEN 3 THEN 'FREQ=WEEKLY'
+    END || CASE WHEN "recurrence_end" IS NULL THEN ''
+        ELSE ';UNTIL=' || strftime('%Y%m%dT%H%M%SZ', ("recurrence_end" - 1) / 1000, 'unixepoch')
+    END,
+    "sort", "start", "updated_at"
+FROM "time_block";
+DROP TABLE "time_block";
+ALTER TABLE "new_time_block" RENAME TO "time_block";
+PRAGMA foreign_key_check;
+COMMIT TRANSACTION;
+PRAGMA foreign_keys=ON;
+BEGIN TRANSACTION;
@@ -0,0 +1,2 @@
+-- AlterTable
+ALTER TABLE "change_entry" ADD COLUMN "row_id_list" TEXT;
@@ -0,0 +1,42 @@
+//! Request bodies of the native api, shared by the app and `shion-cli`.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct AuthRequest {
+    pub path: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct CreateRemarkRequest {
+    pub title: String,
+    pub desc: String,
+    pub arg: Option<String>,
+    pub time: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct StartNoteRequest {
+    /// Name of the label, created under `plan_id` when it does not exist.
+    pub label: String,
+    pub plan_id: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct StatusQuery {
+    pub start: i64,
+    pub end: i64,
+    /// Also report the time covered by any note or activity.
+    pub busy: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct FocusQuery {
+    pub start: i64,
+    pub end: i64,
+    /// Measure every hour instead of every day.
+    pub hourly: Option<bool>,
+    /// Measure focus on dimensions instead of programs.
+    pub dimension: Option<bool>,
+    pub idle_threshold: Option<i64>,
+}
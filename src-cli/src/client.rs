@@ -0,0 +1,99 @@
+use std::io::{BufRead, BufReader};
+
+use reqwest::{
+    blocking::{self, RequestBuilder, Response},
+    Method, StatusCode,
+};
+use serde::Serialize;
+use serde_json::Value;
+use shion_api::AuthRequest;
+
+use crate::config::Config;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+pub struct Client {
+    config: Config,
+    http: blocking::Client,
+}
+
+/// Requests a token for the program at `path`, which must have been recorded by shion.
+pub fn auth(server: &str, path: &str) -> Result<String> {
+    let response = blocking::Client::new()
+        .post(format!("{}/api/auth", server))
+        .json(&AuthRequest {
+            path: path.to_string(),
+        })
+        .send()?;
+    let data = read_data(response)?;
+    Ok(data["token"]
+        .as_str()
+        .ok_or("unexpected response from server")?
+        .to_string())
+}
+
+/// Unwraps the `data` of a `{ success, data, message }` response.
+fn read_data(response: Response) -> Result<Value> {
+    let body = response.json::<Value>()?;
+    if body["success"].as_bool() != Some(true) {
+        let message = body["message"].as_str().unwrap_or("request failed");
+        return Err(message.into());
+    }
+    Ok(body["data"].clone())
+}
+
+impl Client {
+    pub fn new(config: Config) -> Result<Client> {
+        // the event stream stays open, so requests never time out
+        let http = blocking::Client::builder().timeout(None).build()?;
+        Ok(Client { config, http })
+    }
+
+    fn send(
+        &mut self,
+        build: impl Fn(&blocking::Client, &str) -> RequestBuilder,
+    ) -> Result<Response> {
+        let response = build(&self.http, &self.config.server)
+            .bearer_auth(&self.config.token)
+            .send()?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+        // tokens expire after a day, renew it with the paired program
+        self.config.token = auth(&self.config.server, &self.config.path)?;
+        self.config.save()?;
+        Ok(build(&self.http, &self.config.server)
+            .bearer_auth(&self.config.token)
+            .send()?)
+    }
+
+    pub fn post<T: Serialize>(&mut self, path: &str, body: &T) -> Result<Value> {
+        let response = self.send(|http, server| {
+            http.request(Method::POST, format!("{}/api{}", server, path))
+                .json(body)
+        })?;
+        read_data(response)
+    }
+
+    pub fn get<T: Serialize>(&mut self, path: &str, query: &T) -> Result<Value> {
+        let response = self.send(|http, server| {
+            http.request(Method::GET, format!("{}/api{}", server, path))
+                .query(query)
+        })?;
+        read_data(response)
+    }
+
+    /// Calls `f` with the `data` of every server-sent event until the server closes the stream.
+    pub fn subscribe(&mut self, mut f: impl FnMut(Value)) -> Result<()> {
+        let response = self.send(|http, server| http.get(format!("{}/api/events", server)))?;
+        if !response.status().is_success() {
+            return Err(format!("failed to subscribe: {}", response.status()).into());
+        }
+        for line in BufReader::new(response).lines() {
+            if let Some(data) = line?.strip_prefix("data: ") {
+                f(serde_json::from_str(data)?);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use std::{fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_SERVER: &str = "http://localhost:15785";
+
+#[derive(Deserialize, Serialize, Default)]
+pub struct Config {
+    pub server: String,
+    /// Program path the cli was paired with, used to renew the token.
+    pub path: String,
+    pub token: String,
+}
+
+fn config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let dir = dirs::config_dir().ok_or("cannot find the config directory")?;
+    Ok(dir.join("shion").join("cli.json"))
+}
+
+impl Config {
+    pub fn load() -> Result<Config, Box<dyn std::error::Error>> {
+        let path = config_path()?;
+        if !path.exists() {
+            return Err("not paired yet, run `shion-cli pair <path>` first".into());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = config_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
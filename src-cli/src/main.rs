@@ -0,0 +1,274 @@
+use chrono::{Days, Local, NaiveDate, NaiveTime, TimeZone};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use serde_json::{json, Value};
+
+mod client;
+mod config;
+
+use client::Client;
+use config::{Config, DEFAULT_SERVER};
+use shion_api::{CreateRemarkRequest, FocusQuery, StartNoteRequest, StatusQuery};
+
+fn format_duration(ms: i64) -> String {
+    let minutes = ms / 1000 / 60;
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+fn print(value: &Value, json: bool, text: impl FnOnce(&Value)) {
+    if json {
+        println!("{}", value);
+    } else {
+        text(value);
+    }
+}
+
+fn pair(matches: &ArgMatches, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let path = matches.get_one::<String>("path").unwrap().to_string();
+    let server = matches.get_one::<String>("server").unwrap().to_string();
+    let token = client::auth(&server, &path)?;
+    Config {
+        server: server.clone(),
+        path,
+        token,
+    }
+    .save()?;
+    print(&json!({ "server": server }), json, |_| {
+        println!("paired with {}", server)
+    });
+    Ok(())
+}
+
+/// Start of the local day in milliseconds.
+fn day_start(date: NaiveDate) -> Result<i64, Box<dyn std::error::Error>> {
+    Ok(Local
+        .from_local_datetime(&date.and_time(NaiveTime::MIN))
+        .earliest()
+        .ok_or("invalid local time")?
+        .timestamp_millis())
+}
+
+fn today(client: &mut Client, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let date = Local::now().date_naive();
+    let data = client.get(
+        "/status",
+        &StatusQuery {
+            start: day_start(date)?,
+            end: day_start(date + Days::new(1))?,
+            busy: Some(true),
+        },
+    )?;
+    let status = &data["status"][date.format("%Y-%m-%d").to_string()];
+    print(status, json, |status| {
+        if status.is_null() {
+            println!("nothing recorded today");
+            return;
+        }
+        println!(
+            "{:<32}{}",
+            "total",
+            format_duration(status["total"].as_i64().unwrap_or(0))
+        );
+        println!(
+            "{:<32}{}",
+            "busy",
+            format_duration(status["busy"].as_i64().unwrap_or(0))
+        );
+        for item in status["list"].as_array().into_iter().flatten() {
+            println!(
+                "{:<32}{}",
+                item["name"].as_str().unwrap_or(""),
+                format_duration(item["total"].as_i64().unwrap_or(0))
+            );
+        }
+    });
+    Ok(())
+}
+
+fn focus(
+    client: &mut Client,
+    matches: &ArgMatches,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let days = *matches.get_one::<u64>("days").unwrap();
+    let end = Local::now().date_naive() + Days::new(1);
+    let data = client.get(
+        "/focus",
+        &FocusQuery {
+            start: day_start(end - Days::new(days))?,
+            end: day_start(end)?,
+            hourly: Some(matches.get_flag("hourly")),
+            dimension: Some(matches.get_flag("dimension")),
+            idle_threshold: None,
+        },
+    )?;
+    print(&data, json, |data| {
+        println!(
+            "{:<20}{:>10}{:>12}{:>12}",
+            "start", "switches", "median", "longest"
+        );
+        for item in data["list"].as_array().into_iter().flatten() {
+            let start = Local
+                .timestamp_millis_opt(item["start"].as_i64().unwrap_or(0))
+                .single()
+                .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            println!(
+                "{:<20}{:>10}{:>12}{:>12}",
+                start,
+                item["switchCount"],
+                format_duration(item["medianFocus"].as_i64().unwrap_or(0)),
+                format_duration(item["longestFocus"].as_i64().unwrap_or(0))
+            );
+        }
+    });
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let matches = Command::new("shion-cli")
+        .about("Command line client of the shion api")
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Print responses as JSON"),
+        )
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("pair")
+                .about("Authorize the cli as a program recorded by shion")
+                .arg(
+                    Arg::new("path")
+                        .help("The exe path of the program")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("server")
+                        .long("server")
+                        .default_value(DEFAULT_SERVER)
+                        .help("The address of the api service"),
+                ),
+        )
+        .subcommand(
+            Command::new("remark")
+                .about("Create a remark")
+                .arg(Arg::new("title").required(true))
+                .arg(Arg::new("desc").default_value(""))
+                .arg(
+                    Arg::new("arg")
+                        .long("arg")
+                        .help("Argument to open the remark with"),
+                ),
+        )
+        .subcommand(
+            Command::new("note")
+                .about("Start or stop a note")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("start")
+                        .about("Start a note, stopping the running one")
+                        .arg(Arg::new("label").required(true))
+                        .arg(
+                            Arg::new("plan")
+                                .long("plan")
+                                .value_parser(clap::value_parser!(i64))
+                                .help("Plan of the label, when it has to be created"),
+                        ),
+                )
+                .subcommand(Command::new("stop").about("Stop the running note")),
+        )
+        .subcommand(Command::new("today").about("Print the totals of today"))
+        .subcommand(
+            Command::new("focus")
+                .about("Print switches between programs and focus spans of recent days")
+                .arg(
+                    Arg::new("days")
+                        .long("days")
+                        .value_parser(clap::value_parser!(u64).range(1..))
+                        .default_value("7"),
+                )
+                .arg(
+                    Arg::new("hourly")
+                        .long("hourly")
+                        .action(ArgAction::SetTrue)
+                        .help("Measure every hour instead of every day"),
+                )
+                .arg(
+                    Arg::new("dimension")
+                        .long("dimension")
+                        .action(ArgAction::SetTrue)
+                        .help("Measure focus on dimensions instead of programs"),
+                ),
+        )
+        .subcommand(Command::new("events").about("Print events as they happen"))
+        .get_matches();
+
+    let json = matches.get_flag("json");
+
+    if let Some(("pair", sub_m)) = matches.subcommand() {
+        return pair(sub_m, json);
+    }
+
+    let mut client = Client::new(Config::load()?)?;
+
+    match matches.subcommand() {
+        Some(("remark", sub_m)) => {
+            let data = client.post(
+                "/remark/create",
+                &CreateRemarkRequest {
+                    title: sub_m.get_one::<String>("title").unwrap().to_string(),
+                    desc: sub_m.get_one::<String>("desc").unwrap().to_string(),
+                    arg: sub_m.get_one::<String>("arg").cloned(),
+                    time: None,
+                },
+            )?;
+            print(&data, json, |_| println!("remark created"));
+        }
+        Some(("note", sub_m)) => match sub_m.subcommand() {
+            Some(("start", start_m)) => {
+                let data = client.post(
+                    "/note/start",
+                    &StartNoteRequest {
+                        label: start_m.get_one::<String>("label").unwrap().to_string(),
+                        plan_id: start_m.get_one::<i64>("plan").copied(),
+                    },
+                )?;
+                print(&data, json, |data| println!("note {} started", data["id"]));
+            }
+            Some(("stop", _)) => {
+                let data = client.post("/note/stop", &json!({}))?;
+                print(&data, json, |data| match data["id"].as_i64() {
+                    Some(id) => println!("note {} stopped", id),
+                    None => println!("no running note"),
+                });
+            }
+            _ => unreachable!(),
+        },
+        Some(("today", _)) => today(&mut client, json)?,
+        Some(("focus", sub_m)) => focus(&mut client, sub_m, json)?,
+        Some(("events", _)) => client.subscribe(|event| {
+            print(&event, json, |event| {
+                println!(
+                    "{}\t{}",
+                    event["event"].as_str().unwrap_or(""),
+                    event["payload"]
+                )
+            })
+        })?,
+        _ => unreachable!(),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0), "0h 00m");
+        assert_eq!(format_duration((90 * 60 + 59) * 1000), "1h 30m");
+    }
+}
@@ -0,0 +1,126 @@
+//! Path patterns that map several paths to one program, e.g. its version directories.
+//!
+//! A pattern matches the whole path. In a glob `*` and `?` stay within a path segment and `**`
+//! crosses segments, both `/` and `\` separate segments.
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// Changes whenever an alias is added, edited or removed.
+pub type AliasVersion = (i64, i64, i64);
+
+/// Compiled aliases in match order, with the program each of them maps to.
+pub type AliasList = Arc<Vec<(i64, PathPattern)>>;
+
+lazy_static! {
+    static ref CACHE: Mutex<Option<(AliasVersion, AliasList)>> = Mutex::new(None);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AliasKind {
+    Glob = 0,
+    Regex = 1,
+}
+
+impl TryFrom<i64> for AliasKind {
+    type Error = anyhow::Error;
+
+    fn try_from(value: i64) -> Result<Self> {
+        match value {
+            0 => Ok(AliasKind::Glob),
+            1 => Ok(AliasKind::Regex),
+            _ => Err(anyhow!("invalid alias kind {}", value)),
+        }
+    }
+}
+
+pub struct PathPattern(Regex);
+
+impl PathPattern {
+    pub fn new(pattern: &str, kind: AliasKind) -> Result<PathPattern> {
+        let pattern = match kind {
+            AliasKind::Glob => glob_to_regex(pattern),
+            AliasKind::Regex => pattern.to_string(),
+        };
+        Ok(PathPattern(Regex::new(&format!("^(?:{})$", pattern))?))
+    }
+
+    /// A pattern matching only `path`.
+    pub fn exact(path: &str) -> String {
+        regex::escape(path)
+    }
+
+    pub fn is_match(&self, path: &str) -> bool {
+        self.0.is_match(path)
+    }
+}
+
+/// The aliases compiled for `version` of the alias table, if it is still the cached one.
+pub fn get_cached(version: AliasVersion) -> Option<AliasList> {
+    match &*CACHE.lock().unwrap() {
+        Some((cached, list)) if *cached == version => Some(list.clone()),
+        _ => None,
+    }
+}
+
+pub fn set_cached(version: AliasVersion, list: Vec<(i64, PathPattern)>) -> AliasList {
+    let list = Arc::new(list);
+    *CACHE.lock().unwrap() = Some((version, list.clone()));
+    list
+}
+
+/// Drops the compiled aliases, for writes the version may miss, e.g. an undo restoring an older
+/// `updated_at`.
+pub fn invalidate() {
+    *CACHE.lock().unwrap() = None;
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut result = String::new();
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                result.push_str(".*");
+            }
+            '*' => result.push_str(r"[^/\\]*"),
+            '?' => result.push_str(r"[^/\\]"),
+            _ => result.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob() {
+        let pattern = PathPattern::new(r"C:\Program Files\App-*\app.exe", AliasKind::Glob).unwrap();
+        assert!(pattern.is_match(r"C:\Program Files\App-1.2.0\app.exe"));
+        assert!(!pattern.is_match(r"C:\Program Files\App-1.2.0\bin\app.exe"));
+        assert!(!pattern.is_match(r"C:\Program Files\App-1.2.0\app.exe.bak"));
+
+        let pattern = PathPattern::new("/opt/**/app?", AliasKind::Glob).unwrap();
+        assert!(pattern.is_match("/opt/app/1.0/app2"));
+        assert!(!pattern.is_match("/opt/app/1.0/app"));
+    }
+
+    #[test]
+    fn test_regex() {
+        let pattern = PathPattern::new(r"/tmp/\.mount_App\w+/app", AliasKind::Regex).unwrap();
+        assert!(pattern.is_match("/tmp/.mount_AppX1b2/app"));
+        assert!(!pattern.is_match("/home/tmp/.mount_AppX1b2/app"));
+        assert!(PathPattern::new("(", AliasKind::Regex).is_err());
+
+        let pattern =
+            PathPattern::new(&PathPattern::exact("/usr/bin/a+b"), AliasKind::Regex).unwrap();
+        assert!(pattern.is_match("/usr/bin/a+b"));
+        assert!(!pattern.is_match("/usr/bin/aab"));
+    }
+}
@@ -0,0 +1,497 @@
+//! Splits time ranges into calendar buckets.
+//!
+//! Bucket edges are computed on the local wall clock of the given time zone, so a day is
+//! 23 or 25 hours long across daylight saving transitions and hours are never merged.
+
+use anyhow::{anyhow, Result};
+use chrono::{
+    DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike,
+    Weekday,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer};
+
+const HOUR: i64 = 60 * 60 * 1000;
+
+/// Bounds of a window size, smaller windows would cut a range into too many buckets.
+const MIN_WINDOW_SIZE: i64 = 60 * 1000;
+const MAX_WINDOW_SIZE: i64 = 366 * 24 * HOUR;
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum BucketUnit {
+    Hour,
+    Day,
+    /// ISO week, starting on Monday.
+    Week,
+    Month,
+    /// Fixed windows of `size` milliseconds from the start of the range.
+    Window {
+        #[serde(deserialize_with = "deserialize_window_size")]
+        size: i64,
+    },
+}
+
+fn deserialize_window_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    let size = i64::deserialize(deserializer)?;
+    if !(MIN_WINDOW_SIZE..=MAX_WINDOW_SIZE).contains(&size) {
+        return Err(serde::de::Error::custom(format!(
+            "window size must be between {} and {}",
+            MIN_WINDOW_SIZE, MAX_WINDOW_SIZE
+        )));
+    }
+    Ok(size)
+}
+
+/// Where days start, the time zone changes over time in travel mode.
+#[derive(Clone, Debug)]
+pub struct Clock {
+    /// Time zones by the time they took effect, the first one also applies before it.
+    zones: Vec<(i64, Tz)>,
+    day_start_hour: u32,
+}
+
+impl Clock {
+    pub fn new(tz: Tz, day_start_hour: u32) -> Clock {
+        Clock::with_zones(vec![(i64::MIN, tz)], day_start_hour)
+    }
+
+    pub fn with_zones(mut zones: Vec<(i64, Tz)>, day_start_hour: u32) -> Clock {
+        zones.sort_by_key(|(start, _)| *start);
+        if zones.is_empty() {
+            zones.push((i64::MIN, Tz::UTC));
+        }
+        Clock {
+            zones,
+            day_start_hour: day_start_hour.min(23),
+        }
+    }
+
+    pub fn tz_at(&self, timestamp: i64) -> Tz {
+        let index = self
+            .zones
+            .partition_point(|(start, _)| *start <= timestamp)
+            .max(1);
+        self.zones[index - 1].1
+    }
+
+    /// Date of the day containing `timestamp`, days starting at the day start hour.
+    pub fn format_date(&self, timestamp: i64) -> Result<String> {
+        let time = to_datetime(&self.tz_at(timestamp), timestamp)?;
+        Ok(shift(&time, self.day_start_hour)
+            .date()
+            .format("%Y-%m-%d")
+            .to_string())
+    }
+
+    /// Start of the bucket containing `timestamp`, before it is clipped to a range.
+    pub fn bucket_start(&self, unit: BucketUnit, timestamp: i64) -> Result<i64> {
+        let tz = self.tz_at(timestamp);
+        let time = to_datetime(&tz, timestamp)?;
+        let date = shift(&time, self.day_start_hour).date();
+        let start_date = match unit {
+            BucketUnit::Hour => return Ok(timestamp - elapsed_in_hour(&time)),
+            BucketUnit::Window { .. } => return Ok(timestamp),
+            BucketUnit::Day => date,
+            BucketUnit::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            BucketUnit::Month => start_of_month(date),
+        };
+        resolve(&tz, self.day_start(start_date))
+    }
+
+    /// Edges of the buckets covering `range`, the first and last edges are clipped to the range.
+    ///
+    /// A bucket is also cut where the time zone changes.
+    pub fn edges(&self, unit: BucketUnit, range: (i64, i64)) -> Result<Vec<i64>> {
+        let (start, end) = range;
+        let mut edges = vec![start];
+        let mut current = start;
+        while current < end {
+            current = self.edge_after(unit, start, current)?.min(end);
+            edges.push(current);
+        }
+        Ok(edges)
+    }
+
+    /// The same local time `days` days later, in the time zone at `timestamp`.
+    pub fn add_days(&self, timestamp: i64, days: i64) -> Result<i64> {
+        let tz = self.tz_at(timestamp);
+        let time = to_datetime(&tz, timestamp)?.naive_local();
+        resolve(&tz, time + Duration::days(days))
+    }
+
+    /// Local day of the week at `timestamp`, regardless of the day start hour.
+    pub fn weekday(&self, timestamp: i64) -> Result<Weekday> {
+        Ok(to_datetime(&self.tz_at(timestamp), timestamp)?.weekday())
+    }
+
+    /// End of the bucket containing `timestamp`, or the next time zone change before it.
+    pub fn bucket_end(&self, unit: BucketUnit, timestamp: i64) -> Result<i64> {
+        self.edge_after(unit, timestamp, timestamp)
+    }
+
+    /// Identifies the days this clock cuts, two clocks with the same key cut the same days.
+    pub fn key(&self) -> String {
+        let zones = self
+            .zones
+            .iter()
+            .map(|(start, tz)| format!("{}={}", start, tz.name()))
+            .collect::<Vec<_>>();
+        format!("{};{}", self.day_start_hour, zones.join(","))
+    }
+
+    fn edge_after(&self, unit: BucketUnit, origin: i64, timestamp: i64) -> Result<i64> {
+        let zone_end = self
+            .zones
+            .iter()
+            .map(|(start, _)| *start)
+            .find(|start| *start > timestamp)
+            .unwrap_or(i64::MAX);
+        Ok(self
+            .next_edge(&self.tz_at(timestamp), unit, origin, timestamp)?
+            .min(zone_end))
+    }
+
+    fn day_start(&self, date: NaiveDate) -> NaiveDateTime {
+        date.and_time(NaiveTime::from_hms_opt(self.day_start_hour, 0, 0).unwrap())
+    }
+
+    fn next_edge(&self, tz: &Tz, unit: BucketUnit, origin: i64, timestamp: i64) -> Result<i64> {
+        let time = to_datetime(tz, timestamp)?;
+        let date = shift(&time, self.day_start_hour).date();
+        let next_date = match unit {
+            // local hours are whole hours of absolute time, even when the offset changes
+            BucketUnit::Hour => return Ok(timestamp - elapsed_in_hour(&time) + HOUR),
+            BucketUnit::Window { size } => {
+                if size <= 0 {
+                    return Err(anyhow!("window size must be positive"));
+                }
+                return Ok(origin + ((timestamp - origin).div_euclid(size) + 1) * size);
+            }
+            BucketUnit::Day => date.succ_opt(),
+            BucketUnit::Week => date.checked_add_signed(Duration::days(
+                7 - date.weekday().num_days_from_monday() as i64,
+            )),
+            BucketUnit::Month => start_of_month(date).checked_add_months(Months::new(1)),
+        }
+        .ok_or(anyhow!("invalid timestamp {}", timestamp))?;
+        resolve(tz, self.day_start(next_date))
+    }
+}
+
+/// Maps a local time to the first instant at or after it, skipping gaps of daylight saving time.
+pub fn resolve(tz: &Tz, time: NaiveDateTime) -> Result<i64> {
+    (0..=16)
+        .find_map(|i| {
+            tz.from_local_datetime(&(time + Duration::minutes(15 * i)))
+                .earliest()
+        })
+        .map(|time| time.timestamp_millis())
+        .ok_or(anyhow!("invalid local time {}", time))
+}
+
+fn to_datetime(tz: &Tz, timestamp: i64) -> Result<DateTime<Tz>> {
+    tz.timestamp_millis_opt(timestamp)
+        .single()
+        .ok_or(anyhow!("invalid timestamp {}", timestamp))
+}
+
+/// Local time moved back by the day start hour, so its date is the date of the day it belongs to.
+fn shift(time: &DateTime<Tz>, day_start_hour: u32) -> NaiveDateTime {
+    time.naive_local() - Duration::hours(day_start_hour as i64)
+}
+
+fn elapsed_in_hour(time: &DateTime<Tz>) -> i64 {
+    (time.minute() * 60 + time.second()) as i64 * 1000 + time.timestamp_subsec_millis() as i64
+}
+
+fn start_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap()
+}
+
+/// Clips the span to the edges and splits it into `(bucket index, start, end)` parts.
+pub fn split(span: (i64, i64), edges: &[i64]) -> Vec<(usize, i64, i64)> {
+    let (Some(&first), Some(&last)) = (edges.first(), edges.last()) else {
+        return vec![];
+    };
+    let start = span.0.max(first);
+    let end = span.1.min(last);
+    if start >= end {
+        return vec![];
+    }
+    let mut index = edges.partition_point(|&edge| edge <= start) - 1;
+    let mut current = start;
+    let mut result = vec![];
+    while current < end {
+        let next = edges[index + 1].min(end);
+        result.push((index, current, next));
+        current = next;
+        index += 1;
+    }
+    result
+}
+
+/// Total length covered by the spans, overlapping parts are counted once.
+pub fn union_length(spans: Vec<(i64, i64)>) -> i64 {
+    merge(spans).iter().map(|(start, end)| end - start).sum()
+}
+
+/// Joins overlapping and touching spans, the result is ordered by start.
+pub fn merge(mut spans: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    spans.sort_unstable();
+    let mut merged: Vec<(i64, i64)> = vec![];
+    for (start, end) in spans {
+        match merged.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = (*last_end).max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Length of the spans within the range, the spans must not overlap.
+pub fn overlap_length(spans: &[(i64, i64)], range: (i64, i64)) -> i64 {
+    spans
+        .iter()
+        .map(|(start, end)| (end.min(&range.1) - start.max(&range.0)).max(0))
+        .sum()
+}
+
+/// Parts of the spans not covered by `cut`, both ordered by start without overlaps.
+pub fn subtract(spans: &[(i64, i64)], cut: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let mut result = vec![];
+    for &(start, end) in spans {
+        let mut current = start;
+        for &(cut_start, cut_end) in cut {
+            if cut_end <= current || cut_start >= end {
+                continue;
+            }
+            if cut_start > current {
+                result.push((current, cut_start));
+            }
+            current = current.max(cut_end);
+        }
+        if current < end {
+            result.push((current, end));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::{America::New_York, Asia::Shanghai};
+
+    use super::*;
+
+    fn time(datetime: &str) -> i64 {
+        DateTime::parse_from_rfc3339(datetime)
+            .unwrap()
+            .timestamp_millis()
+    }
+
+    fn clock() -> Clock {
+        Clock::new(Shanghai, 0)
+    }
+
+    #[test]
+    fn test_edges_by_day() {
+        let range = (
+            time("2024-08-01T22:00:00+08:00"),
+            time("2024-08-03T02:00:00+08:00"),
+        );
+        assert_eq!(
+            clock().edges(BucketUnit::Day, range).unwrap(),
+            vec![
+                time("2024-08-01T22:00:00+08:00"),
+                time("2024-08-02T00:00:00+08:00"),
+                time("2024-08-03T00:00:00+08:00"),
+                time("2024-08-03T02:00:00+08:00"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_edges_by_week_and_month() {
+        // 2024-08-01 is a Thursday
+        let range = (
+            time("2024-08-01T00:00:00+08:00"),
+            time("2024-09-10T00:00:00+08:00"),
+        );
+        let weeks = clock().edges(BucketUnit::Week, range).unwrap();
+        assert_eq!(weeks[1], time("2024-08-05T00:00:00+08:00"));
+        assert_eq!(weeks[2], time("2024-08-12T00:00:00+08:00"));
+        assert_eq!(
+            clock().edges(BucketUnit::Month, range).unwrap(),
+            vec![
+                time("2024-08-01T00:00:00+08:00"),
+                time("2024-09-01T00:00:00+08:00"),
+                time("2024-09-10T00:00:00+08:00"),
+            ]
+        );
+        assert_eq!(
+            clock()
+                .bucket_start(BucketUnit::Week, time("2024-08-01T12:00:00+08:00"))
+                .unwrap(),
+            time("2024-07-29T00:00:00+08:00")
+        );
+    }
+
+    #[test]
+    fn test_edges_by_hour_and_window() {
+        let range = (
+            time("2024-08-01T10:30:00+08:00"),
+            time("2024-08-01T12:15:00+08:00"),
+        );
+        assert_eq!(
+            clock().edges(BucketUnit::Hour, range).unwrap(),
+            vec![
+                time("2024-08-01T10:30:00+08:00"),
+                time("2024-08-01T11:00:00+08:00"),
+                time("2024-08-01T12:00:00+08:00"),
+                time("2024-08-01T12:15:00+08:00"),
+            ]
+        );
+        let size = 45 * 60 * 1000;
+        assert_eq!(
+            clock().edges(BucketUnit::Window { size }, range).unwrap(),
+            vec![range.0, range.0 + size, range.0 + size * 2, range.1]
+        );
+    }
+
+    #[test]
+    fn test_edges_across_daylight_saving_time() {
+        let clock = Clock::new(New_York, 0);
+        // clocks go back from 02:00 to 01:00
+        let range = (
+            time("2024-11-03T00:00:00-04:00"),
+            time("2024-11-04T00:00:00-05:00"),
+        );
+        assert_eq!(
+            clock.edges(BucketUnit::Day, range).unwrap(),
+            vec![range.0, range.1]
+        );
+        assert_eq!(clock.edges(BucketUnit::Hour, range).unwrap().len(), 26);
+        // clocks go forward from 02:00 to 03:00
+        let range = (
+            time("2024-03-10T00:00:00-05:00"),
+            time("2024-03-11T00:00:00-04:00"),
+        );
+        assert_eq!(
+            clock.edges(BucketUnit::Day, range).unwrap(),
+            vec![range.0, range.1]
+        );
+        assert_eq!(range.1 - range.0, 23 * HOUR);
+    }
+
+    #[test]
+    fn test_day_start_hour() {
+        let clock = Clock::new(Shanghai, 4);
+        assert_eq!(
+            clock
+                .format_date(time("2024-08-02T01:00:00+08:00"))
+                .unwrap(),
+            "2024-08-01"
+        );
+        assert_eq!(
+            clock
+                .format_date(time("2024-08-02T04:00:00+08:00"))
+                .unwrap(),
+            "2024-08-02"
+        );
+        let range = (
+            time("2024-08-01T00:00:00+08:00"),
+            time("2024-08-02T12:00:00+08:00"),
+        );
+        assert_eq!(
+            clock.edges(BucketUnit::Day, range).unwrap(),
+            vec![
+                range.0,
+                time("2024-08-01T04:00:00+08:00"),
+                time("2024-08-02T04:00:00+08:00"),
+                range.1,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_travel_zones() {
+        let change = time("2024-08-02T12:00:00+08:00");
+        let clock = Clock::with_zones(vec![(0, Shanghai), (change, New_York)], 0);
+        assert_eq!(clock.tz_at(-1), Shanghai);
+        assert_eq!(clock.tz_at(change), New_York);
+        let range = (
+            time("2024-08-02T00:00:00+08:00"),
+            time("2024-08-03T00:00:00-04:00"),
+        );
+        assert_eq!(
+            clock.edges(BucketUnit::Day, range).unwrap(),
+            vec![range.0, change, range.1]
+        );
+        // both parts of the day of the change belong to 2024-08-02
+        assert_eq!(clock.format_date(range.0).unwrap(), "2024-08-02");
+        assert_eq!(clock.format_date(change).unwrap(), "2024-08-02");
+        assert_eq!(clock.bucket_end(BucketUnit::Day, range.0).unwrap(), change);
+        assert_eq!(clock.bucket_end(BucketUnit::Day, change).unwrap(), range.1);
+    }
+
+    #[test]
+    fn test_split() {
+        let edges = vec![0, 10, 20, 30];
+        assert_eq!(split((-5, 15), &edges), vec![(0, 0, 10), (1, 10, 15)]);
+        assert_eq!(split((20, 40), &edges), vec![(2, 20, 30)]);
+        assert_eq!(split((30, 40), &edges), vec![]);
+        assert_eq!(split((12, 12), &edges), vec![]);
+    }
+
+    #[test]
+    fn test_window_size_bounds() {
+        let parse = |size: i64| {
+            serde_json::from_value::<BucketUnit>(
+                serde_json::json!({ "type": "window", "size": size }),
+            )
+        };
+        assert_eq!(parse(HOUR).unwrap(), BucketUnit::Window { size: HOUR });
+        assert!(parse(0).is_err());
+        assert!(parse(-HOUR).is_err());
+        assert!(parse(i64::MAX).is_err());
+    }
+
+    #[test]
+    fn test_union_length() {
+        assert_eq!(union_length(vec![]), 0);
+        assert_eq!(union_length(vec![(0, 10), (20, 30)]), 20);
+        assert_eq!(union_length(vec![(20, 30), (0, 10), (5, 25)]), 30);
+        assert_eq!(union_length(vec![(0, 30), (10, 20)]), 30);
+        assert_eq!(union_length(vec![(0, 10), (10, 20)]), 20);
+    }
+
+    #[test]
+    fn test_merge() {
+        assert_eq!(
+            merge(vec![(20, 30), (0, 10), (5, 12), (30, 40)]),
+            vec![(0, 12), (20, 40)]
+        );
+    }
+
+    #[test]
+    fn test_add_days_across_daylight_saving_time() {
+        let clock = Clock::new(New_York, 0);
+        let start = time("2024-11-02T09:00:00-04:00");
+        assert_eq!(
+            clock.add_days(start, 2).unwrap(),
+            time("2024-11-04T09:00:00-05:00")
+        );
+        assert_eq!(clock.weekday(start).unwrap(), Weekday::Sat);
+    }
+
+    #[test]
+    fn test_overlap_and_subtract() {
+        let spans = vec![(0, 10), (20, 30), (40, 50)];
+        assert_eq!(overlap_length(&spans, (5, 45)), 5 + 10 + 5);
+        assert_eq!(
+            subtract(&spans, &[(5, 8), (25, 45)]),
+            vec![(0, 5), (8, 10), (20, 25), (45, 50)]
+        );
+    }
+}
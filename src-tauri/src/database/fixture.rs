@@ -0,0 +1,43 @@
+//! In-memory database with the migrations applied, for tests that need real queries.
+
+use std::{fs, path::PathBuf};
+
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection};
+
+const MIGRATION_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../prisma/migrations");
+
+pub async fn setup() -> DatabaseConnection {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    migrate(&db, |_| true).await;
+    db
+}
+
+/// Applies the migrations older than `name`, to fill rows the way a previous version did.
+pub async fn setup_before(name: &str) -> DatabaseConnection {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    migrate(&db, |dir| dir < name).await;
+    db
+}
+
+/// Applies `name` and the migrations after it.
+pub async fn migrate_from(db: &DatabaseConnection, name: &str) {
+    migrate(db, |dir| dir >= name).await;
+}
+
+/// Runs the migrations the way the sql plugin does, which wraps each in its own transaction.
+async fn migrate(db: &DatabaseConnection, filter: impl Fn(&str) -> bool) {
+    let mut dir_list: Vec<PathBuf> = fs::read_dir(MIGRATION_DIR)
+        .unwrap()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir())
+        .filter(|path| filter(&path.file_name().unwrap().to_string_lossy()))
+        .collect();
+    dir_list.sort();
+    for dir in dir_list {
+        let sql = fs::read_to_string(dir.join("migration.sql"))
+            .unwrap()
+            .replace("BEGIN TRANSACTION;", "")
+            .replace("COMMIT TRANSACTION;", "");
+        db.execute_unprepared(&sql).await.unwrap();
+    }
+}
@@ -0,0 +1,687 @@
+//! An append-only journal of the rows written by the services, to browse, undo and redo them.
+//!
+//! Each change keeps the rows it wrote as JSON, before and after the write. Undo writes the rows
+//! back as they were before, a created row is removed instead. Undo and redo are changes of their
+//! own pointing to the change they replay, the stacks are rebuilt from the journal in order.
+//!
+//! Writes moving many rows from one id to another, e.g. the activities of a merged program, keep
+//! the ids of the rows instead, the remap is written back in reverse on undo.
+//!
+//! Edits, remarks and the programs created on first sight are recorded. Activities from the
+//! watchers, the end of a running note and the notes materialized from templates are tracking
+//! data rather than edits and are written as before.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::anyhow;
+use chrono::Utc;
+use sea_orm::{
+    prelude::Expr, sea_query::Alias, ActiveModelBehavior, ActiveModelTrait, ConnectionTrait,
+    DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter, QuerySelect, TransactionTrait,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use tauri_plugin_shion_sql::Result;
+
+use super::{
+    alias,
+    models::{change, prelude::*, r#box},
+    sql::{self, InsertChangeEntry},
+};
+
+const UNDO: &str = "undo";
+const REDO: &str = "redo";
+
+/// The tables written by the services.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum JournalTable {
+    Activity,
+    Box,
+    DimensionProgram,
+    Label,
+    Moment,
+    Note,
+    NoteTemplate,
+    NoteTemplateException,
+    Program,
+    ProgramAlias,
+    Remark,
+    TimeBlock,
+}
+
+impl JournalTable {
+    pub fn name(self) -> &'static str {
+        match self {
+            JournalTable::Activity => "activity",
+            JournalTable::Box => "box",
+            JournalTable::DimensionProgram => "dimension_program",
+            JournalTable::Label => "label",
+            JournalTable::Moment => "moment",
+            JournalTable::Note => "note",
+            JournalTable::NoteTemplate => "note_template",
+            JournalTable::NoteTemplateException => "note_template_exception",
+            JournalTable::Program => "program",
+            JournalTable::ProgramAlias => "program_alias",
+            JournalTable::Remark => "remark",
+            JournalTable::TimeBlock => "time_block",
+        }
+    }
+
+    /// Rows whose `column` is one of `id_list`, by id.
+    async fn select(
+        self,
+        db: &impl ConnectionTrait,
+        column: &str,
+        id_list: Vec<i64>,
+    ) -> Result<Vec<(i64, Value)>> {
+        match self {
+            JournalTable::Activity => select_row_list::<Activity>(db, column, id_list).await,
+            JournalTable::Box => select_row_list::<r#box::Entity>(db, column, id_list).await,
+            JournalTable::DimensionProgram => {
+                select_row_list::<DimensionProgram>(db, column, id_list).await
+            }
+            JournalTable::Label => select_row_list::<Label>(db, column, id_list).await,
+            JournalTable::Moment => select_row_list::<Moment>(db, column, id_list).await,
+            JournalTable::Note => select_row_list::<Note>(db, column, id_list).await,
+            JournalTable::NoteTemplate => {
+                select_row_list::<NoteTemplate>(db, column, id_list).await
+            }
+            JournalTable::NoteTemplateException => {
+                select_row_list::<NoteTemplateException>(db, column, id_list).await
+            }
+            JournalTable::Program => select_row_list::<Program>(db, column, id_list).await,
+            JournalTable::ProgramAlias => {
+                select_row_list::<ProgramAlias>(db, column, id_list).await
+            }
+            JournalTable::Remark => select_row_list::<Remark>(db, column, id_list).await,
+            JournalTable::TimeBlock => select_row_list::<TimeBlock>(db, column, id_list).await,
+        }
+    }
+
+    /// Ids of the rows whose `column` is `value`.
+    async fn select_id(
+        self,
+        db: &impl ConnectionTrait,
+        column: &str,
+        value: i64,
+    ) -> Result<Vec<i64>> {
+        match self {
+            JournalTable::Activity => select_id_list::<Activity>(db, column, value).await,
+            JournalTable::Box => select_id_list::<r#box::Entity>(db, column, value).await,
+            JournalTable::DimensionProgram => {
+                select_id_list::<DimensionProgram>(db, column, value).await
+            }
+            JournalTable::Label => select_id_list::<Label>(db, column, value).await,
+            JournalTable::Moment => select_id_list::<Moment>(db, column, value).await,
+            JournalTable::Note => select_id_list::<Note>(db, column, value).await,
+            JournalTable::NoteTemplate => select_id_list::<NoteTemplate>(db, column, value).await,
+            JournalTable::NoteTemplateException => {
+                select_id_list::<NoteTemplateException>(db, column, value).await
+            }
+            JournalTable::Program => select_id_list::<Program>(db, column, value).await,
+            JournalTable::ProgramAlias => select_id_list::<ProgramAlias>(db, column, value).await,
+            JournalTable::Remark => select_id_list::<Remark>(db, column, value).await,
+            JournalTable::TimeBlock => select_id_list::<TimeBlock>(db, column, value).await,
+        }
+    }
+
+    /// Points `column` of the rows from `from` to `to`, returns how many rows still pointed to
+    /// `from`.
+    async fn remap(
+        self,
+        db: &impl ConnectionTrait,
+        column: &str,
+        from: i64,
+        to: i64,
+        id_list: Vec<i64>,
+    ) -> Result<u64> {
+        match self {
+            JournalTable::Activity => {
+                remap_row_list::<Activity>(db, column, from, to, id_list).await
+            }
+            JournalTable::Box => {
+                remap_row_list::<r#box::Entity>(db, column, from, to, id_list).await
+            }
+            JournalTable::DimensionProgram => {
+                remap_row_list::<DimensionProgram>(db, column, from, to, id_list).await
+            }
+            JournalTable::Label => remap_row_list::<Label>(db, column, from, to, id_list).await,
+            JournalTable::Moment => remap_row_list::<Moment>(db, column, from, to, id_list).await,
+            JournalTable::Note => remap_row_list::<Note>(db, column, from, to, id_list).await,
+            JournalTable::NoteTemplate => {
+                remap_row_list::<NoteTemplate>(db, column, from, to, id_list).await
+            }
+            JournalTable::NoteTemplateException => {
+                remap_row_list::<NoteTemplateException>(db, column, from, to, id_list).await
+            }
+            JournalTable::Program => remap_row_list::<Program>(db, column, from, to, id_list).await,
+            JournalTable::ProgramAlias => {
+                remap_row_list::<ProgramAlias>(db, column, from, to, id_list).await
+            }
+            JournalTable::Remark => remap_row_list::<Remark>(db, column, from, to, id_list).await,
+            JournalTable::TimeBlock => {
+                remap_row_list::<TimeBlock>(db, column, from, to, id_list).await
+            }
+        }
+    }
+
+    async fn write(self, db: &impl ConnectionTrait, row: Value) -> Result<()> {
+        match self {
+            JournalTable::Activity => write_row::<Activity>(db, row).await,
+            JournalTable::Box => write_row::<r#box::Entity>(db, row).await,
+            JournalTable::DimensionProgram => write_row::<DimensionProgram>(db, row).await,
+            JournalTable::Label => write_row::<Label>(db, row).await,
+            JournalTable::Moment => write_row::<Moment>(db, row).await,
+            JournalTable::Note => write_row::<Note>(db, row).await,
+            JournalTable::NoteTemplate => write_row::<NoteTemplate>(db, row).await,
+            JournalTable::NoteTemplateException => {
+                write_row::<NoteTemplateException>(db, row).await
+            }
+            JournalTable::Program => write_row::<Program>(db, row).await,
+            JournalTable::ProgramAlias => write_row::<ProgramAlias>(db, row).await,
+            JournalTable::Remark => write_row::<Remark>(db, row).await,
+            JournalTable::TimeBlock => write_row::<TimeBlock>(db, row).await,
+        }
+    }
+}
+
+impl TryFrom<&str> for JournalTable {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> anyhow::Result<Self> {
+        [
+            JournalTable::Activity,
+            JournalTable::Box,
+            JournalTable::DimensionProgram,
+            JournalTable::Label,
+            JournalTable::Moment,
+            JournalTable::Note,
+            JournalTable::NoteTemplate,
+            JournalTable::NoteTemplateException,
+            JournalTable::Program,
+            JournalTable::ProgramAlias,
+            JournalTable::Remark,
+            JournalTable::TimeBlock,
+        ]
+        .into_iter()
+        .find(|table| table.name() == value)
+        .ok_or(anyhow!("invalid journal table {}", value))
+    }
+}
+
+async fn select_row_list<E>(
+    db: &impl ConnectionTrait,
+    column: &str,
+    id_list: Vec<i64>,
+) -> Result<Vec<(i64, Value)>>
+where
+    E: EntityTrait,
+    E::Model: Serialize,
+{
+    E::find()
+        .filter(Expr::col(Alias::new(column)).is_in(id_list))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|model| {
+            let row = serde_json::to_value(model).map_err(anyhow::Error::from)?;
+            let id = row["id"].as_i64().ok_or(anyhow!("row without id"))?;
+            Ok((id, row))
+        })
+        .collect()
+}
+
+async fn select_id_list<E: EntityTrait>(
+    db: &impl ConnectionTrait,
+    column: &str,
+    value: i64,
+) -> Result<Vec<i64>> {
+    Ok(E::find()
+        .select_only()
+        .column_as(Expr::col(Alias::new("id")), "id")
+        .filter(Expr::col(Alias::new(column)).eq(value))
+        .into_tuple()
+        .all(db)
+        .await?)
+}
+
+async fn remap_row_list<E: EntityTrait>(
+    db: &impl ConnectionTrait,
+    column: &str,
+    from: i64,
+    to: i64,
+    id_list: Vec<i64>,
+) -> Result<u64> {
+    let result = E::update_many()
+        .col_expr(Alias::new(column), Expr::value(to))
+        .col_expr(
+            Alias::new("updated_at"),
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(Expr::col(Alias::new("id")).is_in(id_list))
+        .filter(Expr::col(Alias::new(column)).eq(from))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+async fn write_row<E>(db: &impl ConnectionTrait, mut row: Value) -> Result<()>
+where
+    E: EntityTrait,
+    E::Model: DeserializeOwned + IntoActiveModel<E::ActiveModel>,
+    E::ActiveModel: ActiveModelBehavior + Send,
+{
+    row["updated_at"] = Utc::now().timestamp_millis().into();
+    let model: E::Model = serde_json::from_value(row).map_err(anyhow::Error::from)?;
+    model.into_active_model().reset_all().update(db).await?;
+    Ok(())
+}
+
+/// Whether the rows only differ in `updated_at`.
+fn is_same_row(a: &Value, b: &Value) -> bool {
+    let strip = |row: &Value| {
+        let mut row = row.clone();
+        if let Some(map) = row.as_object_mut() {
+            map.remove("updated_at");
+        }
+        row
+    };
+    strip(a) == strip(b)
+}
+
+/// Collects the rows a service writes, to record them as one change once it is done.
+pub struct Journal {
+    action: String,
+    target_id: Option<i64>,
+    /// Rows as they were before the write, `None` for the created ones.
+    row_list: Vec<(JournalTable, i64, Option<Value>)>,
+    seen: HashSet<(JournalTable, i64)>,
+    remap_list: Vec<Remap>,
+}
+
+/// Rows whose `column` moved from one id to another.
+struct Remap {
+    table: JournalTable,
+    column: String,
+    from: i64,
+    to: i64,
+    id_list: Vec<i64>,
+}
+
+impl Journal {
+    pub fn new(action: &str) -> Journal {
+        Journal {
+            action: action.to_string(),
+            target_id: None,
+            row_list: vec![],
+            seen: HashSet::new(),
+            remap_list: vec![],
+        }
+    }
+
+    fn push(&mut self, table: JournalTable, id: i64, before: Option<Value>) {
+        // the first state seen is the one before the change
+        if self.seen.insert((table, id)) {
+            self.row_list.push((table, id, before));
+        }
+    }
+
+    /// Keeps the rows whose `column` is one of `id_list` before they are written.
+    pub async fn track(
+        &mut self,
+        db: &impl ConnectionTrait,
+        table: JournalTable,
+        column: &str,
+        id_list: Vec<i64>,
+    ) -> Result<()> {
+        for (id, row) in table.select(db, column, id_list).await? {
+            self.push(table, id, Some(row));
+        }
+        Ok(())
+    }
+
+    /// Keeps the ids of the rows whose `column` is about to move from one of `from_list` to
+    /// `to`, for writes moving more rows than are worth keeping whole.
+    pub async fn remap(
+        &mut self,
+        db: &impl ConnectionTrait,
+        table: JournalTable,
+        column: &str,
+        from_list: Vec<i64>,
+        to: i64,
+    ) -> Result<()> {
+        for from in from_list {
+            let id_list = table.select_id(db, column, from).await?;
+            self.remapped(table, column, from, to, id_list);
+        }
+        Ok(())
+    }
+
+    /// Keeps the ids of rows whose `column` was moved from `from` to `to` by the caller.
+    pub fn remapped(
+        &mut self,
+        table: JournalTable,
+        column: &str,
+        from: i64,
+        to: i64,
+        id_list: Vec<i64>,
+    ) {
+        if !id_list.is_empty() {
+            self.remap_list.push(Remap {
+                table,
+                column: column.to_string(),
+                from,
+                to,
+                id_list,
+            });
+        }
+    }
+
+    /// Marks the rows as created by the change.
+    pub fn created(&mut self, table: JournalTable, id_list: impl IntoIterator<Item = i64>) {
+        for id in id_list {
+            self.push(table, id, None);
+        }
+    }
+
+    /// Records the rows that differ from what they were, nothing when none of them does.
+    pub async fn commit(self, db: &impl ConnectionTrait) -> Result<Option<i64>> {
+        let mut id_map: HashMap<JournalTable, Vec<i64>> = HashMap::new();
+        for (table, id, _) in &self.row_list {
+            id_map.entry(*table).or_default().push(*id);
+        }
+        let mut after_map = HashMap::new();
+        for (table, id_list) in id_map {
+            for (id, row) in table.select(db, "id", id_list).await? {
+                after_map.insert((table, id), row);
+            }
+        }
+        let mut entry_list = vec![];
+        for (table, id, before) in self.row_list {
+            let after = after_map.remove(&(table, id));
+            if before == after {
+                continue;
+            }
+            entry_list.push(InsertChangeEntry {
+                table_name: table.name().to_string(),
+                row_id: id,
+                before: before.map(|row| row.to_string()),
+                after: after.map(|row| row.to_string()),
+                row_id_list: None,
+            });
+        }
+        for remap in self.remap_list {
+            entry_list.push(InsertChangeEntry {
+                table_name: remap.table.name().to_string(),
+                row_id: remap.from,
+                before: Some(json!({ remap.column.clone(): remap.from }).to_string()),
+                after: Some(json!({ remap.column: remap.to }).to_string()),
+                row_id_list: Some(json!(remap.id_list).to_string()),
+            });
+        }
+        if entry_list.is_empty() {
+            return Ok(None);
+        }
+        let id = sql::create_change(db, &self.action, self.target_id).await?;
+        sql::create_change_entry_list(db, id, entry_list).await?;
+        Ok(Some(id))
+    }
+}
+
+/// The changes that can be undone and redone, the next one last.
+fn get_change_stack(change_list: &[change::Model]) -> (Vec<i64>, Vec<i64>) {
+    let mut undo_list = vec![];
+    let mut redo_list = vec![];
+    for change in change_list {
+        match (change.action.as_str(), change.target_id) {
+            (UNDO, Some(target_id)) => {
+                undo_list.retain(|id| *id != target_id);
+                redo_list.push(target_id);
+            }
+            (REDO, Some(target_id)) => {
+                redo_list.retain(|id| *id != target_id);
+                undo_list.push(target_id);
+            }
+            _ => {
+                undo_list.push(change.id);
+                redo_list.clear();
+            }
+        }
+    }
+    (undo_list, redo_list)
+}
+
+fn parse_row(row: &Option<String>) -> Result<Option<Value>> {
+    Ok(row
+        .as_deref()
+        .map(serde_json::from_str)
+        .transpose()
+        .map_err(anyhow::Error::from)?)
+}
+
+/// The column of a remap entry with the ids it points from and to.
+fn parse_remap(from: Option<Value>, to: Option<Value>) -> Result<(String, i64, i64)> {
+    let read = |row: Option<Value>| {
+        let row = row?;
+        let (column, id) = row.as_object()?.iter().next()?;
+        Some((column.clone(), id.as_i64()?))
+    };
+    match (read(from), read(to)) {
+        (Some((column, from)), Some((_, to))) => Ok((column, from, to)),
+        _ => Err(anyhow!("invalid remap entry").into()),
+    }
+}
+
+/// Writes the rows of the last change back as they were before it, returns the change undone.
+///
+/// Fails when a row was edited since, a created row is removed regardless.
+pub async fn undo_change(db: &DatabaseConnection) -> Result<Option<i64>> {
+    replay_change(db, true).await
+}
+
+/// Writes the rows of the last undone change again, returns the change redone.
+pub async fn redo_change(db: &DatabaseConnection) -> Result<Option<i64>> {
+    replay_change(db, false).await
+}
+
+async fn replay_change(db: &DatabaseConnection, undo: bool) -> Result<Option<i64>> {
+    let txn = db.begin().await?;
+    let (undo_list, redo_list) = get_change_stack(&sql::select_change_list(&txn).await?);
+    let Some(&target_id) = (if undo {
+        undo_list.last()
+    } else {
+        redo_list.last()
+    }) else {
+        return Ok(None);
+    };
+    let mut entry_list = sql::select_change_entry_list(&txn, vec![target_id]).await?;
+    if undo {
+        entry_list.reverse();
+    }
+    let mut journal = Journal::new(if undo { UNDO } else { REDO });
+    journal.target_id = Some(target_id);
+    let now = Utc::now().timestamp_millis();
+    for entry in entry_list {
+        let table = JournalTable::try_from(entry.table_name.as_str())?;
+        let before = parse_row(&entry.before)?;
+        let after = parse_row(&entry.after)?;
+        let (expected, row) = if undo {
+            (after, before)
+        } else {
+            (before, after)
+        };
+        if let Some(id_list) = parse_row(&entry.row_id_list)? {
+            let id_list: Vec<i64> = serde_json::from_value(id_list).map_err(anyhow::Error::from)?;
+            let (column, from, to) = parse_remap(expected, row)?;
+            if table
+                .remap(&txn, &column, from, to, id_list.clone())
+                .await?
+                != id_list.len() as u64
+            {
+                return Err(anyhow!(
+                    "{} rows were edited since change {}",
+                    table.name(),
+                    target_id
+                )
+                .into());
+            }
+            journal.remap_list.push(Remap {
+                table,
+                column,
+                from,
+                to,
+                id_list,
+            });
+            continue;
+        }
+        let current = table
+            .select(&txn, "id", vec![entry.row_id])
+            .await?
+            .pop()
+            .map(|(_, row)| row)
+            .ok_or(anyhow!("{} {} not found", table.name(), entry.row_id))?;
+        let row = match (row, expected) {
+            (Some(row), Some(expected)) => {
+                if !is_same_row(&current, &expected) {
+                    return Err(anyhow!(
+                        "{} {} was edited since change {}",
+                        table.name(),
+                        entry.row_id,
+                        target_id
+                    )
+                    .into());
+                }
+                row
+            }
+            // the row was restored by an undo, it is written again as created
+            (Some(row), None) => row,
+            (None, _) => {
+                let mut row = current.clone();
+                row["deleted_at"] = now.into();
+                row
+            }
+        };
+        journal.push(table, entry.row_id, Some(current));
+        table.write(&txn, row).await?;
+    }
+    journal.commit(&txn).await?;
+    txn.commit().await?;
+    alias::invalidate();
+    Ok(Some(target_id))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeEntryResult {
+    table_name: String,
+    row_id: i64,
+    before: Option<Value>,
+    after: Option<Value>,
+    /// Ids of the rows moved by a remap.
+    row_id_list: Option<Value>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeResult {
+    id: i64,
+    action: String,
+    /// The change replayed by an undo or redo.
+    target_id: Option<i64>,
+    /// Whether the change is undone now.
+    undone: bool,
+    created_at: i64,
+    entry_list: Vec<ChangeEntryResult>,
+}
+
+/// The changes made in the range, the latest first.
+pub async fn get_change_list(
+    db: &DatabaseConnection,
+    start: i64,
+    end: i64,
+) -> Result<Vec<ChangeResult>> {
+    let change_list = sql::select_change_list(db).await?;
+    let (_, redo_list) = get_change_stack(&change_list);
+    let change_list = change_list
+        .into_iter()
+        .filter(|change| change.created_at >= start && change.created_at < end)
+        .collect::<Vec<_>>();
+    let mut entry_map: HashMap<i64, Vec<ChangeEntryResult>> = HashMap::new();
+    for entry in
+        sql::select_change_entry_list(db, change_list.iter().map(|change| change.id).collect())
+            .await?
+    {
+        entry_map
+            .entry(entry.change_id)
+            .or_default()
+            .push(ChangeEntryResult {
+                before: parse_row(&entry.before)?,
+                after: parse_row(&entry.after)?,
+                row_id_list: parse_row(&entry.row_id_list)?,
+                table_name: entry.table_name,
+                row_id: entry.row_id,
+            });
+    }
+    Ok(change_list
+        .into_iter()
+        .rev()
+        .map(|change| ChangeResult {
+            undone: redo_list.contains(&change.id),
+            entry_list: entry_map.remove(&change.id).unwrap_or_default(),
+            id: change.id,
+            action: change.action,
+            target_id: change.target_id,
+            created_at: change.created_at,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(id: i64, action: &str, target_id: Option<i64>) -> change::Model {
+        change::Model {
+            id,
+            action: action.to_string(),
+            target_id,
+            deleted_at: 0,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_get_change_stack() {
+        assert_eq!(get_change_stack(&[]), (vec![], vec![]));
+
+        let list = vec![
+            change(1, "create note", None),
+            change(2, "update note", None),
+            change(3, UNDO, Some(2)),
+        ];
+        assert_eq!(get_change_stack(&list), (vec![1], vec![2]));
+
+        let mut redone = list.clone();
+        redone.push(change(4, REDO, Some(2)));
+        assert_eq!(get_change_stack(&redone), (vec![1, 2], vec![]));
+
+        // a new change drops what was undone
+        let mut branched = list.clone();
+        branched.push(change(4, "move label", None));
+        assert_eq!(get_change_stack(&branched), (vec![1, 4], vec![]));
+    }
+
+    #[test]
+    fn test_is_same_row() {
+        let row: Value = serde_json::json!({ "id": 1, "end": 2, "updated_at": 3 });
+        assert!(is_same_row(
+            &row,
+            &serde_json::json!({ "id": 1, "end": 2, "updated_at": 4 })
+        ));
+        assert!(!is_same_row(
+            &row,
+            &serde_json::json!({ "id": 1, "end": 5, "updated_at": 3 })
+        ));
+    }
+}
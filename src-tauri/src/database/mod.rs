@@ -1,3 +1,9 @@
+pub mod alias;
+pub mod bucket;
+#[cfg(test)]
+pub mod fixture;
+pub mod journal;
 pub mod models;
+pub mod rrule;
 pub mod service;
 pub mod sql;
@@ -1,8 +1,9 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0-rc.1
 
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "label")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -13,6 +14,7 @@ pub struct Model {
     pub color: String,
     pub sort: i64,
     pub plan_id: i64,
+    pub parent_id: Option<i64>,
     pub hidden: bool,
     pub deleted_at: i64,
     pub created_at: i64,
@@ -21,10 +23,14 @@ pub struct Model {
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::daily_summary::Entity")]
+    DailySummary,
     #[sea_orm(has_many = "super::dimension_label::Entity")]
     DimensionLabel,
     #[sea_orm(has_many = "super::note::Entity")]
     Note,
+    #[sea_orm(has_many = "super::note_template::Entity")]
+    NoteTemplate,
     #[sea_orm(
         belongs_to = "super::plan::Entity",
         from = "Column::PlanId",
@@ -33,6 +39,14 @@ pub enum Relation {
         on_delete = "Restrict"
     )]
     Plan,
+    #[sea_orm(has_many = "super::time_block::Entity")]
+    TimeBlock,
+}
+
+impl Related<super::daily_summary::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DailySummary.def()
+    }
 }
 
 impl Related<super::dimension_label::Entity> for Entity {
@@ -47,10 +61,22 @@ impl Related<super::note::Entity> for Entity {
     }
 }
 
+impl Related<super::note_template::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::NoteTemplate.def()
+    }
+}
+
 impl Related<super::plan::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Plan.def()
     }
 }
 
+impl Related<super::time_block::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TimeBlock.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -4,16 +4,28 @@ pub mod prelude;
 
 pub mod activity;
 pub mod r#box;
+pub mod change;
+pub mod change_entry;
+pub mod daily_summary;
+pub mod daily_summary_dirty;
 pub mod dimension;
 pub mod dimension_label;
 pub mod dimension_program;
 pub mod domain;
+pub mod goal;
 pub mod history;
 pub mod label;
 pub mod link;
 pub mod moment;
 pub mod note;
+pub mod note_template;
+pub mod note_template_exception;
 pub mod overview;
 pub mod plan;
 pub mod program;
+pub mod program_alias;
 pub mod remark;
+pub mod time_block;
+pub mod timezone_log;
+pub mod usage_limit;
+pub mod usage_limit_snooze;
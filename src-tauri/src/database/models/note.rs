@@ -1,8 +1,9 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0-rc.1
 
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "note")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -11,6 +12,10 @@ pub struct Model {
     pub end: i64,
     pub plan_id: i64,
     pub label_id: i64,
+    #[sea_orm(column_type = "Text")]
+    pub title: String,
+    pub note_template_id: Option<i64>,
+    pub occurrence: Option<i64>,
     pub deleted_at: i64,
     pub created_at: i64,
     pub updated_at: i64,
@@ -1,17 +1,29 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0-rc.1
 
 pub use super::activity::Entity as Activity;
-// pub use super::dimension::Entity as Dimension;
+pub use super::change::Entity as Change;
+pub use super::change_entry::Entity as ChangeEntry;
+pub use super::daily_summary::Entity as DailySummary;
+pub use super::daily_summary_dirty::Entity as DailySummaryDirty;
+pub use super::dimension::Entity as Dimension;
 // pub use super::dimension_label::Entity as DimensionLabel;
-// pub use super::dimension_program::Entity as DimensionProgram;
+pub use super::dimension_program::Entity as DimensionProgram;
 // pub use super::domain::Entity as Domain;
+pub use super::goal::Entity as Goal;
 // pub use super::history::Entity as History;
-// pub use super::label::Entity as Label;
+pub use super::label::Entity as Label;
 // pub use super::link::Entity as Link;
-// pub use super::moment::Entity as Moment;
+pub use super::moment::Entity as Moment;
 pub use super::note::Entity as Note;
+pub use super::note_template::Entity as NoteTemplate;
+pub use super::note_template_exception::Entity as NoteTemplateException;
 // pub use super::overview::Entity as Overview;
-// pub use super::plan::Entity as Plan;
+pub use super::plan::Entity as Plan;
 pub use super::program::Entity as Program;
+pub use super::program_alias::Entity as ProgramAlias;
 // pub use super::r#box::Entity as Box;
-// pub use super::remark::Entity as Remark;
+pub use super::remark::Entity as Remark;
+pub use super::time_block::Entity as TimeBlock;
+pub use super::timezone_log::Entity as TimezoneLog;
+pub use super::usage_limit::Entity as UsageLimit;
+pub use super::usage_limit_snooze::Entity as UsageLimitSnooze;
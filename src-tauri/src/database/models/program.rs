@@ -1,8 +1,9 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0-rc.1
 
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "program")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -28,8 +29,12 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::activity::Entity")]
     Activity,
+    #[sea_orm(has_many = "super::daily_summary::Entity")]
+    DailySummary,
     #[sea_orm(has_many = "super::dimension_program::Entity")]
     DimensionProgram,
+    #[sea_orm(has_many = "super::program_alias::Entity")]
+    ProgramAlias,
     #[sea_orm(has_many = "super::remark::Entity")]
     Remark,
 }
@@ -40,12 +45,24 @@ impl Related<super::activity::Entity> for Entity {
     }
 }
 
+impl Related<super::daily_summary::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DailySummary.def()
+    }
+}
+
 impl Related<super::dimension_program::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::DimensionProgram.def()
     }
 }
 
+impl Related<super::program_alias::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProgramAlias.def()
+    }
+}
+
 impl Related<super::remark::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Remark.def()
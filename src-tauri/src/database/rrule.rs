@@ -0,0 +1,429 @@
+//! Recurrence rules in the iCalendar `RRULE` format, a subset of RFC 5545.
+//!
+//! Supported parts are `FREQ` (daily, weekly, monthly or yearly), `INTERVAL`, `BYDAY` without
+//! ordinals, `BYMONTHDAY`, `COUNT` and `UNTIL`. Every occurrence keeps the local time of the
+//! first one, weeks start on Monday.
+
+use std::{collections::BTreeSet, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use chrono::{
+    Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+use super::bucket;
+
+/// Occurrences expanded at most, a rule without `COUNT` or `UNTIL` is bounded only by the range.
+const MAX_OCCURRENCE_COUNT: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Until {
+    Utc(i64),
+    /// A date or a time without zone, compared with the local time of an occurrence.
+    Local(NaiveDateTime),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecurrenceRule {
+    frequency: Frequency,
+    interval: u32,
+    by_day: Vec<Weekday>,
+    /// Days of the month, negative ones count from the end of the month.
+    by_month_day: Vec<i32>,
+    count: Option<u32>,
+    until: Option<Until>,
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = anyhow::Error;
+
+    fn from_str(rule: &str) -> Result<Self> {
+        let rule = rule.trim();
+        let rule = rule.strip_prefix("RRULE:").unwrap_or(rule);
+        let mut frequency = None;
+        let mut result = RecurrenceRule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            by_day: vec![],
+            by_month_day: vec![],
+            count: None,
+            until: None,
+        };
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or(anyhow!("invalid rule part {}", part))?;
+            match name.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(anyhow!("unsupported frequency {}", value)),
+                    })
+                }
+                "INTERVAL" => {
+                    result.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or(anyhow!("invalid interval {}", value))?
+                }
+                "BYDAY" => {
+                    result.by_day = value.split(',').map(parse_weekday).collect::<Result<_>>()?
+                }
+                "BYMONTHDAY" => {
+                    result.by_month_day = value
+                        .split(',')
+                        .map(|day| {
+                            day.parse()
+                                .ok()
+                                .filter(|day: &i32| (1..=31).contains(&day.abs()))
+                                .ok_or(anyhow!("invalid month day {}", day))
+                        })
+                        .collect::<Result<_>>()?
+                }
+                "COUNT" => {
+                    result.count = Some(
+                        value
+                            .parse()
+                            .map_err(|_| anyhow!("invalid count {}", value))?,
+                    )
+                }
+                "UNTIL" => result.until = Some(parse_until(value)?),
+                _ => return Err(anyhow!("unsupported rule part {}", name)),
+            }
+        }
+        result.frequency = frequency.ok_or(anyhow!("missing frequency in {}", rule))?;
+        if result.count.is_some() && result.until.is_some() {
+            return Err(anyhow!("count and until can not be used together"));
+        }
+        if result.frequency == Frequency::Yearly
+            && !(result.by_day.is_empty() && result.by_month_day.is_empty())
+        {
+            return Err(anyhow!("yearly rules do not support days"));
+        }
+        Ok(result)
+    }
+}
+
+fn parse_weekday(day: &str) -> Result<Weekday> {
+    Ok(match day.to_ascii_uppercase().as_str() {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return Err(anyhow!("unsupported day {}", day)),
+    })
+}
+
+fn parse_until(value: &str) -> Result<Until> {
+    let invalid = || anyhow!("invalid until {}", value);
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+        // the whole day is included
+        return Ok(Until::Local(
+            date.and_time(NaiveTime::MIN) + Duration::days(1) - Duration::milliseconds(1),
+        ));
+    }
+    match value.strip_suffix('Z') {
+        Some(value) => {
+            let time =
+                NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+            Ok(Until::Utc(Utc.from_utc_datetime(&time).timestamp_millis()))
+        }
+        None => Ok(Until::Local(
+            NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").map_err(|_| invalid())?,
+        )),
+    }
+}
+
+impl RecurrenceRule {
+    /// Starts of the occurrences before `end`, the first one is at `start` in the time zone `tz`.
+    ///
+    /// Fails when there are more than [`MAX_OCCURRENCE_COUNT`] of them.
+    pub fn occurrences(&self, tz: &Tz, start: i64, end: i64) -> Result<Vec<i64>> {
+        let first = tz
+            .timestamp_millis_opt(start)
+            .single()
+            .ok_or(anyhow!("invalid timestamp {}", start))?
+            .naive_local();
+        let (first_date, time) = (first.date(), first.time());
+        let mut list = vec![];
+        let mut count = 0;
+        for period in 0.. {
+            let Some((period_start, date_list)) = self.period_dates(first_date, period) else {
+                break;
+            };
+            let period_start = period_start.and_time(NaiveTime::MIN);
+            let period_timestamp = bucket::resolve(tz, period_start)?;
+            let after_until = match self.until {
+                Some(Until::Utc(until)) => period_timestamp > until,
+                Some(Until::Local(until)) => period_start > until,
+                None => false,
+            };
+            if period_timestamp >= end || after_until {
+                break;
+            }
+            for date in date_list.into_iter().filter(|date| *date >= first_date) {
+                let local = date.and_time(time);
+                let timestamp = bucket::resolve(tz, local)?;
+                let after_until = match self.until {
+                    Some(Until::Utc(until)) => timestamp > until,
+                    Some(Until::Local(until)) => local > until,
+                    None => false,
+                };
+                count += 1;
+                if timestamp >= end
+                    || after_until
+                    || self.count.is_some_and(|max_count| count > max_count)
+                {
+                    return Ok(list);
+                }
+                if list.len() == MAX_OCCURRENCE_COUNT {
+                    return Err(anyhow!(
+                        "more than {} occurrences before {}",
+                        MAX_OCCURRENCE_COUNT,
+                        end
+                    ));
+                }
+                list.push(timestamp);
+            }
+        }
+        Ok(list)
+    }
+
+    /// First day of the period and its dates in order, `None` past the supported dates.
+    fn period_dates(
+        &self,
+        first_date: NaiveDate,
+        period: u32,
+    ) -> Option<(NaiveDate, Vec<NaiveDate>)> {
+        let step = period.checked_mul(self.interval)?;
+        let (period_start, candidate_list) = match self.frequency {
+            Frequency::Daily => {
+                let date = first_date.checked_add_signed(Duration::days(step as i64))?;
+                (date, vec![date])
+            }
+            Frequency::Weekly => {
+                let monday =
+                    first_date - Duration::days(first_date.weekday().num_days_from_monday() as i64);
+                let monday = monday.checked_add_signed(Duration::weeks(step as i64))?;
+                let day_list = if self.by_day.is_empty() {
+                    vec![first_date.weekday()]
+                } else {
+                    self.by_day.clone()
+                };
+                let offset_list = day_list
+                    .iter()
+                    .map(|day| day.num_days_from_monday() as i64)
+                    .collect::<BTreeSet<_>>();
+                let date_list = offset_list
+                    .into_iter()
+                    .map(|offset| monday + Duration::days(offset))
+                    .collect();
+                (monday, date_list)
+            }
+            Frequency::Monthly => {
+                let month = first_date
+                    .with_day(1)?
+                    .checked_add_months(Months::new(step))?;
+                let next_month = month.checked_add_months(Months::new(1))?;
+                let length = (next_month - month).num_days() as i32;
+                let date_list = if !self.by_day.is_empty() {
+                    month
+                        .iter_days()
+                        .take_while(|date| *date < next_month)
+                        .filter(|date| self.by_day.contains(&date.weekday()))
+                        .collect()
+                } else {
+                    let day_list = if self.by_month_day.is_empty() {
+                        vec![first_date.day() as i32]
+                    } else {
+                        self.by_month_day.clone()
+                    };
+                    day_list
+                        .into_iter()
+                        .map(|day| if day < 0 { length + day + 1 } else { day })
+                        .filter(|day| (1..=length).contains(day))
+                        .collect::<BTreeSet<_>>()
+                        .into_iter()
+                        .filter_map(|day| month.with_day(day as u32))
+                        .collect()
+                };
+                (month, date_list)
+            }
+            Frequency::Yearly => {
+                let year = first_date.year().checked_add(step as i32)?;
+                let date_list = first_date.with_year(year).into_iter().collect();
+                (NaiveDate::from_ymd_opt(year, 1, 1)?, date_list)
+            }
+        };
+        let date_list = candidate_list
+            .into_iter()
+            .filter(|date| self.matches_day(date))
+            .collect();
+        Some((period_start, date_list))
+    }
+
+    fn matches_day(&self, date: &NaiveDate) -> bool {
+        let by_day = self.by_day.is_empty() || self.by_day.contains(&date.weekday());
+        let by_month_day = self.by_month_day.is_empty() || {
+            let next_month = date
+                .with_day(1)
+                .and_then(|month| month.checked_add_months(Months::new(1)));
+            let from_end = next_month.map_or(0, |next_month| {
+                (date.signed_duration_since(next_month)).num_days() as i32
+            });
+            self.by_month_day
+                .iter()
+                .any(|day| *day == date.day() as i32 || *day == from_end)
+        };
+        by_day && by_month_day
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use chrono_tz::{America::New_York, Asia::Shanghai};
+
+    use super::*;
+
+    fn time(datetime: &str) -> i64 {
+        DateTime::parse_from_rfc3339(datetime)
+            .unwrap()
+            .timestamp_millis()
+    }
+
+    fn occurrences(rule: &str, start: &str, end: &str) -> Vec<i64> {
+        rule.parse::<RecurrenceRule>()
+            .unwrap()
+            .occurrences(&Shanghai, time(start), time(end))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let rule = "RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR;COUNT=4"
+            .parse::<RecurrenceRule>()
+            .unwrap();
+        assert_eq!(rule.frequency, Frequency::Weekly);
+        assert_eq!(rule.interval, 2);
+        assert_eq!(rule.by_day, vec![Weekday::Mon, Weekday::Fri]);
+        assert_eq!(rule.count, Some(4));
+        assert!("INTERVAL=2".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=HOURLY".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=DAILY;COUNT=2;UNTIL=20240801"
+            .parse::<RecurrenceRule>()
+            .is_err());
+        assert!("FREQ=MONTHLY;BYDAY=1MO".parse::<RecurrenceRule>().is_err());
+    }
+
+    #[test]
+    fn test_occurrence_count_cap() {
+        let rule = "FREQ=DAILY".parse::<RecurrenceRule>().unwrap();
+        let start = time("2024-08-01T10:00:00+08:00");
+        assert!(rule.occurrences(&Shanghai, start, i64::MAX).is_err());
+        let end = time("2049-12-01T00:00:00+08:00");
+        assert_eq!(rule.occurrences(&Shanghai, start, end).unwrap().len(), 9253);
+        // bounded by the rule itself
+        let rule = "FREQ=DAILY;COUNT=3".parse::<RecurrenceRule>().unwrap();
+        assert_eq!(
+            rule.occurrences(&Shanghai, start, i64::MAX).unwrap().len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_daily_on_weekdays() {
+        // 2024-08-01 is a Thursday
+        assert_eq!(
+            occurrences(
+                "FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR;UNTIL=20240806",
+                "2024-08-01T10:00:00+08:00",
+                "2024-09-01T00:00:00+08:00"
+            ),
+            vec![
+                time("2024-08-01T10:00:00+08:00"),
+                time("2024-08-02T10:00:00+08:00"),
+                time("2024-08-05T10:00:00+08:00"),
+                time("2024-08-06T10:00:00+08:00"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_weekly_with_interval_and_count() {
+        assert_eq!(
+            occurrences(
+                "FREQ=WEEKLY;INTERVAL=2;BYDAY=TH,MO;COUNT=3",
+                "2024-08-01T10:00:00+08:00",
+                "2024-09-01T00:00:00+08:00"
+            ),
+            vec![
+                time("2024-08-01T10:00:00+08:00"),
+                time("2024-08-12T10:00:00+08:00"),
+                time("2024-08-15T10:00:00+08:00"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_monthly_skips_missing_days() {
+        assert_eq!(
+            occurrences(
+                "FREQ=MONTHLY",
+                "2024-01-31T10:00:00+08:00",
+                "2024-05-01T00:00:00+08:00"
+            ),
+            vec![
+                time("2024-01-31T10:00:00+08:00"),
+                time("2024-03-31T10:00:00+08:00"),
+            ]
+        );
+        assert_eq!(
+            occurrences(
+                "FREQ=MONTHLY;BYMONTHDAY=-1",
+                "2024-01-31T10:00:00+08:00",
+                "2024-04-01T00:00:00+08:00"
+            ),
+            vec![
+                time("2024-01-31T10:00:00+08:00"),
+                time("2024-02-29T10:00:00+08:00"),
+                time("2024-03-31T10:00:00+08:00"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_local_time_across_daylight_saving_time() {
+        let rule = "FREQ=DAILY".parse::<RecurrenceRule>().unwrap();
+        let list = rule
+            .occurrences(
+                &New_York,
+                time("2024-11-02T09:00:00-04:00"),
+                time("2024-11-04T00:00:00-05:00"),
+            )
+            .unwrap();
+        assert_eq!(
+            list,
+            vec![
+                time("2024-11-02T09:00:00-04:00"),
+                time("2024-11-03T09:00:00-05:00"),
+            ]
+        );
+    }
+}
@@ -1,20 +1,42 @@
-use std::collections::HashMap;
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap, HashSet},
+    env::consts::OS,
+    hash::{DefaultHasher, Hash, Hasher},
+};
 
 use anyhow::anyhow;
-use chrono::{Duration, Local, TimeZone};
+use chrono::Utc;
+use chrono_tz::Tz;
 use futures_util::try_join;
-use now::DateTimeNow;
-use sea_orm::DatabaseConnection;
-use serde::Serialize;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DatabaseTransaction, TransactionTrait};
+use serde::{Deserialize, Serialize};
 use tauri_plugin_shion_sql::Result;
 
-use crate::database::sql::DailyStatusResult;
+use crate::database::{
+    alias::{self, AliasKind, AliasList, PathPattern},
+    bucket::{self, BucketUnit, Clock},
+    journal::{Journal, JournalTable},
+    models::{
+        activity, goal, label, note, note_template, note_template_exception, program, remark,
+        time_block, usage_limit,
+    },
+    rrule::RecurrenceRule,
+    sql::{
+        CalendarEventResult, DailyStatusResult, InsertActivity, InsertDailySummary, InsertLabel,
+        InsertNote, InsertNoteTemplateException, InsertProgram, InsertProgramAlias, InsertRemark,
+        InsertTemplateNote, UpdateLabel, UpdateProgram,
+    },
+};
 
 use super::sql;
 
 #[derive(Serialize, Clone)]
 pub struct DailyStatus {
     total: i64,
+    /// Time covered by any note or activity, counted once when they overlap.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    busy: Option<i64>,
     list: Vec<DailyStatusItem>,
 }
 
@@ -25,111 +47,3669 @@ pub struct DailyStatusItem {
     name: String,
     color: String,
 }
+
+#[derive(Serialize, Clone)]
+pub struct BucketStatus {
+    start: i64,
+    end: i64,
+    #[serde(flatten)]
+    status: DailyStatus,
+}
+
+/// How days are cut, read from the config.
+pub struct DaySetting {
+    /// IANA name, the system time zone is used when empty.
+    pub timezone: Option<String>,
+    pub day_start_hour: u32,
+    /// Evaluate every day in the time zone the system was in at that time.
+    pub travel_mode: bool,
+}
+
+fn parse_timezone(name: &str) -> Result<Tz> {
+    Ok(name
+        .parse::<Tz>()
+        .map_err(|_| anyhow!("invalid timezone {}", name))?)
+}
+
+fn get_system_timezone() -> Tz {
+    iana_time_zone::get_timezone()
+        .ok()
+        .and_then(|name| name.parse().ok())
+        .unwrap_or(Tz::UTC)
+}
+
+/// Logs the system time zone when it differs from the last logged one.
+pub async fn record_timezone(db: &DatabaseConnection) -> Result<()> {
+    let timezone = get_system_timezone().name().to_string();
+    let list = sql::select_timezone_log_list(db).await?;
+    if list.last().map(|log| &log.timezone) != Some(&timezone) {
+        sql::create_timezone_log(db, timezone, Utc::now().timestamp_millis()).await?;
+    }
+    Ok(())
+}
+
+pub async fn get_clock(db: &DatabaseConnection, setting: &DaySetting) -> Result<Clock> {
+    if setting.travel_mode {
+        let mut zones: Vec<(i64, Tz)> = sql::select_timezone_log_list(db)
+            .await?
+            .into_iter()
+            .filter_map(|log| log.timezone.parse().ok().map(|tz| (log.start, tz)))
+            .collect();
+        // nothing is logged until the background loop first runs
+        if zones.is_empty() {
+            zones.push((i64::MIN, get_system_timezone()));
+        }
+        return Ok(Clock::with_zones(zones, setting.day_start_hour));
+    }
+    let tz = match setting.timezone.as_deref() {
+        Some(name) if !name.is_empty() => parse_timezone(name)?,
+        _ => get_system_timezone(),
+    };
+    Ok(Clock::new(tz, setting.day_start_hour))
+}
+
+/// Days touching the range as `(date, start, end)`, clipped to it.
+///
+/// A day is cut in two when the time zone changes, both parts have the same date.
+fn get_day_list(clock: &Clock, range: (i64, i64)) -> Result<Vec<(String, i64, i64)>> {
+    clock
+        .edges(BucketUnit::Day, range)?
+        .windows(2)
+        .map(|edge| Ok((clock.format_date(edge[0])?, edge[0], edge[1])))
+        .collect()
+}
+
+/// Statuses by date, read from the summary the background task keeps up to date.
+///
+/// Days cut by the range, or changed since the summary was last refreshed, are computed from the
+/// raw rows. A summary of another day setting is not used until it is rebuilt.
 pub async fn get_active_status_calendar_map(
     db: &DatabaseConnection,
     start: i64,
     end: i64,
+    busy: bool,
+    clock: &Clock,
 ) -> Result<HashMap<String, DailyStatus>> {
+    let (stale, dirty_list) = try_join!(
+        sql::select_stale_daily_summary(db, clock.key()),
+        sql::select_daily_summary_dirty_list(db)
+    )?;
+    let (whole_start, whole_end) = match stale {
+        Some(_) => (end, start),
+        None => (
+            match clock.bucket_start(BucketUnit::Day, start)? {
+                day_start if day_start == start => start,
+                _ => clock.bucket_end(BucketUnit::Day, start)?,
+            },
+            clock.bucket_start(BucketUnit::Day, end)?,
+        ),
+    };
+    let day_list = get_day_list(clock, (start, end))?;
+    let cut_date_list = day_list
+        .iter()
+        .filter(|(_, start, end)| {
+            *start < whole_start
+                || *end > whole_end
+                || dirty_list
+                    .iter()
+                    .any(|dirty| dirty.start < *end && dirty.end.max(dirty.start + 1) > *start)
+        })
+        .map(|(date, ..)| date.clone())
+        .collect::<Vec<_>>();
+    let cut_day_list = day_list
+        .into_iter()
+        .filter(|(date, ..)| cut_date_list.contains(date))
+        .collect::<Vec<_>>();
+    let (cut_list, summary_list, label_list) = try_join!(
+        sql::select_daily_status(db, start, end, &cut_day_list, busy),
+        sql::select_daily_summary(db, whole_start, whole_end, busy),
+        sql::select_label_list(db)
+    )?;
+    let list = summary_list
+        .into_iter()
+        .filter(|item| !cut_date_list.contains(&item.date))
+        .chain(cut_list);
+
+    let mut map: HashMap<String, DailyStatus> = HashMap::new();
+    for item in list {
+        let status = map.entry(item.date).or_insert_with(|| DailyStatus {
+            total: 0,
+            busy: None,
+            list: vec![],
+        });
+        if item.key.is_empty() {
+            status.busy = Some(item.total);
+            continue;
+        }
+        status.total += item.total;
+        status.list.push(DailyStatusItem {
+            total: item.total,
+            key: item.key,
+            name: item.name,
+            color: item.color,
+        });
+    }
+    // the summary keeps the time of each label itself, moving a label needs no rebuild. A parent
+    // counts the time its subtree covers, from the notes as they overlap across labels
+    let tree = LabelTree::new(label_list);
+    let parent_id_set = tree.parent_id_set();
+    if parent_id_set.is_empty() {
+        return Ok(map);
+    }
+    let note_list = sql::select_note_for_daily_status(db, start, end).await?;
+    let list = tree
+        .roll_up(note_list)
+        .into_iter()
+        .filter(|item| {
+            parse_key_id(&item.key, "label_").is_some_and(|id| parent_id_set.contains(&id))
+        })
+        .collect();
+    // a day cut by a time zone change has two buckets of the same date
+    let mut parent_map: HashMap<(String, String), DailyStatusItem> = HashMap::new();
+    for bucket in aggregate(list, &clock.edges(BucketUnit::Day, (start, end))?, false) {
+        let date = clock.format_date(bucket.start)?;
+        for item in bucket.status.list {
+            let total = item.total;
+            parent_map
+                .entry((date.clone(), item.key.clone()))
+                .and_modify(|parent| parent.total += total)
+                .or_insert(item);
+        }
+    }
+    for ((date, key), parent) in parent_map {
+        let Some(status) = map.get_mut(&date) else {
+            continue;
+        };
+        match status.list.iter_mut().find(|item| item.key == key) {
+            Some(item) => item.total = parent.total,
+            None => status.list.push(parent),
+        }
+    }
+    Ok(map)
+}
+
+/// Days summarized in one query.
+const DAILY_SUMMARY_CHUNK_SIZE: usize = 366;
+
+/// Summarizes the days touched by notes and activities changed since the last refresh, run in
+/// the background so reading the summary never writes.
+///
+/// Everything is summarized again when the day setting changed.
+pub async fn refresh_daily_summary(db: &DatabaseConnection, clock: &Clock) -> Result<()> {
+    if sql::select_stale_daily_summary(db, clock.key())
+        .await?
+        .is_some()
+    {
+        return rebuild_daily_summary(db, clock).await;
+    }
+    summarize_dirty_days(db, clock).await
+}
+
+/// Drops the summary and summarizes the whole history again.
+pub async fn rebuild_daily_summary(db: &DatabaseConnection, clock: &Clock) -> Result<()> {
+    let txn = db.begin().await?;
+    sql::delete_daily_summary_all(&txn).await?;
+    sql::create_daily_summary_dirty_for_all(&txn).await?;
+    txn.commit().await?;
+    summarize_dirty_days(db, clock).await
+}
+
+async fn summarize_dirty_days(db: &DatabaseConnection, clock: &Clock) -> Result<()> {
+    let txn = db.begin().await?;
+    let dirty_list = sql::select_daily_summary_dirty_list(&txn).await?;
+    let Some(last_id) = dirty_list.last().map(|dirty| dirty.id) else {
+        return Ok(());
+    };
+    // a date is always summarized with all of its parts
+    let mut day_map: BTreeMap<String, Vec<(i64, i64)>> = BTreeMap::new();
+    for dirty in dirty_list {
+        let start = clock.bucket_start(BucketUnit::Day, dirty.start)?;
+        let end = clock.bucket_end(BucketUnit::Day, dirty.end.max(dirty.start + 1) - 1)?;
+        for (date, start, end) in get_day_list(clock, (start, end))? {
+            let part_list = day_map.entry(date).or_default();
+            if !part_list.contains(&(start, end)) {
+                part_list.push((start, end));
+            }
+        }
+    }
+
+    let clock_key = clock.key();
+    let day_list = day_map.into_iter().collect::<Vec<_>>();
+    for chunk in day_list.chunks(DAILY_SUMMARY_CHUNK_SIZE) {
+        let part_list = chunk
+            .iter()
+            .flat_map(|(date, part_list)| {
+                part_list
+                    .iter()
+                    .map(|(start, end)| (date.clone(), *start, *end))
+            })
+            .collect::<Vec<_>>();
+        let span_map = chunk
+            .iter()
+            .map(|(date, part_list)| {
+                let start = part_list.iter().map(|(start, _)| *start).min();
+                let end = part_list.iter().map(|(_, end)| *end).max();
+                (date, (start.unwrap_or(0), end.unwrap_or(0)))
+            })
+            .collect::<HashMap<_, _>>();
+        let start = span_map.values().map(|(start, _)| *start).min();
+        let end = span_map.values().map(|(_, end)| *end).max();
+        let list =
+            sql::select_daily_status(&txn, start.unwrap_or(0), end.unwrap_or(0), &part_list, true)
+                .await?;
+        let list = list
+            .into_iter()
+            .map(|item| {
+                let (start, end) = span_map[&item.date];
+                InsertDailySummary {
+                    label_id: parse_key_id(&item.key, "label_"),
+                    program_id: parse_key_id(&item.key, "program_"),
+                    date: item.date,
+                    start,
+                    end,
+                    total: item.total,
+                    clock: clock_key.clone(),
+                }
+            })
+            .collect();
+        sql::delete_daily_summary_by_date(
+            &txn,
+            chunk.iter().map(|(date, _)| date.clone()).collect(),
+        )
+        .await?;
+        sql::create_daily_summary_list(&txn, list).await?;
+    }
+    sql::delete_daily_summary_dirty(&txn, last_id).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+fn parse_key_id(key: &str, prefix: &str) -> Option<i64> {
+    key.strip_prefix(prefix).and_then(|id| id.parse().ok())
+}
+
+#[derive(Serialize)]
+pub struct DailySummaryMismatch {
+    date: String,
+    key: String,
+    summary: i64,
+    actual: i64,
+}
+
+/// Compares the summary of the days touching the range with totals computed from the raw rows.
+pub async fn check_daily_summary(
+    db: &DatabaseConnection,
+    start: i64,
+    end: i64,
+    clock: &Clock,
+) -> Result<Vec<DailySummaryMismatch>> {
+    refresh_daily_summary(db, clock).await?;
+    let start = clock.bucket_start(BucketUnit::Day, start)?;
+    let end = match clock.bucket_start(BucketUnit::Day, end)? {
+        day_start if day_start == end => end,
+        _ => clock.bucket_end(BucketUnit::Day, end)?,
+    };
+    let day_list = get_day_list(clock, (start, end))?;
+    let (actual_list, summary_list) = try_join!(
+        sql::select_daily_status(db, start, end, &day_list, true),
+        sql::select_daily_summary(db, start, end, true)
+    )?;
+
+    let mut map: BTreeMap<(String, String), (i64, i64)> = BTreeMap::new();
+    for item in summary_list {
+        map.entry((item.date, item.key)).or_default().0 += item.total;
+    }
+    for item in actual_list {
+        map.entry((item.date, item.key)).or_default().1 += item.total;
+    }
+    Ok(map
+        .into_iter()
+        .filter(|(_, (summary, actual))| summary != actual)
+        .map(|((date, key), (summary, actual))| DailySummaryMismatch {
+            date,
+            key,
+            summary,
+            actual,
+        })
+        .collect())
+}
+
+/// Totals of every bucket in the range, including empty ones. A parent label counts the time of
+/// its children.
+pub async fn get_bucket_status_list(
+    db: &DatabaseConnection,
+    start: i64,
+    end: i64,
+    unit: BucketUnit,
+    busy: bool,
+    clock: &Clock,
+) -> Result<Vec<BucketStatus>> {
+    let (note_list, activity_list, label_list) = try_join!(
+        sql::select_note_for_daily_status(db, start, end),
+        sql::select_activity_for_daily_status(db, start, end),
+        sql::select_label_list(db)
+    )?;
+    let edges = clock.edges(unit, (start, end))?;
+    let list: Vec<_> = note_list.into_iter().chain(activity_list).collect();
+    // the bucket total counts the time of each label itself, the copies of the rows for the
+    // ancestors would count it again
+    let total_list = aggregate(list.clone(), &edges, false)
+        .into_iter()
+        .map(|bucket| bucket.status.total);
+    let mut list = aggregate(LabelTree::new(label_list).roll_up(list), &edges, busy);
+    for (bucket, total) in list.iter_mut().zip(total_list) {
+        bucket.status.total = total;
+    }
+    Ok(list)
+}
+
+/// Labels by their parent, a label whose parent is removed counts as a root.
+pub struct LabelTree {
+    label_map: HashMap<i64, label::Model>,
+}
+
+impl LabelTree {
+    pub fn new(label_list: Vec<label::Model>) -> LabelTree {
+        LabelTree {
+            label_map: label_list
+                .into_iter()
+                .map(|label| (label.id, label))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, id: i64) -> Option<&label::Model> {
+        self.label_map.get(&id)
+    }
+
+    /// Ancestors of the label, its parent first.
+    pub fn ancestor_list(&self, id: i64) -> Vec<i64> {
+        let mut list = vec![];
+        let mut current = id;
+        while let Some(parent_id) = self.get(current).and_then(|label| label.parent_id) {
+            // a cycle is cut where it closes
+            if !self.label_map.contains_key(&parent_id)
+                || parent_id == id
+                || list.contains(&parent_id)
+            {
+                break;
+            }
+            list.push(parent_id);
+            current = parent_id;
+        }
+        list
+    }
+
+    /// The label and its descendants.
+    pub fn subtree(&self, id: i64) -> Vec<i64> {
+        let mut list = vec![id];
+        list.extend(
+            self.label_map
+                .keys()
+                .filter(|label_id| self.ancestor_list(**label_id).contains(&id)),
+        );
+        list
+    }
+
+    fn relabel(&self, item: &DailyStatusResult, id: i64) -> DailyStatusResult {
+        let label = &self.label_map[&id];
+        DailyStatusResult {
+            name: label.name.clone(),
+            color: label.color.clone(),
+            key: format!("label_{}", id),
+            ..item.clone()
+        }
+    }
+
+    /// Adds a copy of every label row for each ancestor of its label, so a parent counts the
+    /// time of its children.
+    fn roll_up(&self, list: Vec<DailyStatusResult>) -> Vec<DailyStatusResult> {
+        let mut result = vec![];
+        for item in list {
+            if let Some(id) = parse_key_id(&item.key, "label_") {
+                for ancestor_id in self.ancestor_list(id) {
+                    result.push(self.relabel(&item, ancestor_id));
+                }
+            }
+            result.push(item);
+        }
+        result
+    }
+
+    /// Labels with at least one child.
+    fn parent_id_set(&self) -> HashSet<i64> {
+        self.label_map
+            .keys()
+            .flat_map(|id| self.ancestor_list(*id))
+            .collect()
+    }
+
+    /// Counts the labels deeper than `depth` as their ancestor at `depth`, 0 being the roots.
+    fn collapse(&self, list: Vec<DailyStatusResult>, depth: usize) -> Vec<DailyStatusResult> {
+        list.into_iter()
+            .map(|item| {
+                let ancestor_list = parse_key_id(&item.key, "label_")
+                    .map(|id| self.ancestor_list(id))
+                    .unwrap_or_default();
+                match ancestor_list.len().checked_sub(depth + 1) {
+                    Some(index) => self.relabel(&item, ancestor_list[index]),
+                    None => item,
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum RollupLevel {
+    /// Notes by their plan.
+    Plan,
+    /// Notes by the dimensions of their label and activities by the dimensions of their program.
+    Dimension,
+    /// Notes by the ancestor of their label at `depth`, 0 being the labels without a parent.
+    Label { depth: usize },
+}
+
+/// Totals of every bucket in the range by plan, dimension or label level, including empty buckets.
+///
+/// Time of a label or program in several dimensions is counted in each of them, but only once in
+/// the bucket total.
+pub async fn get_rollup_status_list(
+    db: &DatabaseConnection,
+    start: i64,
+    end: i64,
+    level: RollupLevel,
+    unit: BucketUnit,
+    clock: &Clock,
+) -> Result<Vec<BucketStatus>> {
+    let list = match level {
+        RollupLevel::Plan => sql::select_note_for_plan_status(db, start, end).await?,
+        RollupLevel::Dimension => {
+            let (note_list, activity_list) = try_join!(
+                sql::select_note_for_dimension_status(db, start, end),
+                sql::select_activity_for_dimension_status(db, start, end)
+            )?;
+            note_list.into_iter().chain(activity_list).collect()
+        }
+        RollupLevel::Label { depth } => {
+            let (note_list, label_list) = try_join!(
+                sql::select_note_for_daily_status(db, start, end),
+                sql::select_label_list(db)
+            )?;
+            LabelTree::new(label_list).collapse(note_list, depth)
+        }
+    };
+    let edges = clock.edges(unit, (start, end))?;
+    let mut list = aggregate(list, &edges, true);
+    for bucket in &mut list {
+        bucket.status.total = bucket.status.busy.take().unwrap_or(0);
+    }
+    Ok(list)
+}
+
+/// A key in a bucket and the spans recorded for it.
+type Group = (DailyStatusResult, Vec<(i64, i64)>);
+
+fn aggregate(list: Vec<DailyStatusResult>, edges: &[i64], busy: bool) -> Vec<BucketStatus> {
+    let bucket_count = edges.len().saturating_sub(1);
+    // overlapping rows of the same key, e.g. two watchers recording the same program,
+    // only count the time they cover
+    let mut groups: Vec<Vec<Group>> = vec![vec![]; bucket_count];
+    let mut group_index: HashMap<(usize, String), usize> = HashMap::new();
+    let mut busy_spans: Vec<Vec<(i64, i64)>> = vec![vec![]; bucket_count];
+
+    for item in list {
+        for (index, start, end) in bucket::split((item.start, item.end), edges) {
+            if busy {
+                busy_spans[index].push((start, end));
+            }
+            match group_index.get(&(index, item.key.clone())) {
+                Some(&i) => groups[index][i].1.push((start, end)),
+                None => {
+                    group_index.insert((index, item.key.clone()), groups[index].len());
+                    groups[index].push((item.clone(), vec![(start, end)]));
+                }
+            }
+        }
+    }
+
+    groups
+        .into_iter()
+        .zip(busy_spans)
+        .enumerate()
+        .map(|(index, (groups, busy_spans))| {
+            let list = groups
+                .into_iter()
+                .map(|(item, spans)| DailyStatusItem {
+                    total: bucket::union_length(spans),
+                    key: item.key,
+                    name: item.name,
+                    color: item.color,
+                })
+                .collect::<Vec<_>>();
+            BucketStatus {
+                start: edges[index],
+                end: edges[index + 1],
+                status: DailyStatus {
+                    total: list.iter().map(|item| item.total).sum(),
+                    busy: busy.then(|| bucket::union_length(busy_spans)),
+                    list,
+                },
+            }
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkSession {
+    start: i64,
+    end: i64,
+    /// Time covered by activities.
+    active_time: i64,
+    /// Gaps between activities shorter than the idle threshold.
+    break_time: i64,
+    program: Option<DailyStatusItem>,
+    /// Label of the notes overlapping the session the longest.
+    label: Option<DailyStatusItem>,
+}
+
+/// Activities in the range grouped into sessions, split where no activity is recorded for longer
+/// than `idle_threshold` milliseconds.
+pub async fn get_work_session_list(
+    db: &DatabaseConnection,
+    start: i64,
+    end: i64,
+    idle_threshold: i64,
+) -> Result<Vec<WorkSession>> {
     let (note_list, activity_list) = try_join!(
         sql::select_note_for_daily_status(db, start, end),
         sql::select_activity_for_daily_status(db, start, end)
     )?;
+    let clip = |item: DailyStatusResult| DailyStatusResult {
+        start: item.start.max(start),
+        end: item.end.min(end),
+        ..item
+    };
+    Ok(detect_work_sessions(
+        activity_list.into_iter().map(clip).collect(),
+        note_list.into_iter().map(clip).collect(),
+        idle_threshold,
+    ))
+}
 
-    let mut map = HashMap::new();
+fn detect_work_sessions(
+    mut activity_list: Vec<DailyStatusResult>,
+    note_list: Vec<DailyStatusResult>,
+    idle_threshold: i64,
+) -> Vec<WorkSession> {
+    activity_list.sort_by_key(|activity| (activity.start, activity.end));
+    let mut groups: Vec<(i64, i64, Vec<DailyStatusResult>)> = vec![];
+    for activity in activity_list {
+        match groups.last_mut() {
+            Some((_, end, list)) if activity.start - *end <= idle_threshold => {
+                *end = (*end).max(activity.end);
+                list.push(activity);
+            }
+            _ => groups.push((activity.start, activity.end, vec![activity])),
+        }
+    }
 
-    let mut insert = |timestamp: i64, name: String, color: String, key: String, spend: i64| {
-        let date = Local
-            .timestamp_millis_opt(timestamp)
-            .unwrap()
-            .format("%Y-%m-%d")
-            .to_string();
-        let total = map
-            .get(&date)
-            .map(|status: &DailyStatus| status.total)
-            .unwrap_or(0);
-        let mut list = map
-            .get(&date)
-            .map(|status: &DailyStatus| status.list.clone())
-            .unwrap_or(vec![]);
-        let index = list.iter().position(|i| i.key == key);
-        if let Some(index) = index {
-            list[index].total += spend
-        } else {
-            list.push(DailyStatusItem {
-                key,
-                total: spend,
-                name,
-                color,
-            })
+    groups
+        .into_iter()
+        .map(|(start, end, list)| {
+            let active_time =
+                bucket::union_length(list.iter().map(|item| (item.start, item.end)).collect());
+            let note_list = note_list
+                .iter()
+                .filter(|note| note.start < end && note.end > start)
+                .cloned()
+                .collect();
+            WorkSession {
+                start,
+                end,
+                active_time,
+                break_time: end - start - active_time,
+                program: get_dominant(list, (start, end)),
+                label: get_dominant(note_list, (start, end)),
+            }
+        })
+        .collect()
+}
+
+/// The key recorded the longest within the range.
+fn get_dominant(list: Vec<DailyStatusResult>, range: (i64, i64)) -> Option<DailyStatusItem> {
+    aggregate(list, &[range.0, range.1], false)
+        .pop()?
+        .status
+        .list
+        .into_iter()
+        .max_by_key(|item| item.total)
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum NoteScope {
+    Note { id: i64 },
+    Label { id: i64 },
+    Plan { id: i64 },
+}
+
+#[derive(Serialize)]
+pub struct ProgramBreakdown {
+    /// Time covered by the notes.
+    total: i64,
+    /// Time of every program within the notes, the longest first.
+    list: Vec<DailyStatusItem>,
+}
+
+/// Programs in the foreground while the notes of the scope were recorded.
+pub async fn get_program_breakdown(
+    db: &DatabaseConnection,
+    scope: NoteScope,
+    start: Option<i64>,
+    end: Option<i64>,
+) -> Result<ProgramBreakdown> {
+    let (column, id_list) = match scope {
+        NoteScope::Note { id } => (note::Column::Id, vec![id]),
+        // the notes of the children are part of the label
+        NoteScope::Label { id } => (
+            note::Column::LabelId,
+            LabelTree::new(sql::select_label_list(db).await?).subtree(id),
+        ),
+        NoteScope::Plan { id } => (note::Column::PlanId, vec![id]),
+    };
+    let start = start.unwrap_or(i64::MIN);
+    let end = end.unwrap_or(i64::MAX);
+    let note_list = sql::select_note_list_by(db, column, id_list, start, end).await?;
+    let span_list = bucket::merge(
+        note_list
+            .into_iter()
+            .map(|note| (note.start.max(start), note.end.min(end)))
+            .filter(|(start, end)| start < end)
+            .collect(),
+    );
+    let activity_list = match (span_list.first(), span_list.last()) {
+        (Some((start, _)), Some((_, end))) => {
+            sql::select_activity_for_daily_status(db, *start, *end).await?
         }
-        map.insert(
-            date,
-            DailyStatus {
-                total: total + spend,
-                list,
-            },
-        );
+        _ => vec![],
     };
+    Ok(break_down_programs(&span_list, activity_list))
+}
+
+/// Expects the spans ordered and apart from each other.
+fn break_down_programs(
+    span_list: &[(i64, i64)],
+    activity_list: Vec<DailyStatusResult>,
+) -> ProgramBreakdown {
+    let mut groups: Vec<Group> = vec![];
+    let mut group_index: HashMap<String, usize> = HashMap::new();
+    for activity in activity_list {
+        let first = span_list.partition_point(|(_, end)| *end <= activity.start);
+        for (start, end) in span_list[first..]
+            .iter()
+            .take_while(|(start, _)| *start < activity.end)
+        {
+            let span = (activity.start.max(*start), activity.end.min(*end));
+            match group_index.get(&activity.key) {
+                Some(&i) => groups[i].1.push(span),
+                None => {
+                    group_index.insert(activity.key.clone(), groups.len());
+                    groups.push((activity.clone(), vec![span]));
+                }
+            }
+        }
+    }
+
+    let mut list = groups
+        .into_iter()
+        .map(|(item, spans)| DailyStatusItem {
+            total: bucket::union_length(spans),
+            key: item.key,
+            name: item.name,
+            color: item.color,
+        })
+        .collect::<Vec<_>>();
+    list.sort_by_key(|item| Reverse(item.total));
+    ProgramBreakdown {
+        total: span_list.iter().map(|(start, end)| end - start).sum(),
+        list,
+    }
+}
+
+/// Gap between activities treated as a break when none is configured.
+pub const DEFAULT_IDLE_THRESHOLD: i64 = 5 * 60 * 1000;
 
-    let list = note_list.into_iter().chain(activity_list).collect();
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum FocusLevel {
+    Program,
+    /// An activity stays in every dimension of its program.
+    Dimension,
+}
 
-    let list = split_by_day(list, (start, end))?;
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusStatus {
+    start: i64,
+    end: i64,
+    /// Times an activity shares no program or dimension with the one right before it.
+    switch_count: i64,
+    /// Median length of the spans spent in one program or dimension without interruption.
+    median_focus: i64,
+    longest_focus: i64,
+}
 
+/// Focus metrics of every bucket in the range, spans are cut at the bucket edges.
+pub async fn get_focus_status_list(
+    db: &DatabaseConnection,
+    start: i64,
+    end: i64,
+    level: FocusLevel,
+    unit: BucketUnit,
+    idle_threshold: i64,
+    clock: &Clock,
+) -> Result<Vec<FocusStatus>> {
+    let list = match level {
+        FocusLevel::Program => sql::select_activity_for_daily_status(db, start, end).await?,
+        FocusLevel::Dimension => sql::select_activity_for_dimension_status(db, start, end).await?,
+    };
+    let edges = clock.edges(unit, (start, end))?;
+    let mut bucket_list = vec![vec![]; edges.len().saturating_sub(1)];
     for item in list {
-        let DailyStatusResult {
-            name,
-            color,
-            key,
+        for (index, start, end) in bucket::split((item.start, item.end), &edges) {
+            bucket_list[index].push(DailyStatusResult {
+                start,
+                end,
+                ..item.clone()
+            });
+        }
+    }
+    Ok(bucket_list
+        .into_iter()
+        .enumerate()
+        .map(|(index, list)| measure_focus(list, (edges[index], edges[index + 1]), idle_threshold))
+        .collect())
+}
+
+/// Rows with the same start and end are taken as one activity in several keys.
+fn measure_focus(
+    mut list: Vec<DailyStatusResult>,
+    range: (i64, i64),
+    idle_threshold: i64,
+) -> FocusStatus {
+    list.sort_by_key(|item| (item.start, item.end));
+    let mut switch_count = 0;
+    let mut span_list = vec![];
+    // spans of the keys of the last activity
+    let mut open_spans: HashMap<String, (i64, i64)> = HashMap::new();
+    let mut last_end = None;
+
+    for activity in list.chunk_by(|a, b| (a.start, a.end) == (b.start, b.end)) {
+        let (start, end) = (activity[0].start, activity[0].end);
+        let is_idle = last_end.is_some_and(|last_end| start - last_end > idle_threshold);
+        let is_continued = |key: &String| activity.iter().any(|item| &item.key == key);
+        if !is_idle && !open_spans.is_empty() && !open_spans.keys().any(is_continued) {
+            switch_count += 1;
+        }
+        open_spans.retain(|key, (span_start, span_end)| {
+            let keep = !is_idle && is_continued(key);
+            if !keep {
+                span_list.push(*span_end - *span_start);
+            }
+            keep
+        });
+        for item in activity {
+            open_spans
+                .entry(item.key.clone())
+                .and_modify(|(_, span_end)| *span_end = (*span_end).max(end))
+                .or_insert((start, end));
+        }
+        last_end = Some(last_end.map_or(end, |last_end: i64| last_end.max(end)));
+    }
+    span_list.extend(open_spans.values().map(|(start, end)| end - start));
+    span_list.sort();
+
+    let median_focus = match span_list.len() {
+        0 => 0,
+        len if len % 2 == 1 => span_list[len / 2],
+        len => (span_list[len / 2 - 1] + span_list[len / 2]) / 2,
+    };
+    FocusStatus {
+        start: range.0,
+        end: range.1,
+        switch_count,
+        median_focus,
+        longest_focus: span_list.last().copied().unwrap_or(0),
+    }
+}
+
+#[derive(Serialize)]
+pub struct GoalPeriod {
+    start: i64,
+    end: i64,
+    total: i64,
+    met: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoalStatus {
+    id: i64,
+    /// Time recorded in the current period.
+    progress: i64,
+    /// Periods from the one the goal starts in to the current one.
+    period_list: Vec<GoalPeriod>,
+    /// Met periods up to now, the current period is only counted once it can no longer be missed.
+    current_streak: i64,
+    longest_streak: i64,
+}
+
+/// Bucket of the `period` of a goal or usage limit.
+fn get_period_unit(period: i64) -> Result<BucketUnit> {
+    Ok(match period {
+        0 => BucketUnit::Day,
+        1 => BucketUnit::Week,
+        2 => BucketUnit::Month,
+        _ => return Err(anyhow!("invalid period {}", period).into()),
+    })
+}
+
+/// Rows of every label, plan, program or dimension, by the `target_type` of a goal or usage limit.
+async fn select_target_list(
+    db: &DatabaseConnection,
+    target_type: i64,
+    start: i64,
+    end: i64,
+) -> Result<(&'static str, Vec<DailyStatusResult>)> {
+    Ok(match target_type {
+        0 => (
+            "label",
+            sql::select_note_for_daily_status(db, start, end).await?,
+        ),
+        1 => (
+            "plan",
+            sql::select_note_for_plan_status(db, start, end).await?,
+        ),
+        2 => (
+            "program",
+            sql::select_activity_for_daily_status(db, start, end).await?,
+        ),
+        3 => {
+            let (note_list, activity_list) = try_join!(
+                sql::select_note_for_dimension_status(db, start, end),
+                sql::select_activity_for_dimension_status(db, start, end)
+            )?;
+            (
+                "dimension",
+                note_list.into_iter().chain(activity_list).collect(),
+            )
+        }
+        _ => return Err(anyhow!("invalid target type {}", target_type).into()),
+    })
+}
+
+pub async fn get_goal_status_list(
+    db: &DatabaseConnection,
+    clock: &Clock,
+) -> Result<Vec<GoalStatus>> {
+    let now = Utc::now().timestamp_millis();
+    let tree = LabelTree::new(sql::select_label_list(db).await?);
+    let mut status_list = vec![];
+    for goal in sql::select_goal_list(db).await? {
+        let unit = get_period_unit(goal.period)?;
+        let start = clock.bucket_start(unit, goal.start)?;
+        let end = clock.bucket_end(unit, now)?;
+        let (prefix, mut list) = select_target_list(db, goal.target_type, start, end).await?;
+        // a goal of a label counts the time of its children
+        if prefix == "label" {
+            list = tree.roll_up(list);
+        }
+        let key = format!("{}_{}", prefix, goal.target_id);
+        let list = list.into_iter().filter(|item| item.key == key).collect();
+        let total_list = aggregate(list, &clock.edges(unit, (start, end))?, false)
+            .into_iter()
+            .map(|bucket| (bucket.start, bucket.end, bucket.status.total))
+            .collect();
+        status_list.push(evaluate_goal(&goal, total_list));
+    }
+    Ok(status_list)
+}
+
+/// Expects the totals of every period, the last one being the current period.
+fn evaluate_goal(goal: &goal::Model, total_list: Vec<(i64, i64, i64)>) -> GoalStatus {
+    let period_list = total_list
+        .into_iter()
+        .map(|(start, end, total)| GoalPeriod {
             start,
             end,
-        } = item;
-        insert(start, name, color, key, end - start);
+            total,
+            met: if goal.at_most {
+                total <= goal.duration
+            } else {
+                total >= goal.duration
+            },
+        })
+        .collect::<Vec<_>>();
+    // the current period of a maximum may still be missed, one of a minimum may still be met
+    let is_current_final = !goal.at_most && period_list.last().is_some_and(|period| period.met);
+    let final_period_count = if is_current_final {
+        period_list.len()
+    } else {
+        period_list.len().saturating_sub(1)
+    };
+
+    let mut streak = 0;
+    let mut longest_streak = 0;
+    for period in &period_list[..final_period_count] {
+        streak = if period.met { streak + 1 } else { 0 };
+        longest_streak = longest_streak.max(streak);
+    }
+    GoalStatus {
+        id: goal.id,
+        progress: period_list.last().map_or(0, |period| period.total),
+        period_list,
+        current_streak: streak,
+        longest_streak,
     }
+}
 
-    Ok(map)
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum LimitLevel {
+    Warning,
+    Exceeded,
 }
 
-fn split_by_day(list: Vec<DailyStatusResult>, range: (i64, i64)) -> Result<Vec<DailyStatusResult>> {
-    let mut result = Vec::new();
-    let (start_date, end_date) = range;
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitStatus {
+    pub id: i64,
+    pub period_start: i64,
+    pub period_end: i64,
+    pub total: i64,
+    pub duration: i64,
+    pub level: Option<LimitLevel>,
+    pub snoozed_until: Option<i64>,
+}
 
-    for DailyStatusResult {
-        start,
-        end,
-        name,
-        color,
-        key,
-    } in list
-    {
-        let mut _start = start.max(start_date);
+/// Usage of every limit in its current period.
+pub async fn get_limit_status_list(
+    db: &DatabaseConnection,
+    clock: &Clock,
+) -> Result<Vec<LimitStatus>> {
+    let now = Utc::now().timestamp_millis();
+    let snooze_list = sql::select_usage_limit_snooze_list(db, now).await?;
+    let mut status_list = vec![];
+    for limit in sql::select_usage_limit_list(db).await? {
+        let unit = match check_usage_limit(&limit) {
+            Ok(unit) => unit,
+            Err(e) => {
+                log::warn!("invalid usage limit {}: {}", limit.id, e);
+                continue;
+            }
+        };
+        let start = clock.bucket_start(unit, now)?;
+        let end = clock.bucket_end(unit, now)?;
+        let (prefix, list) = select_target_list(db, limit.target_type, start, end).await?;
+        let key = format!("{}_{}", prefix, limit.target_id);
+        let list = list.into_iter().filter(|item| item.key == key).collect();
+        let total = aggregate(list, &[start, end], false)
+            .pop()
+            .map_or(0, |bucket| bucket.status.total);
+        status_list.push(LimitStatus {
+            id: limit.id,
+            period_start: start,
+            period_end: end,
+            total,
+            duration: limit.duration,
+            level: get_limit_level(total, limit.duration, limit.warning_percent),
+            snoozed_until: snooze_list
+                .iter()
+                .filter(|snooze| snooze.usage_limit_id == limit.id)
+                .map(|snooze| snooze.end)
+                .max(),
+        });
+    }
+    Ok(status_list)
+}
 
-        let mut time_list = vec![_start];
+/// Period of a usage limit, a limit without any duration would be exceeded all the time.
+fn check_usage_limit(limit: &usage_limit::Model) -> Result<BucketUnit> {
+    if limit.duration <= 0 {
+        return Err(anyhow!("duration {} is not positive", limit.duration).into());
+    }
+    if !(0..=100).contains(&limit.warning_percent) {
+        return Err(anyhow!("invalid warning percent {}", limit.warning_percent).into());
+    }
+    get_period_unit(limit.period)
+}
 
-        _start = Local
-            .timestamp_millis_opt(_start)
-            .single()
-            .ok_or(anyhow!("invalid timestamp"))?
-            .beginning_of_day()
-            .timestamp_millis()
-            + Duration::days(1).num_milliseconds();
+fn get_limit_level(total: i64, duration: i64, warning_percent: i64) -> Option<LimitLevel> {
+    if total >= duration {
+        Some(LimitLevel::Exceeded)
+    } else if total * 100 >= duration * warning_percent {
+        Some(LimitLevel::Warning)
+    } else {
+        None
+    }
+}
 
-        while end > _start {
-            time_list.push(_start);
-            _start += Duration::days(1).num_milliseconds();
+/// Silences the warnings of a limit for `duration` milliseconds.
+pub async fn snooze_usage_limit(db: &DatabaseConnection, id: i64, duration: i64) -> Result<()> {
+    let now = Utc::now().timestamp_millis();
+    sql::create_usage_limit_snooze(db, id, now, now + duration).await?;
+    Ok(())
+}
+
+/// How far back continuous activity is looked for.
+const WORK_LOOKBACK: i64 = 24 * 60 * 60 * 1000;
+
+/// Start of the activity running at `now` without a gap of at least `break_duration`
+/// milliseconds, or `None` when no activity is recorded within the last `break_duration`.
+pub async fn get_work_start(
+    db: &DatabaseConnection,
+    now: i64,
+    break_duration: i64,
+) -> Result<Option<i64>> {
+    let list = sql::select_activity_for_daily_status(db, now - WORK_LOOKBACK, now).await?;
+    let span_list = list
+        .into_iter()
+        .map(|item| (item.start.max(now - WORK_LOOKBACK), item.end.min(now)))
+        .collect();
+    Ok(find_work_start(
+        bucket::merge(span_list),
+        now,
+        break_duration,
+    ))
+}
+
+fn find_work_start(span_list: Vec<(i64, i64)>, now: i64, break_duration: i64) -> Option<i64> {
+    let mut span_list = span_list.into_iter().rev();
+    let (mut start, end) = span_list.next()?;
+    if now - end >= break_duration {
+        return None;
+    }
+    for (previous_start, previous_end) in span_list {
+        if start - previous_end >= break_duration {
+            break;
         }
+        start = previous_start;
+    }
+    Some(start)
+}
 
-        time_list.push(end.min(end_date));
+/// A running note keeps its end up to date once a minute.
+const RUNNING_NOTE_TOLERANCE: i64 = 2 * 60 * 1000;
 
-        for i in 0..time_list.len() - 1 {
-            result.push(DailyStatusResult {
-                start: time_list[i],
-                end: time_list[i + 1],
-                name: name.clone(),
-                color: color.clone(),
-                key: key.clone(),
-            });
+/// Whether a note of one of the labels or of their children is running at `now`.
+pub async fn is_label_running(
+    db: &DatabaseConnection,
+    label_list: &[i64],
+    now: i64,
+) -> Result<bool> {
+    if label_list.is_empty() {
+        return Ok(false);
+    }
+    let note_list = sql::select_note_list(db, now - RUNNING_NOTE_TOLERANCE, now + 1).await?;
+    if note_list.is_empty() {
+        return Ok(false);
+    }
+    let tree = LabelTree::new(sql::select_label_list(db).await?);
+    Ok(note_list.iter().any(|note| {
+        label_list.contains(&note.label_id)
+            || tree
+                .ancestor_list(note.label_id)
+                .iter()
+                .any(|id| label_list.contains(id))
+    }))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeBlockOccurrence {
+    id: i64,
+    label_id: i64,
+    start: i64,
+    end: i64,
+    /// Time of notes of the label within the block.
+    tracked: i64,
+    /// Time covered by activities within the block.
+    active: i64,
+    /// Time notes of the label kept running past the end of the block.
+    overrun: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeBlockReport {
+    start: i64,
+    end: i64,
+    planned: i64,
+    tracked: i64,
+    /// Share of the planned time tracked, `None` when nothing is planned.
+    adherence: Option<f64>,
+    overrun: i64,
+    /// Time of notes outside every block of their label, overruns excluded.
+    unplanned: i64,
+    occurrence_list: Vec<TimeBlockOccurrence>,
+}
+
+/// Planned time blocks compared with the notes and activities recorded, by bucket.
+///
+/// Blocks are clipped to the range and counted in the bucket they start in.
+pub async fn get_time_block_report_list(
+    db: &DatabaseConnection,
+    start: i64,
+    end: i64,
+    unit: BucketUnit,
+    clock: &Clock,
+) -> Result<Vec<TimeBlockReport>> {
+    let (block_list, note_list, activity_list) = try_join!(
+        sql::select_time_block_list(db, start, end),
+        sql::select_note_for_daily_status(db, start, end),
+        sql::select_activity_for_daily_status(db, start, end)
+    )?;
+    let mut occurrence_list = vec![];
+    for block in block_list {
+        for occurrence in expand_time_block(&block, clock, (start, end))? {
+            occurrence_list.push((block.id, block.label_id, occurrence));
         }
     }
+    let note_list = note_list
+        .into_iter()
+        .filter_map(|note| Some((parse_key_id(&note.key, "label_")?, (note.start, note.end))))
+        .collect();
+    let activity_list = activity_list
+        .into_iter()
+        .map(|activity| (activity.start, activity.end))
+        .collect();
+    let edges = clock.edges(unit, (start, end))?;
+    Ok(compare_time_blocks(
+        occurrence_list,
+        note_list,
+        activity_list,
+        &edges,
+    ))
+}
 
-    Ok(result)
+/// Occurrences of the block overlapping the range, clipped to it.
+fn expand_time_block(
+    block: &time_block::Model,
+    clock: &Clock,
+    range: (i64, i64),
+) -> Result<Vec<(i64, i64)>> {
+    let length = block.end - block.start;
+    let start_list = match block.rrule.as_deref().filter(|rule| !rule.is_empty()) {
+        Some(rule) => rule.parse::<RecurrenceRule>()?.occurrences(
+            &clock.tz_at(block.start),
+            block.start,
+            range.1,
+        )?,
+        None => vec![block.start],
+    };
+    Ok(start_list
+        .into_iter()
+        .filter(|start| *start < range.1 && start + length > range.0)
+        .map(|start| (start.max(range.0), (start + length).min(range.1)))
+        .collect())
+}
+
+/// Spans of the notes and of the blocks of a label.
+type LabelSpans = (Vec<(i64, i64)>, Vec<(i64, i64)>);
+
+fn compare_time_blocks(
+    mut occurrence_list: Vec<(i64, i64, (i64, i64))>,
+    note_list: Vec<(i64, (i64, i64))>,
+    activity_list: Vec<(i64, i64)>,
+    edges: &[i64],
+) -> Vec<TimeBlockReport> {
+    occurrence_list.sort_by_key(|(_, _, span)| *span);
+    let mut label_map: HashMap<i64, LabelSpans> = HashMap::new();
+    for (label_id, span) in note_list {
+        label_map.entry(label_id).or_default().0.push(span);
+    }
+    for (_, label_id, span) in &occurrence_list {
+        label_map.entry(*label_id).or_default().1.push(*span);
+    }
+    let label_map = label_map
+        .into_iter()
+        .map(|(label_id, (note_list, block_list))| {
+            (
+                label_id,
+                (bucket::merge(note_list), bucket::merge(block_list)),
+            )
+        })
+        .collect::<HashMap<_, _>>();
+    let activity_list = bucket::merge(activity_list);
+
+    let mut report_list = edges
+        .windows(2)
+        .map(|edge| TimeBlockReport {
+            start: edge[0],
+            end: edge[1],
+            planned: 0,
+            tracked: 0,
+            adherence: None,
+            overrun: 0,
+            unplanned: 0,
+            occurrence_list: vec![],
+        })
+        .collect::<Vec<_>>();
+    if report_list.is_empty() {
+        return report_list;
+    }
+    let mut overrun_map: HashMap<i64, Vec<(i64, i64)>> = HashMap::new();
+    for (id, label_id, (start, end)) in occurrence_list {
+        let (note_list, block_list) = &label_map[&label_id];
+        let next_start = block_list
+            .iter()
+            .map(|(start, _)| *start)
+            .find(|next_start| *next_start >= end)
+            .unwrap_or(i64::MAX);
+        let overrun = note_list
+            .iter()
+            .find(|(note_start, note_end)| *note_start < end && *note_end > end)
+            .map_or(0, |(_, note_end)| note_end.min(&next_start) - end);
+        let index = (edges.partition_point(|edge| *edge <= start) - 1).min(report_list.len() - 1);
+        let report = &mut report_list[index];
+        let occurrence = TimeBlockOccurrence {
+            id,
+            label_id,
+            start,
+            end,
+            tracked: bucket::overlap_length(note_list, (start, end)),
+            active: bucket::overlap_length(&activity_list, (start, end)),
+            overrun,
+        };
+        report.planned += end - start;
+        report.tracked += occurrence.tracked;
+        report.overrun += occurrence.overrun;
+        report.occurrence_list.push(occurrence);
+        if overrun > 0 {
+            overrun_map
+                .entry(label_id)
+                .or_default()
+                .push((end, end + overrun));
+        }
+    }
+    for (label_id, (note_list, block_list)) in &label_map {
+        let overrun_list = overrun_map.remove(label_id).unwrap_or_default();
+        let cut = bucket::merge(block_list.iter().copied().chain(overrun_list).collect());
+        for span in bucket::subtract(note_list, &cut) {
+            for (index, start, end) in bucket::split(span, edges) {
+                report_list[index].unplanned += end - start;
+            }
+        }
+    }
+    for report in &mut report_list {
+        if report.planned > 0 {
+            report.adherence = Some(report.tracked as f64 / report.planned as f64);
+        }
+    }
+    report_list
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteOccurrence {
+    note_template_id: i64,
+    /// Start of the occurrence by the rule, it identifies the occurrence.
+    occurrence: i64,
+    start: i64,
+    end: i64,
+    skipped: bool,
+}
+
+/// Starts of the occurrences of the template before `end`, none when it has no rule.
+fn get_template_occurrence_list(
+    template: &note_template::Model,
+    clock: &Clock,
+    end: i64,
+) -> Result<Vec<i64>> {
+    let Some(rule) = template.rrule.as_deref().filter(|rule| !rule.is_empty()) else {
+        return Ok(vec![]);
+    };
+    let rule = rule.parse::<RecurrenceRule>()?;
+    Ok(rule.occurrences(&clock.tz_at(template.start), template.start, end)?)
+}
+
+fn apply_note_template_exceptions(
+    template: &note_template::Model,
+    occurrence_list: Vec<i64>,
+    exception_list: &[note_template_exception::Model],
+) -> Vec<NoteOccurrence> {
+    occurrence_list
+        .into_iter()
+        .map(|occurrence| {
+            let exception = exception_list
+                .iter()
+                .find(|exception| exception.occurrence == occurrence);
+            NoteOccurrence {
+                note_template_id: template.id,
+                occurrence,
+                start: exception
+                    .and_then(|exception| exception.start)
+                    .unwrap_or(occurrence),
+                end: exception
+                    .and_then(|exception| exception.end)
+                    .unwrap_or(occurrence + template.end - template.start),
+                skipped: exception.is_some_and(|exception| exception.skip),
+            }
+        })
+        .collect()
+}
+
+/// Occurrences of the template overlapping the range, with skips and overrides applied.
+pub async fn get_note_occurrence_list(
+    db: &DatabaseConnection,
+    note_template_id: i64,
+    start: i64,
+    end: i64,
+    clock: &Clock,
+) -> Result<Vec<NoteOccurrence>> {
+    let template = sql::select_note_template_by_id(db, note_template_id)
+        .await?
+        .ok_or(anyhow!("note template {} not found", note_template_id))?;
+    let exception_list = sql::select_note_template_exception_list(db, note_template_id).await?;
+    let occurrence_list = get_template_occurrence_list(&template, clock, end)?;
+    Ok(
+        apply_note_template_exceptions(&template, occurrence_list, &exception_list)
+            .into_iter()
+            .filter(|occurrence| occurrence.end > start && occurrence.start < end)
+            .collect(),
+    )
+}
+
+/// Creates the notes of the template occurrences started by `now`.
+///
+/// Every occurrence is created at most once, a removed note or a skipped occurrence is not
+/// created again.
+pub async fn materialize_note_templates(
+    db: &DatabaseConnection,
+    clock: &Clock,
+    now: i64,
+) -> Result<()> {
+    for (template, label) in sql::select_note_template_list(db).await? {
+        if let Err(e) = materialize_note_template(db, &template, &label, clock, now).await {
+            log::error!("failed to materialize note template {}: {}", template.id, e);
+        }
+    }
+    Ok(())
+}
+
+async fn materialize_note_template(
+    db: &DatabaseConnection,
+    template: &note_template::Model,
+    label: &label::Model,
+    clock: &Clock,
+    now: i64,
+) -> Result<()> {
+    let occurrence_list = get_template_occurrence_list(template, clock, now + 1)?
+        .into_iter()
+        .filter(|occurrence| *occurrence >= template.materialized_until)
+        .collect();
+    let exception_list = sql::select_note_template_exception_list(db, template.id).await?;
+    let note_list = apply_note_template_exceptions(template, occurrence_list, &exception_list)
+        .into_iter()
+        .filter(|occurrence| !occurrence.skipped)
+        .map(|occurrence| InsertTemplateNote {
+            start: occurrence.start,
+            end: occurrence.end,
+            plan_id: label.plan_id,
+            label_id: label.id,
+            note_template_id: template.id,
+            occurrence: occurrence.occurrence,
+        })
+        .collect();
+    let txn = db.begin().await?;
+    sql::create_template_note_list(&txn, note_list).await?;
+    sql::update_note_template_materialized_until(&txn, template.id, now + 1).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+async fn check_note_occurrence(
+    db: &DatabaseConnection,
+    note_template_id: i64,
+    occurrence: i64,
+    clock: &Clock,
+) -> Result<()> {
+    let template = sql::select_note_template_by_id(db, note_template_id)
+        .await?
+        .ok_or(anyhow!("note template {} not found", note_template_id))?;
+    if !get_template_occurrence_list(&template, clock, occurrence + 1)?.contains(&occurrence) {
+        return Err(anyhow!(
+            "{} is not an occurrence of note template {}",
+            occurrence,
+            note_template_id
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Skips an occurrence of the template, its note is removed when it was already created.
+pub async fn skip_note_occurrence(
+    db: &DatabaseConnection,
+    note_template_id: i64,
+    occurrence: i64,
+    clock: &Clock,
+) -> Result<()> {
+    check_note_occurrence(db, note_template_id, occurrence, clock).await?;
+    let txn = db.begin().await?;
+    let mut journal = Journal::new("skip note occurrence");
+    journal
+        .track(
+            &txn,
+            JournalTable::NoteTemplateException,
+            "note_template_id",
+            vec![note_template_id],
+        )
+        .await?;
+    let exception_id = sql::create_note_template_exception(
+        &txn,
+        InsertNoteTemplateException {
+            note_template_id,
+            occurrence,
+            skip: true,
+            start: None,
+            end: None,
+        },
+    )
+    .await?;
+    journal.created(JournalTable::NoteTemplateException, [exception_id]);
+    if let Some(note) = sql::select_note_by_occurrence(&txn, note_template_id, occurrence).await? {
+        if note.deleted_at == 0 {
+            journal
+                .track(&txn, JournalTable::Note, "id", vec![note.id])
+                .await?;
+            sql::remove_note(&txn, note.id).await?;
+        }
+    }
+    journal.commit(&txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+/// Moves an occurrence of the template, its note is moved too when it was already created.
+pub async fn override_note_occurrence(
+    db: &DatabaseConnection,
+    note_template_id: i64,
+    occurrence: i64,
+    start: i64,
+    end: i64,
+    clock: &Clock,
+) -> Result<()> {
+    if start >= end {
+        return Err(anyhow!("start {} is not before end {}", start, end).into());
+    }
+    check_note_occurrence(db, note_template_id, occurrence, clock).await?;
+    let txn = db.begin().await?;
+    let mut journal = Journal::new("override note occurrence");
+    journal
+        .track(
+            &txn,
+            JournalTable::NoteTemplateException,
+            "note_template_id",
+            vec![note_template_id],
+        )
+        .await?;
+    let exception_id = sql::create_note_template_exception(
+        &txn,
+        InsertNoteTemplateException {
+            note_template_id,
+            occurrence,
+            skip: false,
+            start: Some(start),
+            end: Some(end),
+        },
+    )
+    .await?;
+    journal.created(JournalTable::NoteTemplateException, [exception_id]);
+    if let Some(note) = sql::select_note_by_occurrence(&txn, note_template_id, occurrence).await? {
+        if note.deleted_at == 0 {
+            journal
+                .track(&txn, JournalTable::Note, "id", vec![note.id])
+                .await?;
+            sql::update_note_range(&txn, note.id, start, end).await?;
+        }
+    }
+    journal.commit(&txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+/// How a manual note that overlaps other notes is handled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConflictPolicy {
+    Reject,
+    /// Trims the note to the free time around the other notes, splitting it when needed.
+    Resolve,
+}
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum NoteConflict {
+    #[error("note ends before it starts")]
+    Inverted,
+    #[error("note is too far in the future")]
+    FarFuture,
+    #[error("note overlaps notes {0:?}")]
+    Overlap(Vec<i64>),
+}
+
+/// How far past now a note may end, planned notes come from templates instead.
+const NOTE_FUTURE_LIMIT: i64 = 24 * 60 * 60 * 1000;
+
+/// Spans the note is saved as, given the `(id, start, end)` of the notes around it.
+fn resolve_note_conflict(
+    span: (i64, i64),
+    now: i64,
+    other_list: &[(i64, i64, i64)],
+    policy: ConflictPolicy,
+) -> std::result::Result<Vec<(i64, i64)>, NoteConflict> {
+    let (start, mut end) = span;
+    if start > end {
+        return Err(NoteConflict::Inverted);
+    }
+    let limit = now + NOTE_FUTURE_LIMIT;
+    if start > limit || (end > limit && policy == ConflictPolicy::Reject) {
+        return Err(NoteConflict::FarFuture);
+    }
+    end = end.min(limit);
+    // a zero-length note is a running one, it only conflicts when it starts inside another
+    let overlap_list = other_list
+        .iter()
+        .filter(|(_, other_start, other_end)| {
+            other_start < other_end && *other_start < end && *other_end > start
+        })
+        .collect::<Vec<_>>();
+    if overlap_list.is_empty() {
+        return Ok(vec![(start, end)]);
+    }
+    let overlap = || NoteConflict::Overlap(overlap_list.iter().map(|(id, _, _)| *id).collect());
+    if policy == ConflictPolicy::Reject || start == end {
+        return Err(overlap());
+    }
+    let cut = bucket::merge(
+        overlap_list
+            .iter()
+            .map(|(_, other_start, other_end)| (*other_start, *other_end))
+            .collect(),
+    );
+    let span_list = bucket::subtract(&[(start, end)], &cut);
+    if span_list.is_empty() {
+        return Err(overlap());
+    }
+    Ok(span_list)
+}
+
+/// Validates a manual note against the other notes, `id` is set when the note is updated.
+pub async fn check_note(
+    db: &DatabaseConnection,
+    id: Option<i64>,
+    span: (i64, i64),
+    now: i64,
+    policy: ConflictPolicy,
+) -> Result<std::result::Result<Vec<(i64, i64)>, NoteConflict>> {
+    let other_list = sql::select_note_list(db, span.0, span.1)
+        .await?
+        .into_iter()
+        .filter(|note| Some(note.id) != id)
+        .map(|note| (note.id, note.start, note.end))
+        .collect::<Vec<_>>();
+    Ok(resolve_note_conflict(span, now, &other_list, policy))
+}
+
+/// Creates a note for each of the spans returned by [`check_note`].
+pub async fn create_note(
+    db: &DatabaseConnection,
+    data: InsertNote,
+    span_list: Vec<(i64, i64)>,
+) -> Result<Vec<note::Model>> {
+    let txn = db.begin().await?;
+    let mut note_list = vec![];
+    for (start, end) in span_list {
+        note_list.push(
+            sql::create_note(
+                &txn,
+                InsertNote {
+                    start,
+                    end,
+                    ..data.clone()
+                },
+            )
+            .await?,
+        );
+    }
+    let mut journal = Journal::new("create note");
+    journal.created(JournalTable::Note, note_list.iter().map(|note| note.id));
+    journal.commit(&txn).await?;
+    txn.commit().await?;
+    Ok(note_list)
+}
+
+/// Moves the end of a running note to `now`, checked like any other change of the note.
+///
+/// With [`ConflictPolicy::Resolve`] the note ends where the next note starts and stays there.
+pub async fn extend_running_note(
+    db: &DatabaseConnection,
+    id: i64,
+    now: i64,
+    policy: ConflictPolicy,
+) -> Result<std::result::Result<(), NoteConflict>> {
+    let note = sql::select_note_by_id(db, id)
+        .await?
+        .ok_or(anyhow!("note {} not found", id))?;
+    let span_list =
+        match check_note(db, Some(id), (note.start, now.max(note.start)), now, policy).await? {
+            Ok(span_list) => span_list,
+            Err(conflict) => return Ok(Err(conflict)),
+        };
+    // the running note keeps its start, the parts after a note in its way are left out
+    if let Some((_, end)) = span_list.first().filter(|(start, _)| *start == note.start) {
+        sql::update_note_end(db, id, *end).await?;
+    }
+    Ok(Ok(()))
+}
+
+/// Moves the note to the first of the spans returned by [`check_note`], the rest become new
+/// notes of the same label.
+pub async fn update_note(
+    db: &DatabaseConnection,
+    id: i64,
+    span_list: Vec<(i64, i64)>,
+) -> Result<Vec<i64>> {
+    let note = sql::select_note_by_id(db, id)
+        .await?
+        .ok_or(anyhow!("note {} not found", id))?;
+    let txn = db.begin().await?;
+    let mut journal = Journal::new("update note");
+    journal
+        .track(&txn, JournalTable::Note, "id", vec![id])
+        .await?;
+    let mut id_list = vec![];
+    for (index, (start, end)) in span_list.into_iter().enumerate() {
+        if index == 0 {
+            sql::update_note_range(&txn, id, start, end).await?;
+            id_list.push(id);
+            continue;
+        }
+        let split = sql::create_note(
+            &txn,
+            InsertNote {
+                start,
+                end,
+                plan_id: note.plan_id,
+                label_id: note.label_id,
+                title: note.title.clone(),
+            },
+        )
+        .await?;
+        id_list.push(split.id);
+    }
+    journal.created(JournalTable::Note, id_list.iter().skip(1).copied());
+    journal.commit(&txn).await?;
+    txn.commit().await?;
+    Ok(id_list)
+}
+
+/// The watchers keep the end of a running activity up to date, it is edited once it stops.
+const RUNNING_ACTIVITY_TOLERANCE: i64 = 2 * 60 * 1000;
+
+/// Longest gap between merged activities, the watchers leave short ones when focus moves.
+const MERGE_ACTIVITY_GAP: i64 = 60 * 1000;
+
+async fn select_edited_activity(
+    db: &impl ConnectionTrait,
+    id: i64,
+    now: i64,
+) -> Result<activity::Model> {
+    let activity = sql::select_activity_list_by_id(db, vec![id])
+        .await?
+        .pop()
+        .ok_or(anyhow!("activity {} not found", id))?;
+    check_activity_stopped(&activity, now)?;
+    Ok(activity)
+}
+
+fn check_activity_stopped(activity: &activity::Model, now: i64) -> Result<()> {
+    if activity.end > now - RUNNING_ACTIVITY_TOLERANCE {
+        return Err(anyhow!("activity {} may still be running", activity.id).into());
+    }
+    Ok(())
+}
+
+/// Replaces the activities with new ones as one change in the journal, returns the new ones
+/// ordered by start.
+///
+/// Runs in the transaction the activities were read and checked in, so they cannot change in
+/// between.
+async fn apply_activity_edit(
+    txn: DatabaseTransaction,
+    action: &str,
+    replaced_id_list: Vec<i64>,
+    data_list: Vec<InsertActivity>,
+) -> Result<Vec<i64>> {
+    let mut journal = Journal::new(action);
+    journal
+        .track(&txn, JournalTable::Activity, "id", replaced_id_list.clone())
+        .await?;
+    let replaced_count = replaced_id_list.len() as u64;
+    if sql::remove_activity_list(&txn, replaced_id_list, Utc::now().timestamp_millis()).await?
+        != replaced_count
+    {
+        return Err(anyhow!("activities were removed while they were edited").into());
+    }
+    let id_list = sql::create_activity_list(&txn, data_list).await?;
+    journal.created(JournalTable::Activity, id_list.clone());
+    journal.commit(&txn).await?;
+    txn.commit().await?;
+    Ok(id_list)
+}
+
+/// Splits the activity in two at `at`.
+pub async fn split_activity(
+    db: &DatabaseConnection,
+    id: i64,
+    at: i64,
+    now: i64,
+) -> Result<Vec<i64>> {
+    let txn = db.begin().await?;
+    let activity = select_edited_activity(&txn, id, now).await?;
+    if at <= activity.start || at >= activity.end {
+        return Err(anyhow!("{} is not inside activity {}", at, id).into());
+    }
+    let part = |start, end| InsertActivity {
+        start,
+        end,
+        program_id: activity.program_id,
+    };
+    apply_activity_edit(
+        txn,
+        "split activity",
+        vec![id],
+        vec![part(activity.start, at), part(at, activity.end)],
+    )
+    .await
+}
+
+/// Span of the merged activities, they must share a program and follow each other with no
+/// other activity between.
+fn get_merge_span(
+    activity_list: &[activity::Model],
+    between_list: &[activity::Model],
+) -> Result<(i64, i64)> {
+    if activity_list.len() < 2 {
+        return Err(anyhow!("at least two activities are merged").into());
+    }
+    let first = &activity_list[0];
+    if let Some(activity) = activity_list
+        .iter()
+        .find(|activity| activity.program_id != first.program_id)
+    {
+        return Err(anyhow!("activity {} belongs to another program", activity.id).into());
+    }
+    let end = activity_list
+        .iter()
+        .map(|activity| activity.end)
+        .max()
+        .unwrap();
+    let id_list = activity_list
+        .iter()
+        .map(|activity| activity.id)
+        .collect::<Vec<_>>();
+    let gap_list = bucket::subtract(
+        &[(first.start, end)],
+        &bucket::merge(
+            activity_list
+                .iter()
+                .map(|activity| (activity.start, activity.end))
+                .collect(),
+        ),
+    );
+    if let Some(activity) = between_list.iter().find(|activity| {
+        !id_list.contains(&activity.id)
+            && gap_list
+                .iter()
+                .any(|(start, end)| activity.start < *end && activity.end > *start)
+    }) {
+        return Err(anyhow!("activity {} is between the merged activities", activity.id).into());
+    }
+    // the gaps would be counted as tracked time
+    if let Some((start, end)) = gap_list
+        .iter()
+        .find(|(start, end)| end - start > MERGE_ACTIVITY_GAP)
+    {
+        return Err(anyhow!("activities are {} ms apart at {}", end - start, start).into());
+    }
+    Ok((first.start, end))
+}
+
+/// Merges adjacent activities of the same program into one spanning them.
+pub async fn merge_activity(
+    db: &DatabaseConnection,
+    id_list: Vec<i64>,
+    now: i64,
+) -> Result<Vec<i64>> {
+    let txn = db.begin().await?;
+    let activity_list = sql::select_activity_list_by_id(&txn, id_list.clone()).await?;
+    if let Some(id) = id_list
+        .iter()
+        .find(|id| !activity_list.iter().any(|activity| activity.id == **id))
+    {
+        return Err(anyhow!("activity {} not found", id).into());
+    }
+    for activity in &activity_list {
+        check_activity_stopped(activity, now)?;
+    }
+    let start = activity_list
+        .iter()
+        .map(|activity| activity.start)
+        .min()
+        .unwrap_or_default();
+    let end = activity_list
+        .iter()
+        .map(|activity| activity.end)
+        .max()
+        .unwrap_or_default();
+    let between_list = sql::select_activity_list(&txn, start, end).await?;
+    let (start, end) = get_merge_span(&activity_list, &between_list)?;
+    let program_id = activity_list[0].program_id;
+    apply_activity_edit(
+        txn,
+        "merge activity",
+        id_list,
+        vec![InsertActivity {
+            start,
+            end,
+            program_id,
+        }],
+    )
+    .await
+}
+
+/// Moves the start or end of the activity inward, e.g. to cut a lunch break.
+pub async fn trim_activity(
+    db: &DatabaseConnection,
+    id: i64,
+    start: i64,
+    end: i64,
+    now: i64,
+) -> Result<Vec<i64>> {
+    let txn = db.begin().await?;
+    let activity = select_edited_activity(&txn, id, now).await?;
+    if start >= end || start < activity.start || end > activity.end {
+        return Err(anyhow!("{}..{} is not a part of activity {}", start, end, id).into());
+    }
+    if start == activity.start && end == activity.end {
+        return Err(anyhow!("activity {} is not trimmed", id).into());
+    }
+    apply_activity_edit(
+        txn,
+        "trim activity",
+        vec![id],
+        vec![InsertActivity {
+            start,
+            end,
+            program_id: activity.program_id,
+        }],
+    )
+    .await
+}
+
+/// Attributes the activity to another program.
+pub async fn reassign_activity(
+    db: &DatabaseConnection,
+    id: i64,
+    program_id: i64,
+    now: i64,
+) -> Result<Vec<i64>> {
+    let txn = db.begin().await?;
+    let activity = select_edited_activity(&txn, id, now).await?;
+    if activity.program_id == program_id {
+        return Err(anyhow!("activity {} already belongs to program {}", id, program_id).into());
+    }
+    sql::select_program_by_id(&txn, program_id)
+        .await?
+        .ok_or(anyhow!("program {} not found", program_id))?;
+    apply_activity_edit(
+        txn,
+        "reassign activity",
+        vec![id],
+        vec![InsertActivity {
+            start: activity.start,
+            end: activity.end,
+            program_id,
+        }],
+    )
+    .await
+}
+
+/// The program recorded for `path`, by its own path or else by the first matching alias.
+pub async fn resolve_program(
+    db: &DatabaseConnection,
+    path: &str,
+) -> Result<Option<program::Model>> {
+    if let Some(program) = sql::select_program_by_path(db, path.to_string()).await? {
+        return Ok(Some(program));
+    }
+    for (program_id, pattern) in get_alias_list(db).await?.iter() {
+        if pattern.is_match(path) {
+            // the program may be removed since the aliases were compiled
+            if let Some(program) = sql::select_program_by_id(db, *program_id).await? {
+                return Ok(Some(program));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// The compiled aliases, compiled again only when the alias table changed.
+async fn get_alias_list(db: &DatabaseConnection) -> Result<AliasList> {
+    let version = sql::select_program_alias_version(db).await?;
+    if let Some(list) = alias::get_cached(version) {
+        return Ok(list);
+    }
+    let mut list = vec![];
+    for (alias, program) in sql::select_program_alias_list(db).await? {
+        match AliasKind::try_from(alias.kind)
+            .and_then(|kind| PathPattern::new(&alias.pattern, kind))
+        {
+            Ok(pattern) => list.push((program.id, pattern)),
+            Err(e) => log::warn!("invalid program alias {}: {}", alias.id, e),
+        }
+    }
+    Ok(alias::set_cached(version, list))
+}
+
+/// Adds an alias of the program, the pattern must compile.
+pub async fn create_program_alias(
+    db: &DatabaseConnection,
+    program_id: i64,
+    pattern: String,
+    kind: i64,
+) -> Result<i64> {
+    PathPattern::new(&pattern, AliasKind::try_from(kind)?)?;
+    let txn = db.begin().await?;
+    sql::select_program_by_id(&txn, program_id)
+        .await?
+        .ok_or(anyhow!("program {} not found", program_id))?;
+    let id = sql::create_program_alias(
+        &txn,
+        InsertProgramAlias {
+            program_id,
+            pattern,
+            kind,
+        },
+    )
+    .await?;
+    let mut journal = Journal::new("create program alias");
+    journal.created(JournalTable::ProgramAlias, [id]);
+    journal.commit(&txn).await?;
+    txn.commit().await?;
+    Ok(id)
+}
+
+/// Moves the history of the sources to the target and removes them, their paths become aliases
+/// of the target.
+pub async fn merge_program(
+    db: &DatabaseConnection,
+    source_id_list: Vec<i64>,
+    target_id: i64,
+) -> Result<()> {
+    if source_id_list.is_empty() || source_id_list.contains(&target_id) {
+        return Err(anyhow!("program {} cannot be merged into itself", target_id).into());
+    }
+    let txn = db.begin().await?;
+    sql::select_program_by_id(&txn, target_id)
+        .await?
+        .ok_or(anyhow!("program {} not found", target_id))?;
+    let source_list = sql::select_program_list_by_id(&txn, source_id_list.clone()).await?;
+    if let Some(id) = source_id_list
+        .iter()
+        .find(|id| !source_list.iter().any(|program| program.id == **id))
+    {
+        return Err(anyhow!("program {} not found", id).into());
+    }
+    let mut journal = Journal::new("merge program");
+    journal
+        .track(&txn, JournalTable::Program, "id", source_id_list.clone())
+        .await?;
+    // the history of a program can be long, only the ids of its rows are kept
+    for table in [JournalTable::Activity, JournalTable::Remark] {
+        journal
+            .remap(&txn, table, "program_id", source_id_list.clone(), target_id)
+            .await?;
+    }
+    journal
+        .track(
+            &txn,
+            JournalTable::ProgramAlias,
+            "program_id",
+            source_id_list.clone(),
+        )
+        .await?;
+    journal
+        .track(
+            &txn,
+            JournalTable::DimensionProgram,
+            "program_id",
+            [source_id_list.clone(), vec![target_id]].concat(),
+        )
+        .await?;
+    sql::move_program_relation(&txn, source_id_list.clone(), target_id).await?;
+    for program in &source_list {
+        let alias_id = sql::create_program_alias(
+            &txn,
+            InsertProgramAlias {
+                program_id: target_id,
+                pattern: PathPattern::exact(&program.path),
+                kind: AliasKind::Regex as i64,
+            },
+        )
+        .await?;
+        journal.created(JournalTable::ProgramAlias, [alias_id]);
+    }
+    sql::remove_program_list(&txn, source_id_list).await?;
+    journal.commit(&txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+pub async fn get_or_create_program(
+    db: &DatabaseConnection,
+    name: String,
+    path: String,
+) -> Result<program::Model> {
+    if let Some(program) = resolve_program(db, &path).await? {
+        return Ok(program);
+    }
+    let txn = db.begin().await?;
+    let program = sql::create_program(
+        &txn,
+        InsertProgram {
+            name,
+            color: text_to_color(&path),
+            path,
+            icon: "".to_string(),
+            platform: OS.to_string(),
+        },
+    )
+    .await?;
+    let mut journal = Journal::new("create program");
+    journal.created(JournalTable::Program, [program.id]);
+    journal.commit(&txn).await?;
+    txn.commit().await?;
+    Ok(program)
+}
+
+/// Moves the label under `parent_id` in `plan_id`, or to the top of the plan without a parent.
+///
+/// Its children move along, with their notes when the plan changes.
+pub async fn move_label(
+    db: &DatabaseConnection,
+    id: i64,
+    plan_id: i64,
+    parent_id: Option<i64>,
+) -> Result<()> {
+    let txn = db.begin().await?;
+    let tree = LabelTree::new(sql::select_label_list(&txn).await?);
+    let mut journal = Journal::new("move label");
+    write_label_move(&txn, &mut journal, &tree, id, plan_id, parent_id).await?;
+    journal.commit(&txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+async fn write_label_move(
+    txn: &DatabaseTransaction,
+    journal: &mut Journal,
+    tree: &LabelTree,
+    id: i64,
+    plan_id: i64,
+    parent_id: Option<i64>,
+) -> Result<()> {
+    let label = tree.get(id).ok_or(anyhow!("label {} not found", id))?;
+    sql::select_plan_by_id(txn, plan_id)
+        .await?
+        .ok_or(anyhow!("plan {} not found", plan_id))?;
+    if let Some(parent_id) = parent_id {
+        let parent = tree
+            .get(parent_id)
+            .ok_or(anyhow!("label {} not found", parent_id))?;
+        if parent.plan_id != plan_id {
+            return Err(anyhow!("label {} is not in plan {}", parent_id, plan_id).into());
+        }
+        if parent_id == id || tree.ancestor_list(parent_id).contains(&id) {
+            return Err(anyhow!("label {} cannot be moved under itself", id).into());
+        }
+    }
+    journal
+        .track(txn, JournalTable::Label, "id", vec![id])
+        .await?;
+    sql::update_label_parent(txn, id, parent_id).await?;
+    if label.plan_id != plan_id {
+        let id_list = tree.subtree(id);
+        journal
+            .track(txn, JournalTable::Label, "id", id_list.clone())
+            .await?;
+        journal
+            .track(txn, JournalTable::Note, "label_id", id_list.clone())
+            .await?;
+        sql::update_label_plan(txn, id_list.clone(), plan_id).await?;
+        sql::update_note_plan(txn, id_list, plan_id).await?;
+    }
+    Ok(())
+}
+
+/// Fields of a label edited from the app, the ones left out stay as they are.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LabelPatch {
+    id: i64,
+    name: Option<String>,
+    color: Option<String>,
+    /// Moves the label to the top of the plan, see [`move_label`].
+    plan_id: Option<i64>,
+    sort: Option<i64>,
+    hidden: Option<bool>,
+}
+
+/// Edits the labels as one change, e.g. their order after a drag.
+pub async fn update_label_list(db: &DatabaseConnection, patch_list: Vec<LabelPatch>) -> Result<()> {
+    let txn = db.begin().await?;
+    let tree = LabelTree::new(sql::select_label_list(&txn).await?);
+    let mut journal = Journal::new("update label");
+    for patch in patch_list {
+        let label = tree
+            .get(patch.id)
+            .ok_or(anyhow!("label {} not found", patch.id))?;
+        if let Some(plan_id) = patch.plan_id.filter(|plan_id| *plan_id != label.plan_id) {
+            write_label_move(&txn, &mut journal, &tree, patch.id, plan_id, None).await?;
+        }
+        journal
+            .track(&txn, JournalTable::Label, "id", vec![patch.id])
+            .await?;
+        sql::update_label(
+            &txn,
+            patch.id,
+            UpdateLabel {
+                name: patch.name,
+                color: patch.color,
+                sort: patch.sort,
+                hidden: patch.hidden,
+            },
+        )
+        .await?;
+    }
+    journal.commit(&txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+/// Creates a label at the end of the list.
+pub async fn create_label(db: &DatabaseConnection, data: InsertLabel) -> Result<label::Model> {
+    let txn = db.begin().await?;
+    let label = sql::create_label(&txn, data).await?;
+    let mut journal = Journal::new("create label");
+    journal.created(JournalTable::Label, [label.id]);
+    journal.commit(&txn).await?;
+    txn.commit().await?;
+    Ok(label)
+}
+
+/// Removes the labels, with their notes, time blocks and templates when `relation` is set.
+///
+/// Children of a label removed with its relation move up to its parent.
+pub async fn remove_label(
+    db: &DatabaseConnection,
+    id_list: Vec<i64>,
+    relation: bool,
+) -> Result<()> {
+    let txn = db.begin().await?;
+    let tree = LabelTree::new(sql::select_label_list(&txn).await?);
+    let mut journal = Journal::new("remove label");
+    journal
+        .track(&txn, JournalTable::Label, "id", id_list.clone())
+        .await?;
+    if relation {
+        journal
+            .track(&txn, JournalTable::Label, "parent_id", id_list.clone())
+            .await?;
+        for (table, column) in [
+            (JournalTable::Note, "label_id"),
+            (JournalTable::TimeBlock, "label_id"),
+            (JournalTable::NoteTemplate, "label_id"),
+        ] {
+            journal.track(&txn, table, column, id_list.clone()).await?;
+        }
+    }
+    sql::remove_label_list(&txn, id_list.clone()).await?;
+    if relation {
+        for id in &id_list {
+            let parent_id = tree.get(*id).and_then(|label| label.parent_id);
+            sql::update_label_children_parent(&txn, *id, parent_id).await?;
+        }
+        sql::remove_note_by_label(&txn, id_list.clone()).await?;
+        sql::remove_time_block_by_label(&txn, id_list.clone()).await?;
+        sql::remove_note_template_by_label(&txn, id_list).await?;
+    }
+    journal.commit(&txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+/// Fields of a program edited from the app, the ones left out stay as they are.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramPatch {
+    id: i64,
+    name: Option<String>,
+    color: Option<String>,
+    path: Option<String>,
+    sort: Option<i64>,
+    hidden: Option<bool>,
+}
+
+/// Edits the programs as one change, e.g. their order after a drag.
+pub async fn update_program_list(
+    db: &DatabaseConnection,
+    patch_list: Vec<ProgramPatch>,
+) -> Result<()> {
+    let txn = db.begin().await?;
+    let mut journal = Journal::new("update program");
+    for patch in patch_list {
+        journal
+            .track(&txn, JournalTable::Program, "id", vec![patch.id])
+            .await?;
+        sql::update_program(
+            &txn,
+            patch.id,
+            UpdateProgram {
+                name: patch.name,
+                color: patch.color,
+                path: patch.path,
+                sort: patch.sort,
+                hidden: patch.hidden,
+            },
+        )
+        .await?;
+    }
+    journal.commit(&txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+/// Removes the programs, with their activities and aliases when `relation` is set.
+pub async fn remove_program(
+    db: &DatabaseConnection,
+    id_list: Vec<i64>,
+    relation: bool,
+) -> Result<()> {
+    let txn = db.begin().await?;
+    let mut journal = Journal::new("remove program");
+    journal
+        .track(&txn, JournalTable::Program, "id", id_list.clone())
+        .await?;
+    sql::remove_program_list(&txn, id_list.clone()).await?;
+    if relation {
+        // the history of a program can be long, only the ids of its activities are kept
+        let now = Utc::now().timestamp_millis();
+        let activity_id_list =
+            sql::select_activity_id_list_by_program(&txn, id_list.clone()).await?;
+        sql::remove_activity_list(&txn, activity_id_list.clone(), now).await?;
+        journal.remapped(
+            JournalTable::Activity,
+            "deleted_at",
+            0,
+            now,
+            activity_id_list,
+        );
+        journal
+            .track(
+                &txn,
+                JournalTable::ProgramAlias,
+                "program_id",
+                id_list.clone(),
+            )
+            .await?;
+        sql::remove_program_alias_by_program(&txn, id_list).await?;
+    }
+    journal.commit(&txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+/// Removes a note edited in the app.
+pub async fn remove_note(db: &DatabaseConnection, id: i64) -> Result<()> {
+    let txn = db.begin().await?;
+    let mut journal = Journal::new("remove note");
+    journal
+        .track(&txn, JournalTable::Note, "id", vec![id])
+        .await?;
+    sql::remove_note(&txn, id).await?;
+    journal.commit(&txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+/// Removes the boxes, with their moments when `relation` is set.
+pub async fn remove_box(db: &DatabaseConnection, id_list: Vec<i64>, relation: bool) -> Result<()> {
+    let txn = db.begin().await?;
+    let mut journal = Journal::new("remove box");
+    journal
+        .track(&txn, JournalTable::Box, "id", id_list.clone())
+        .await?;
+    if relation {
+        journal
+            .track(&txn, JournalTable::Moment, "box_id", id_list.clone())
+            .await?;
+    }
+    sql::remove_box_list(&txn, id_list.clone()).await?;
+    if relation {
+        sql::remove_moment_by_box(&txn, id_list).await?;
+    }
+    journal.commit(&txn).await?;
+    txn.commit().await?;
+    Ok(())
+}
+
+pub async fn get_or_create_label(
+    db: &DatabaseConnection,
+    name: String,
+    plan_id: i64,
+) -> Result<label::Model> {
+    if let Some(label) = sql::select_label_by_name(db, name.clone()).await? {
+        return Ok(label);
+    }
+    create_label(
+        db,
+        InsertLabel {
+            color: text_to_color(&name),
+            name,
+            plan_id,
+        },
+    )
+    .await
+}
+
+pub async fn create_remark(db: &DatabaseConnection, data: InsertRemark) -> Result<remark::Model> {
+    let txn = db.begin().await?;
+    let remark = sql::create_remark(&txn, data).await?;
+    let mut journal = Journal::new("create remark");
+    journal.created(JournalTable::Remark, [remark.id]);
+    journal.commit(&txn).await?;
+    txn.commit().await?;
+    Ok(remark)
+}
+
+fn text_to_color(text: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    format!("#{:06X}", hasher.finish() & 0xFFFFFF)
+}
+
+#[derive(Clone, Copy)]
+pub enum CalendarKind {
+    Plan,
+    Dimension,
+}
+
+pub struct Calendar {
+    pub name: String,
+    pub color: String,
+}
+
+pub struct CalendarEvent {
+    pub uid: String,
+    pub summary: String,
+    pub start: i64,
+    pub end: i64,
+}
+
+/// Activities of the same program closer than this are shown as one event.
+const ACTIVITY_MERGE_GAP: i64 = 5 * 60 * 1000;
+
+pub async fn get_calendar(
+    db: &DatabaseConnection,
+    kind: CalendarKind,
+    id: i64,
+) -> Result<Option<Calendar>> {
+    Ok(match kind {
+        CalendarKind::Plan => sql::select_plan_by_id(db, id).await?.map(|plan| Calendar {
+            name: plan.name,
+            color: plan.color,
+        }),
+        CalendarKind::Dimension => {
+            sql::select_dimension_by_id(db, id)
+                .await?
+                .map(|dimension| Calendar {
+                    name: dimension.name,
+                    color: dimension.color,
+                })
+        }
+    })
+}
+
+pub async fn get_calendar_list(
+    db: &DatabaseConnection,
+    kind: CalendarKind,
+) -> Result<Vec<(i64, Calendar)>> {
+    Ok(match kind {
+        CalendarKind::Plan => sql::select_plan_list(db)
+            .await?
+            .into_iter()
+            .map(|plan| {
+                (
+                    plan.id,
+                    Calendar {
+                        name: plan.name,
+                        color: plan.color,
+                    },
+                )
+            })
+            .collect(),
+        CalendarKind::Dimension => sql::select_dimension_list(db)
+            .await?
+            .into_iter()
+            .map(|dimension| {
+                (
+                    dimension.id,
+                    Calendar {
+                        name: dimension.name,
+                        color: dimension.color,
+                    },
+                )
+            })
+            .collect(),
+    })
+}
+
+pub async fn get_calendar_events(
+    db: &DatabaseConnection,
+    kind: CalendarKind,
+    id: i64,
+    start: i64,
+    end: i64,
+) -> Result<Vec<CalendarEvent>> {
+    let (note_list, activity_list) = match kind {
+        CalendarKind::Plan => (
+            sql::select_note_for_plan_calendar(db, id, start, end).await?,
+            vec![],
+        ),
+        CalendarKind::Dimension => try_join!(
+            sql::select_note_for_dimension_calendar(db, id, start, end),
+            sql::select_activity_for_dimension_calendar(db, id, start, end)
+        )?,
+    };
+
+    let mut list = note_list
+        .into_iter()
+        .map(|note| CalendarEvent {
+            uid: format!("note-{}", note.id),
+            summary: note.name,
+            start: note.start,
+            end: note.end,
+        })
+        .chain(merge_activity_spans(activity_list, ACTIVITY_MERGE_GAP))
+        .collect::<Vec<_>>();
+    list.sort_by_key(|event| event.start);
+    Ok(list)
+}
+
+/// The event with `uid` in the calendar, looked up by the row it comes from at any time.
+pub async fn get_calendar_event(
+    db: &DatabaseConnection,
+    kind: CalendarKind,
+    id: i64,
+    uid: &str,
+) -> Result<Option<CalendarEvent>> {
+    let find = |list: Vec<CalendarEvent>| list.into_iter().find(|event| event.uid == uid);
+    if let Some(note_id) = uid.strip_prefix("note-").and_then(|id| id.parse().ok()) {
+        let Some(note) = sql::select_note_by_id(db, note_id).await? else {
+            return Ok(None);
+        };
+        return Ok(find(
+            get_calendar_events(db, kind, id, note.start, note.end.max(note.start + 1)).await?,
+        ));
+    }
+    let Some(activity_id) = uid.strip_prefix("activity-").and_then(|id| id.parse().ok()) else {
+        return Ok(None);
+    };
+    let Some(activity) = sql::select_activity_list_by_id(db, vec![activity_id])
+        .await?
+        .pop()
+    else {
+        return Ok(None);
+    };
+    // an activity right after another one of its program is part of that span, not its head
+    let start = activity.start - ACTIVITY_MERGE_GAP;
+    // the span goes on as long as the next activity starts within the gap
+    let mut end = activity.end + ACTIVITY_MERGE_GAP + 1;
+    loop {
+        let Some(event) = find(get_calendar_events(db, kind, id, start, end).await?) else {
+            return Ok(None);
+        };
+        if event.end + ACTIVITY_MERGE_GAP < end {
+            return Ok(Some(event));
+        }
+        end = event.end + ACTIVITY_MERGE_GAP + 1;
+    }
+}
+
+/// Joins activities of the same program into spans, expects the list ordered by start.
+fn merge_activity_spans(list: Vec<CalendarEventResult>, gap: i64) -> Vec<CalendarEvent> {
+    let mut spans: Vec<CalendarEvent> = vec![];
+    let mut last_span_of_program: HashMap<i64, usize> = HashMap::new();
+
+    for activity in list {
+        if let Some(&index) = last_span_of_program.get(&activity.group_id) {
+            let span = &mut spans[index];
+            if activity.start <= span.end + gap {
+                span.end = span.end.max(activity.end);
+                continue;
+            }
+        }
+        last_span_of_program.insert(activity.group_id, spans.len());
+        spans.push(CalendarEvent {
+            uid: format!("activity-{}", activity.id),
+            summary: activity.name,
+            start: activity.start,
+            end: activity.end,
+        });
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{fixture, journal};
+
+    fn item(key: &str, start: i64, end: i64) -> DailyStatusResult {
+        DailyStatusResult {
+            name: key.to_string(),
+            color: "#000000".to_string(),
+            key: key.to_string(),
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let edges = vec![0, 100, 200];
+        let list = vec![
+            // crosses the edge between the buckets
+            item("program_1", 50, 150),
+            // overlaps the row above
+            item("program_1", 120, 180),
+            item("label_1", 160, 190),
+            // outside of the range
+            item("label_1", 200, 300),
+        ];
+        let result = aggregate(list, &edges, true);
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].status.total, 50);
+        assert_eq!(result[0].status.busy, Some(50));
+        assert_eq!(result[1].status.total, 80 + 30);
+        assert_eq!(result[1].status.busy, Some(90));
+        assert_eq!(result[1].status.list[0].total, 80);
+        assert_eq!(result[1].status.list[1].total, 30);
+    }
+
+    #[test]
+    fn test_aggregate_multiple_dimensions() {
+        // a label in two dimensions and a program in one of them
+        let list = vec![
+            item("dimension_1", 0, 60),
+            item("dimension_2", 0, 60),
+            item("dimension_1", 30, 90),
+        ];
+        let result = aggregate(list, &[0, 100], true);
+        let status = &result[0].status;
+        assert_eq!(status.list[0].total, 90);
+        assert_eq!(status.list[1].total, 60);
+        assert_eq!(status.busy, Some(90));
+    }
+
+    #[test]
+    fn test_detect_work_sessions() {
+        let activity_list = vec![
+            item("program_1", 0, 40),
+            item("program_2", 30, 60),
+            item("program_2", 70, 100),
+            // the gap before is longer than the threshold
+            item("program_1", 200, 220),
+        ];
+        let note_list = vec![item("label_1", 50, 150), item("label_2", 80, 100)];
+        let result = detect_work_sessions(activity_list, note_list, 10);
+        assert_eq!(result.len(), 2);
+        assert_eq!((result[0].start, result[0].end), (0, 100));
+        assert_eq!(result[0].active_time, 90);
+        assert_eq!(result[0].break_time, 10);
+        assert_eq!(result[0].program.as_ref().unwrap().key, "program_2");
+        assert_eq!(result[0].label.as_ref().unwrap().key, "label_1");
+        assert_eq!(result[1].active_time, 20);
+        assert!(result[1].label.is_none());
+    }
+
+    #[test]
+    fn test_measure_focus() {
+        let list = vec![
+            item("program_1", 0, 30),
+            item("program_1", 35, 50),
+            item("program_2", 50, 60),
+            item("program_1", 60, 70),
+            // the gap before is longer than the threshold, not a switch
+            item("program_2", 100, 120),
+        ];
+        let result = measure_focus(list, (0, 200), 10);
+        assert_eq!(result.switch_count, 2);
+        assert_eq!(result.longest_focus, 50);
+        // spans of 50, 10, 10 and 20
+        assert_eq!(result.median_focus, 15);
+    }
+
+    #[test]
+    fn test_measure_focus_in_dimensions() {
+        // the second activity stays in dimension 2
+        let list = vec![
+            item("dimension_1", 0, 10),
+            item("dimension_2", 0, 10),
+            item("dimension_2", 10, 30),
+            item("dimension_3", 30, 40),
+        ];
+        let result = measure_focus(list, (0, 100), 0);
+        assert_eq!(result.switch_count, 1);
+        assert_eq!(result.longest_focus, 30);
+        assert_eq!(result.median_focus, 10);
+    }
+
+    #[test]
+    fn test_break_down_programs() {
+        let span_list = vec![(0, 100), (200, 300)];
+        let activity_list = vec![
+            // crosses both notes
+            item("program_1", 50, 250),
+            item("program_2", 90, 110),
+            item("program_2", 280, 320),
+            // overlaps the first row of the program
+            item("program_1", 60, 80),
+            // between the notes
+            item("program_3", 120, 180),
+        ];
+        let result = break_down_programs(&span_list, activity_list);
+        assert_eq!(result.total, 200);
+        assert_eq!(result.list.len(), 2);
+        assert_eq!(result.list[0].key, "program_1");
+        assert_eq!(result.list[0].total, 100);
+        assert_eq!(result.list[1].total, 30);
+    }
+
+    fn goal(duration: i64, at_most: bool) -> goal::Model {
+        goal::Model {
+            id: 1,
+            target_type: 0,
+            target_id: 1,
+            period: 0,
+            duration,
+            at_most,
+            start: 0,
+            sort: 1,
+            deleted_at: 0,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_evaluate_goal() {
+        let total_list = vec![
+            (0, 10, 5),
+            (10, 20, 5),
+            (20, 30, 0),
+            (30, 40, 6),
+            (40, 50, 3),
+        ];
+        // at least 5
+        let result = evaluate_goal(&goal(5, false), total_list.clone());
+        assert_eq!(result.progress, 3);
+        assert_eq!(result.longest_streak, 2);
+        // the current period is not met yet, but is not missed either
+        assert_eq!(result.current_streak, 1);
+        assert!(!result.period_list[4].met);
+        // at most 5
+        let result = evaluate_goal(&goal(5, true), total_list);
+        assert_eq!(result.longest_streak, 3);
+        assert_eq!(result.current_streak, 0);
+        assert!(result.period_list[4].met);
+    }
+
+    #[test]
+    fn test_evaluate_goal_met_in_current_period() {
+        let result = evaluate_goal(&goal(5, false), vec![(0, 10, 5), (10, 20, 8)]);
+        assert_eq!(result.current_streak, 2);
+        assert_eq!(result.longest_streak, 2);
+    }
+
+    #[test]
+    fn test_check_usage_limit() {
+        let limit = |duration: i64, warning_percent: i64| usage_limit::Model {
+            id: 1,
+            target_type: 0,
+            target_id: 1,
+            period: 0,
+            duration,
+            warning_percent,
+            sort: 0,
+            deleted_at: 0,
+            created_at: 0,
+            updated_at: 0,
+        };
+        assert_eq!(
+            check_usage_limit(&limit(60_000, 80)).unwrap(),
+            BucketUnit::Day
+        );
+        assert!(check_usage_limit(&limit(0, 80)).is_err());
+        assert!(check_usage_limit(&limit(-60_000, 80)).is_err());
+        assert!(check_usage_limit(&limit(60_000, 120)).is_err());
+    }
+
+    #[test]
+    fn test_get_limit_level() {
+        assert_eq!(get_limit_level(79, 100, 80), None);
+        assert_eq!(get_limit_level(80, 100, 80), Some(LimitLevel::Warning));
+        assert_eq!(get_limit_level(100, 100, 80), Some(LimitLevel::Exceeded));
+        assert_eq!(get_limit_level(100, 100, 100), Some(LimitLevel::Exceeded));
+    }
+
+    #[test]
+    fn test_find_work_start() {
+        let span_list = vec![(0, 50), (100, 150), (155, 170), (172, 190)];
+        // the gap before 100 is a break
+        assert_eq!(find_work_start(span_list.clone(), 195, 20), Some(100));
+        assert_eq!(find_work_start(span_list.clone(), 195, 60), Some(0));
+        // no activity since the end of the last span
+        assert_eq!(find_work_start(span_list, 210, 20), None);
+        assert_eq!(find_work_start(vec![], 0, 20), None);
+    }
+
+    #[test]
+    fn test_expand_time_block() {
+        let clock = Clock::new(Tz::UTC, 0);
+        const DAY: i64 = 24 * 60 * 60 * 1000;
+        // 1970-01-01 was a Thursday
+        let block = time_block::Model {
+            id: 1,
+            label_id: 1,
+            start: 9 * 60 * 60 * 1000,
+            end: 10 * 60 * 60 * 1000,
+            rrule: Some("FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR;UNTIL=19700110T235959Z".to_string()),
+            sort: 0,
+            deleted_at: 0,
+            created_at: 0,
+            updated_at: 0,
+        };
+        let list = expand_time_block(&block, &clock, (DAY, 20 * DAY)).unwrap();
+        let day_list = list
+            .iter()
+            .map(|(start, _)| start / DAY)
+            .collect::<Vec<_>>();
+        assert_eq!(day_list, vec![1, 4, 5, 6, 7, 8]);
+        let range = (block.start + DAY * 4 + 1000, DAY * 5);
+        let list = expand_time_block(&block, &clock, range).unwrap();
+        assert_eq!(list, vec![(range.0, block.end + DAY * 4)]);
+    }
+
+    #[tokio::test]
+    async fn test_time_block_recurrence_migration() {
+        let db = fixture::setup_before("20250324102210_").await;
+        db.execute_unprepared(
+            r#"
+            INSERT INTO "plan" ("name", "color") VALUES ('plan', '#000000');
+            INSERT INTO "label" ("name", "color", "plan_id") VALUES ('label', '#000000', 1);
+            INSERT INTO "time_block" ("label_id", "start", "end", "recurrence", "recurrence_end")
+            VALUES (1, 0, 1000, 0, NULL), (1, 0, 1000, 1, NULL), (1, 0, 1000, 2, 864000000),
+                (1, 0, 1000, 3, NULL);
+            "#,
+        )
+        .await
+        .unwrap();
+        fixture::migrate_from(&db, "20250324102210_").await;
+        let rrule_list = sql::select_time_block_list(&db, 0, 1000)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|block| block.rrule)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rrule_list,
+            vec![
+                None,
+                Some("FREQ=DAILY".to_string()),
+                Some("FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR;UNTIL=19700110T235959Z".to_string()),
+                Some("FREQ=WEEKLY".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare_time_blocks() {
+        let occurrence_list = vec![(1, 1, (0, 100)), (1, 1, (150, 200)), (2, 2, (300, 400))];
+        let note_list = vec![
+            // runs into the next block of the label
+            (1, (50, 180)),
+            // another label during the block
+            (2, (90, 120)),
+            // past the end of the block
+            (2, (350, 450)),
+        ];
+        let result = compare_time_blocks(occurrence_list, note_list, vec![(0, 40)], &[0, 250, 500]);
+        assert_eq!(result[0].planned, 150);
+        assert_eq!(result[0].tracked, 50 + 30);
+        assert_eq!(result[0].overrun, 50);
+        assert_eq!(result[0].occurrence_list[0].active, 40);
+        assert_eq!(result[0].unplanned, 30);
+        assert_eq!(result[1].overrun, 50);
+        assert_eq!(result[1].unplanned, 0);
+        assert_eq!(result[1].adherence, Some(0.5));
+    }
+
+    #[test]
+    fn test_apply_note_template_exceptions() {
+        let template = note_template::Model {
+            id: 1,
+            label_id: 1,
+            start: 100,
+            end: 115,
+            rrule: Some("FREQ=DAILY".to_string()),
+            materialized_until: 0,
+            sort: 0,
+            deleted_at: 0,
+            created_at: 0,
+            updated_at: 0,
+        };
+        let exception = |occurrence, skip, start, end| note_template_exception::Model {
+            id: 1,
+            note_template_id: 1,
+            occurrence,
+            skip,
+            start,
+            end,
+            deleted_at: 0,
+            created_at: 0,
+            updated_at: 0,
+        };
+        let exception_list = vec![
+            exception(200, true, None, None),
+            exception(300, false, Some(310), Some(330)),
+        ];
+        let result =
+            apply_note_template_exceptions(&template, vec![100, 200, 300], &exception_list);
+        assert_eq!((result[0].start, result[0].end), (100, 115));
+        assert!(result[1].skipped);
+        assert_eq!((result[2].start, result[2].end), (310, 330));
+        assert_eq!(result[2].occurrence, 300);
+    }
+
+    #[test]
+    fn test_resolve_note_conflict() {
+        let other_list = vec![(1, 100, 200), (2, 300, 400), (3, 150, 150)];
+        let reject = |span| resolve_note_conflict(span, 0, &other_list, ConflictPolicy::Reject);
+        let resolve = |span| resolve_note_conflict(span, 0, &other_list, ConflictPolicy::Resolve);
+        assert_eq!(reject((200, 300)), Ok(vec![(200, 300)]));
+        assert_eq!(reject((300, 200)), Err(NoteConflict::Inverted));
+        assert_eq!(reject((50, 350)), Err(NoteConflict::Overlap(vec![1, 2])));
+        assert_eq!(resolve((50, 350)), Ok(vec![(50, 100), (200, 300)]));
+        assert_eq!(resolve((120, 180)), Err(NoteConflict::Overlap(vec![1])));
+        // a running note may start where another ends, not inside it
+        assert_eq!(reject((200, 200)), Ok(vec![(200, 200)]));
+        assert_eq!(resolve((150, 150)), Err(NoteConflict::Overlap(vec![1])));
+
+        let limit = NOTE_FUTURE_LIMIT;
+        assert_eq!(reject((500, limit + 1)), Err(NoteConflict::FarFuture));
+        assert_eq!(resolve((500, limit + 1)), Ok(vec![(500, limit)]));
+        assert_eq!(
+            resolve((limit + 1, limit + 2)),
+            Err(NoteConflict::FarFuture)
+        );
+    }
+
+    #[test]
+    fn test_get_merge_span() {
+        let activity = |id, program_id, start, end| activity::Model {
+            id,
+            start,
+            end,
+            program_id,
+            deleted_at: 0,
+            created_at: 0,
+            updated_at: 0,
+        };
+        let list = vec![activity(1, 1, 0, 100), activity(2, 1, 150, 200)];
+        assert_eq!(get_merge_span(&list, &list).unwrap(), (0, 200));
+        // another program was used in the gap
+        let between_list = vec![activity(3, 2, 100, 120)];
+        assert!(get_merge_span(&list, &between_list).is_err());
+        // overlapping the merged activities is not in the gap
+        let between_list = vec![activity(3, 2, 50, 100)];
+        assert_eq!(get_merge_span(&list, &between_list).unwrap(), (0, 200));
+        assert!(get_merge_span(&list[..1], &[]).is_err());
+        // nothing was recorded in a long gap
+        let list = vec![
+            activity(1, 1, 0, 100),
+            activity(2, 1, 100 + MERGE_ACTIVITY_GAP + 1, 200 + MERGE_ACTIVITY_GAP),
+        ];
+        assert!(get_merge_span(&list, &[]).is_err());
+        let list = vec![activity(1, 1, 0, 100), activity(2, 2, 100, 200)];
+        assert!(get_merge_span(&list, &[]).is_err());
+    }
+
+    #[test]
+    fn test_label_tree() {
+        let label = |id, parent_id| label::Model {
+            id,
+            name: format!("label {}", id),
+            color: "#000000".to_string(),
+            sort: 0,
+            plan_id: 1,
+            parent_id,
+            hidden: false,
+            deleted_at: 0,
+            created_at: 0,
+            updated_at: 0,
+        };
+        // 1 > 2 > 3, 4 has a removed parent, 5 and 6 form a cycle
+        let tree = LabelTree::new(vec![
+            label(1, None),
+            label(2, Some(1)),
+            label(3, Some(2)),
+            label(4, Some(99)),
+            label(5, Some(6)),
+            label(6, Some(5)),
+        ]);
+        assert_eq!(tree.ancestor_list(3), vec![2, 1]);
+        assert!(tree.ancestor_list(4).is_empty());
+        assert_eq!(tree.ancestor_list(5), vec![6]);
+        let mut subtree = tree.subtree(1);
+        subtree.sort();
+        assert_eq!(subtree, vec![1, 2, 3]);
+
+        let list = vec![item("label_3", 0, 10), item("program_1", 0, 10)];
+        let keys = |list: Vec<DailyStatusResult>| {
+            list.into_iter().map(|item| item.key).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            keys(tree.roll_up(list.clone())),
+            vec!["label_2", "label_1", "label_3", "program_1"]
+        );
+        assert_eq!(
+            keys(tree.collapse(list.clone(), 0)),
+            vec!["label_1", "program_1"]
+        );
+        assert_eq!(
+            keys(tree.collapse(list.clone(), 1)),
+            vec!["label_2", "program_1"]
+        );
+        assert_eq!(keys(tree.collapse(list, 5)), vec!["label_3", "program_1"]);
+    }
+
+    fn local(datetime: &str) -> i64 {
+        chrono::DateTime::parse_from_rfc3339(datetime)
+            .unwrap()
+            .timestamp_millis()
+    }
+
+    fn day_edges(range: (i64, i64)) -> Vec<i64> {
+        Clock::new(chrono_tz::Asia::Shanghai, 0)
+            .edges(BucketUnit::Day, range)
+            .unwrap()
+    }
+
+    fn totals(list: Vec<BucketStatus>) -> Vec<i64> {
+        list.iter().map(|bucket| bucket.status.total).collect()
+    }
+
+    #[test]
+    fn test_aggregate_clips_to_range() {
+        let range = (
+            local("2024-08-02T00:00:00+08:00"),
+            local("2024-08-03T00:00:00+08:00"),
+        );
+        let list = vec![
+            // crosses the midnight at the range start
+            item(
+                "label_1",
+                local("2024-08-01T23:00:00+08:00"),
+                local("2024-08-02T01:00:00+08:00"),
+            ),
+            // still running past the range end
+            item(
+                "label_1",
+                local("2024-08-02T23:30:00+08:00"),
+                local("2024-08-03T02:00:00+08:00"),
+            ),
+        ];
+        let hour = 60 * 60 * 1000;
+        assert_eq!(
+            totals(aggregate(list, &day_edges(range), false)),
+            vec![hour + hour / 2]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_splits_at_midnight() {
+        let range = (
+            local("2024-08-01T00:00:00+08:00"),
+            local("2024-08-04T00:00:00+08:00"),
+        );
+        let list = vec![item(
+            "label_1",
+            local("2024-08-01T22:00:00+08:00"),
+            local("2024-08-03T02:00:00+08:00"),
+        )];
+        let hour = 60 * 60 * 1000;
+        assert_eq!(
+            totals(aggregate(list, &day_edges(range), false)),
+            vec![2 * hour, 24 * hour, 2 * hour]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_skips_empty_interval() {
+        let range = (
+            local("2024-08-02T00:00:00+08:00"),
+            local("2024-08-03T00:00:00+08:00"),
+        );
+        let list = vec![
+            item(
+                "label_1",
+                local("2024-08-01T10:00:00+08:00"),
+                local("2024-08-01T11:00:00+08:00"),
+            ),
+            item(
+                "label_1",
+                local("2024-08-02T10:00:00+08:00"),
+                local("2024-08-02T10:00:00+08:00"),
+            ),
+        ];
+        let result = aggregate(list, &day_edges(range), false);
+        assert_eq!(totals(result.clone()), vec![0]);
+        assert!(result[0].status.list.is_empty());
+    }
+
+    /// A label and a program, both with id 1.
+    async fn setup_daily_status() -> DatabaseConnection {
+        let db = fixture::setup().await;
+        db.execute_unprepared(
+            r#"
+            INSERT INTO "plan" ("name", "color") VALUES ('plan', '#000000');
+            INSERT INTO "label" ("name", "color", "plan_id") VALUES ('label', '#000000', 1);
+            INSERT INTO "program" ("name", "color", "path", "icon", "platform")
+                VALUES ('program', '#000000', 'program', '', '');
+            "#,
+        )
+        .await
+        .unwrap();
+        db
+    }
+
+    async fn insert_note(db: &DatabaseConnection, start: i64, end: i64) {
+        db.execute_unprepared(&format!(
+            r#"INSERT INTO "note" ("start", "end", "plan_id", "label_id") VALUES ({}, {}, 1, 1)"#,
+            start, end
+        ))
+        .await
+        .unwrap();
+    }
+
+    async fn insert_activity(db: &DatabaseConnection, start: i64, end: i64) {
+        db.execute_unprepared(&format!(
+            r#"INSERT INTO "activity" ("start", "end", "program_id") VALUES ({}, {}, 1)"#,
+            start, end
+        ))
+        .await
+        .unwrap();
+    }
+
+    async fn daily_status(
+        db: &DatabaseConnection,
+        clock: &Clock,
+        range: (i64, i64),
+        busy: bool,
+    ) -> Vec<(String, String, i64)> {
+        let day_list = get_day_list(clock, range).unwrap();
+        sql::select_daily_status(db, range.0, range.1, &day_list, busy)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|status| (status.date, status.key, status.total))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_select_daily_status_day_start() {
+        let db = setup_daily_status().await;
+        let hour = 60 * 60 * 1000;
+        // before 4 o'clock, still the day before
+        insert_note(
+            &db,
+            local("2024-08-02T02:00:00+00:00"),
+            local("2024-08-02T03:00:00+00:00"),
+        )
+        .await;
+        insert_activity(
+            &db,
+            local("2024-08-02T05:00:00+00:00"),
+            local("2024-08-02T07:00:00+00:00"),
+        )
+        .await;
+        let range = (
+            local("2024-08-01T04:00:00+00:00"),
+            local("2024-08-03T04:00:00+00:00"),
+        );
+        assert_eq!(
+            daily_status(&db, &Clock::new(Tz::UTC, 4), range, false).await,
+            vec![
+                ("2024-08-01".to_string(), "label_1".to_string(), hour),
+                ("2024-08-02".to_string(), "program_1".to_string(), 2 * hour),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_select_daily_status_across_midnight() {
+        let db = setup_daily_status().await;
+        let hour = 60 * 60 * 1000;
+        insert_note(
+            &db,
+            local("2024-08-01T23:00:00+00:00"),
+            local("2024-08-02T01:00:00+00:00"),
+        )
+        .await;
+        insert_activity(
+            &db,
+            local("2024-08-01T23:30:00+00:00"),
+            local("2024-08-02T00:30:00+00:00"),
+        )
+        .await;
+        let range = (
+            local("2024-08-01T00:00:00+00:00"),
+            local("2024-08-03T00:00:00+00:00"),
+        );
+        let mut result = daily_status(&db, &Clock::new(Tz::UTC, 0), range, true).await;
+        result.sort();
+        assert_eq!(
+            result,
+            vec![
+                // the busy time counts the overlap once
+                ("2024-08-01".to_string(), "".to_string(), hour),
+                ("2024-08-01".to_string(), "label_1".to_string(), hour),
+                ("2024-08-01".to_string(), "program_1".to_string(), hour / 2),
+                ("2024-08-02".to_string(), "".to_string(), hour),
+                ("2024-08-02".to_string(), "label_1".to_string(), hour),
+                ("2024-08-02".to_string(), "program_1".to_string(), hour / 2),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_select_daily_status_skips_deleted() {
+        let db = setup_daily_status().await;
+        let hour = 60 * 60 * 1000;
+        let start = local("2024-08-01T10:00:00+00:00");
+        insert_note(&db, start, start + hour).await;
+        insert_activity(&db, start, start + hour).await;
+        insert_activity(&db, start + 2 * hour, start + 3 * hour).await;
+        db.execute_unprepared(
+            r#"
+            UPDATE "note" SET "deleted_at" = 1;
+            UPDATE "activity" SET "deleted_at" = 1 WHERE "id" = 2;
+            "#,
+        )
+        .await
+        .unwrap();
+        let clock = Clock::new(Tz::UTC, 0);
+        let range = (
+            local("2024-08-01T00:00:00+00:00"),
+            local("2024-08-02T00:00:00+00:00"),
+        );
+        assert_eq!(
+            daily_status(&db, &clock, range, false).await,
+            vec![("2024-08-01".to_string(), "program_1".to_string(), hour)]
+        );
+
+        // rows of a deleted program are left out as well
+        db.execute_unprepared(r#"UPDATE "program" SET "deleted_at" = 1"#)
+            .await
+            .unwrap();
+        assert_eq!(daily_status(&db, &clock, range, false).await, vec![]);
+    }
+
+    #[tokio::test]
+    async fn test_daily_summary_of_existing_rows() {
+        // recorded before the daily summary was added
+        let db = fixture::setup_before("20250218150244_").await;
+        db.execute_unprepared(
+            r#"
+            INSERT INTO "plan" ("name", "color") VALUES ('plan', '#000000');
+            INSERT INTO "label" ("name", "color", "plan_id") VALUES ('label', '#000000', 1);
+            INSERT INTO "program" ("name", "color", "path", "icon", "platform")
+                VALUES ('program', '#000000', 'program', '', '');
+            "#,
+        )
+        .await
+        .unwrap();
+        let hour = 60 * 60 * 1000;
+        let start = local("2024-08-01T10:00:00+00:00");
+        insert_note(&db, start, start + hour).await;
+        insert_activity(&db, start + 24 * hour, start + 26 * hour).await;
+        fixture::migrate_from(&db, "20250218150244_").await;
+
+        let clock = Clock::new(Tz::UTC, 0);
+        refresh_daily_summary(&db, &clock).await.unwrap();
+        let range = (
+            local("2024-08-01T00:00:00+00:00"),
+            local("2024-08-03T00:00:00+00:00"),
+        );
+        let summary = sql::select_daily_summary(&db, range.0, range.1, false)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|status| (status.date, status.key, status.total))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("2024-08-01".to_string(), "label_1".to_string(), hour),
+                ("2024-08-02".to_string(), "program_1".to_string(), 2 * hour),
+            ]
+        );
+        assert_eq!(summary, daily_status(&db, &clock, range, false).await);
+    }
+
+    #[tokio::test]
+    async fn test_extend_running_note() {
+        let db = setup_daily_status().await;
+        let minute = 60 * 1000;
+        let start = local("2024-08-01T10:00:00+00:00");
+        insert_note(&db, start, start).await;
+        insert_note(&db, start + 30 * minute, start + 60 * minute).await;
+        let end = |db: DatabaseConnection| async move {
+            sql::select_note_by_id(&db, 1).await.unwrap().unwrap().end
+        };
+
+        extend_running_note(&db, 1, start + 10 * minute, ConflictPolicy::Reject)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(end(db.clone()).await, start + 10 * minute);
+
+        // the note after it is not overwritten
+        assert_eq!(
+            extend_running_note(&db, 1, start + 40 * minute, ConflictPolicy::Reject)
+                .await
+                .unwrap(),
+            Err(NoteConflict::Overlap(vec![2]))
+        );
+        assert_eq!(end(db.clone()).await, start + 10 * minute);
+        extend_running_note(&db, 1, start + 40 * minute, ConflictPolicy::Resolve)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(end(db.clone()).await, start + 30 * minute);
+    }
+
+    #[test]
+    fn test_aggregate_empty_bucket() {
+        let result = aggregate(vec![item("program_1", 0, 50)], &[0, 100, 200], false);
+        assert!(result[1].status.list.is_empty());
+        assert_eq!(result[1].status.total, 0);
+        assert_eq!(result[1].status.busy, None);
+    }
+
+    #[tokio::test]
+    async fn test_program_alias_cache() {
+        let db = setup_daily_status().await;
+        assert!(
+            create_program_alias(&db, 1, "(".to_string(), AliasKind::Regex as i64)
+                .await
+                .is_err()
+        );
+        assert!(
+            create_program_alias(&db, 2, "/opt/*".to_string(), AliasKind::Glob as i64)
+                .await
+                .is_err()
+        );
+        assert!(resolve_program(&db, "/opt/app-1/app")
+            .await
+            .unwrap()
+            .is_none());
+
+        create_program_alias(&db, 1, "/opt/app-*/app".to_string(), AliasKind::Glob as i64)
+            .await
+            .unwrap();
+        let program = resolve_program(&db, "/opt/app-1/app").await.unwrap();
+        assert_eq!(program.map(|program| program.id), Some(1));
+
+        db.execute_unprepared(r#"UPDATE "program_alias" SET "deleted_at" = 1"#)
+            .await
+            .unwrap();
+        assert!(resolve_program(&db, "/opt/app-1/app")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_label_rollup_in_status() {
+        let db = setup_daily_status().await;
+        let hour = 60 * 60 * 1000;
+        db.execute_unprepared(&format!(
+            r#"
+            INSERT INTO "label" ("name", "color", "plan_id", "parent_id")
+                VALUES ('child', '#000000', 1, 1);
+            INSERT INTO "note" ("start", "end", "plan_id", "label_id") VALUES ({}, {}, 1, 2);
+            "#,
+            local("2024-08-02T01:00:00+00:00"),
+            local("2024-08-02T03:00:00+00:00"),
+        ))
+        .await
+        .unwrap();
+        insert_note(
+            &db,
+            local("2024-08-02T04:00:00+00:00"),
+            local("2024-08-02T05:00:00+00:00"),
+        )
+        .await;
+        let clock = Clock::new(Tz::UTC, 0);
+        let range = (
+            local("2024-08-02T00:00:00+00:00"),
+            local("2024-08-03T00:00:00+00:00"),
+        );
+        let label_totals = |status: &DailyStatus| {
+            let mut list = status
+                .list
+                .iter()
+                .map(|item| (item.key.clone(), item.total))
+                .collect::<Vec<_>>();
+            list.sort();
+            list
+        };
+        let expected = vec![
+            ("label_1".to_string(), 3 * hour),
+            ("label_2".to_string(), 2 * hour),
+        ];
+
+        let bucket_list =
+            get_bucket_status_list(&db, range.0, range.1, BucketUnit::Day, false, &clock)
+                .await
+                .unwrap();
+        assert_eq!(label_totals(&bucket_list[0].status), expected);
+        assert_eq!(bucket_list[0].status.total, 3 * hour);
+
+        // whole days come from the summary
+        refresh_daily_summary(&db, &clock).await.unwrap();
+        let map = get_active_status_calendar_map(&db, range.0, range.1, false, &clock)
+            .await
+            .unwrap();
+        assert_eq!(label_totals(&map["2024-08-02"]), expected);
+        assert_eq!(map["2024-08-02"].total, 3 * hour);
+    }
+
+    #[tokio::test]
+    async fn test_label_rollup_overlap() {
+        let db = setup_daily_status().await;
+        let hour = 60 * 60 * 1000;
+        db.execute_unprepared(&format!(
+            r#"
+            INSERT INTO "label" ("name", "color", "plan_id", "parent_id")
+                VALUES ('child', '#000000', 1, 1);
+            INSERT INTO "note" ("start", "end", "plan_id", "label_id") VALUES ({}, {}, 1, 2);
+            "#,
+            local("2024-08-02T01:00:00+00:00"),
+            local("2024-08-02T03:00:00+00:00"),
+        ))
+        .await
+        .unwrap();
+        // the parent note overlaps the child note by an hour
+        insert_note(
+            &db,
+            local("2024-08-02T02:00:00+00:00"),
+            local("2024-08-02T04:00:00+00:00"),
+        )
+        .await;
+        let clock = Clock::new(Tz::UTC, 0);
+        let range = (
+            local("2024-08-02T00:00:00+00:00"),
+            local("2024-08-03T00:00:00+00:00"),
+        );
+        let parent_total = |status: &DailyStatus| {
+            status
+                .list
+                .iter()
+                .find(|item| item.key == "label_1")
+                .map(|item| item.total)
+        };
+
+        let bucket_list =
+            get_bucket_status_list(&db, range.0, range.1, BucketUnit::Day, false, &clock)
+                .await
+                .unwrap();
+        assert_eq!(parent_total(&bucket_list[0].status), Some(3 * hour));
+        assert_eq!(bucket_list[0].status.total, 4 * hour);
+
+        // the day is computed from the raw rows until the summary is refreshed, and reading it
+        // leaves the summary as it is
+        for refresh in [false, true] {
+            if refresh {
+                refresh_daily_summary(&db, &clock).await.unwrap();
+            }
+            let map = get_active_status_calendar_map(&db, range.0, range.1, false, &clock)
+                .await
+                .unwrap();
+            assert_eq!(parent_total(&map["2024-08-02"]), Some(3 * hour));
+            assert_eq!(map["2024-08-02"].total, 4 * hour);
+            let dirty_list = sql::select_daily_summary_dirty_list(&db).await.unwrap();
+            assert_eq!(dirty_list.is_empty(), refresh);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_activity_edit_journal() {
+        let db = setup_daily_status().await;
+        insert_activity(&db, 0, 100).await;
+        insert_activity(&db, 120, 200).await;
+        let live = |db: DatabaseConnection| async move {
+            sql::select_activity_list(&db, 0, 1000)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|activity| (activity.start, activity.end))
+                .collect::<Vec<_>>()
+        };
+        let now = 10_000_000;
+        let id_list = split_activity(&db, 1, 50, now).await.unwrap();
+        merge_activity(&db, vec![id_list[1], 2], now).await.unwrap();
+        assert_eq!(live(db.clone()).await, vec![(0, 50), (50, 200)]);
+
+        // the merge is undone before the split it depends on
+        journal::undo_change(&db).await.unwrap().unwrap();
+        assert_eq!(live(db.clone()).await, vec![(0, 50), (50, 100), (120, 200)]);
+        journal::undo_change(&db).await.unwrap().unwrap();
+        assert_eq!(live(db.clone()).await, vec![(0, 100), (120, 200)]);
+
+        journal::redo_change(&db).await.unwrap().unwrap();
+        journal::redo_change(&db).await.unwrap().unwrap();
+        assert_eq!(live(db.clone()).await, vec![(0, 50), (50, 200)]);
+    }
+
+    #[tokio::test]
+    async fn test_merge_program_journal() {
+        let db = setup_daily_status().await;
+        db.execute_unprepared(
+            r#"
+            INSERT INTO "program" ("name", "color", "path", "icon", "platform")
+                VALUES ('other', '#000000', 'other', '', '');
+            INSERT INTO "activity" ("start", "end", "program_id")
+                VALUES (0, 100, 1), (100, 200, 2), (200, 300, 2);
+            "#,
+        )
+        .await
+        .unwrap();
+        let program_list = |db: DatabaseConnection| async move {
+            sql::select_activity_list(&db, 0, 1000)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|activity| activity.program_id)
+                .collect::<Vec<_>>()
+        };
+        merge_program(&db, vec![2], 1).await.unwrap();
+        assert_eq!(program_list(db.clone()).await, vec![1, 1, 1]);
+
+        // the activities are kept as one remap, not row by row
+        let change_id = journal::undo_change(&db).await.unwrap().unwrap();
+        let entry_list = sql::select_change_entry_list(&db, vec![change_id])
+            .await
+            .unwrap();
+        let activity_entry_list = entry_list
+            .iter()
+            .filter(|entry| entry.table_name == "activity")
+            .collect::<Vec<_>>();
+        assert_eq!(activity_entry_list.len(), 1);
+        assert_eq!(activity_entry_list[0].row_id_list.as_deref(), Some("[2,3]"));
+        assert_eq!(program_list(db.clone()).await, vec![1, 2, 2]);
+        assert!(sql::select_program_by_id(&db, 2).await.unwrap().is_some());
+
+        journal::redo_change(&db).await.unwrap().unwrap();
+        assert_eq!(program_list(db.clone()).await, vec![1, 1, 1]);
+        assert!(sql::select_program_by_id(&db, 2).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_remove_label_journal() {
+        let db = setup_daily_status().await;
+        db.execute_unprepared(
+            r#"INSERT INTO "label" ("name", "color", "plan_id", "parent_id") VALUES ('child', '#000000', 1, 1)"#,
+        )
+        .await
+        .unwrap();
+        insert_note(&db, 0, 100).await;
+        let live = |db: DatabaseConnection| async move {
+            let mut label_list = sql::select_label_list(&db)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|label| (label.id, label.parent_id))
+                .collect::<Vec<_>>();
+            label_list.sort();
+            let note_count = sql::select_note_list(&db, 0, 1000).await.unwrap().len();
+            (label_list, note_count)
+        };
+        remove_label(&db, vec![1], true).await.unwrap();
+        assert_eq!(live(db.clone()).await, (vec![(2, None)], 0));
+
+        journal::undo_change(&db).await.unwrap().unwrap();
+        assert_eq!(live(db.clone()).await, (vec![(1, None), (2, Some(1))], 1));
+
+        journal::redo_change(&db).await.unwrap().unwrap();
+        assert_eq!(live(db.clone()).await, (vec![(2, None)], 0));
+    }
+
+    #[tokio::test]
+    async fn test_remove_program_journal() {
+        let db = setup_daily_status().await;
+        insert_activity(&db, 0, 100).await;
+        insert_activity(&db, 100, 200).await;
+        let live = |db: DatabaseConnection| async move {
+            let activity_count = sql::select_activity_list(&db, 0, 1000).await.unwrap().len();
+            let program = sql::select_program_by_id(&db, 1).await.unwrap();
+            (program.is_some(), activity_count)
+        };
+        remove_program(&db, vec![1], true).await.unwrap();
+        assert_eq!(live(db.clone()).await, (false, 0));
+
+        journal::undo_change(&db).await.unwrap().unwrap();
+        assert_eq!(live(db.clone()).await, (true, 2));
+
+        journal::redo_change(&db).await.unwrap().unwrap();
+        assert_eq!(live(db.clone()).await, (false, 0));
+    }
+
+    #[tokio::test]
+    async fn test_remove_box_journal() {
+        use crate::database::models::{moment, r#box};
+        use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter};
+
+        let db = setup_daily_status().await;
+        db.execute_unprepared(
+            r#"INSERT INTO "box" ("name", "color") VALUES ('box', '#000000');
+            INSERT INTO "moment" ("title", "content", "box_id") VALUES ('a', '', 1), ('b', '', 1)"#,
+        )
+        .await
+        .unwrap();
+        let live = |db: DatabaseConnection| async move {
+            let box_count = r#box::Entity::find()
+                .filter(r#box::Column::DeletedAt.eq(0))
+                .count(&db)
+                .await
+                .unwrap();
+            let moment_count = moment::Entity::find()
+                .filter(moment::Column::DeletedAt.eq(0))
+                .count(&db)
+                .await
+                .unwrap();
+            (box_count, moment_count)
+        };
+        remove_box(&db, vec![1], true).await.unwrap();
+        assert_eq!(live(db.clone()).await, (0, 0));
+
+        journal::undo_change(&db).await.unwrap().unwrap();
+        assert_eq!(live(db.clone()).await, (1, 2));
+
+        journal::redo_change(&db).await.unwrap().unwrap();
+        assert_eq!(live(db.clone()).await, (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_create_program_journal() {
+        use crate::database::models::prelude::Remark;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+        let db = setup_daily_status().await;
+        let program = get_or_create_program(&db, "editor".to_string(), "/bin/editor".to_string())
+            .await
+            .unwrap();
+        let remark = create_remark(
+            &db,
+            InsertRemark {
+                title: "title".to_string(),
+                desc: "".to_string(),
+                arg: "".to_string(),
+                program_id: program.id,
+                time: 0,
+            },
+        )
+        .await
+        .unwrap();
+
+        journal::undo_change(&db).await.unwrap().unwrap();
+        let remark_list = Remark::find()
+            .filter(remark::Column::DeletedAt.eq(0))
+            .filter(remark::Column::Id.eq(remark.id))
+            .all(&db)
+            .await
+            .unwrap();
+        assert!(remark_list.is_empty());
+
+        journal::undo_change(&db).await.unwrap().unwrap();
+        let program = sql::select_program_by_id(&db, program.id).await.unwrap();
+        assert!(program.is_none());
+    }
 }
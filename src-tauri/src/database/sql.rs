@@ -1,10 +1,21 @@
+use chrono::Utc;
 use sea_orm::{
-    prelude::Expr, ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection,
-    EntityTrait, FromQueryResult, JoinType::LeftJoin, QueryFilter, QuerySelect, RelationTrait,
+    prelude::Expr,
+    sea_query::OnConflict,
+    ActiveModelTrait,
+    ActiveValue::Set,
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait, FromQueryResult,
+    JoinType::{InnerJoin, LeftJoin},
+    QueryFilter, QueryOrder, QuerySelect, QueryTrait, RelationTrait, Select, Statement,
 };
 use tauri_plugin_shion_sql::Result;
 
-use super::models::{activity, label, note, prelude::*, program, remark};
+use super::models::{
+    activity, change, change_entry, daily_summary, daily_summary_dirty, dimension, dimension_label,
+    dimension_program, goal, label, moment, note, note_template, note_template_exception, plan,
+    prelude::*, program, program_alias, r#box, remark, time_block, timezone_log, usage_limit,
+    usage_limit_snooze,
+};
 
 pub struct InsertRemark {
     pub title: String,
@@ -14,7 +25,7 @@ pub struct InsertRemark {
     pub time: i64,
 }
 
-pub async fn create_remark(db: &DatabaseConnection, data: InsertRemark) -> Result<()> {
+pub async fn create_remark(db: &impl ConnectionTrait, data: InsertRemark) -> Result<remark::Model> {
     let model = remark::ActiveModel {
         title: Set(data.title),
         desc: Set(data.desc),
@@ -23,8 +34,23 @@ pub async fn create_remark(db: &DatabaseConnection, data: InsertRemark) -> Resul
         time: Set(data.time),
         ..Default::default()
     };
-    model.insert(db).await?;
-    Ok(())
+    Ok(model.insert(db).await?)
+}
+
+/// The last remark of the program at or before `time`.
+pub async fn select_last_remark(
+    db: &DatabaseConnection,
+    program_id: i64,
+    time: i64,
+) -> Result<Option<remark::Model>> {
+    Ok(Remark::find()
+        .filter(remark::Column::DeletedAt.eq(0))
+        .filter(remark::Column::ProgramId.eq(program_id))
+        .filter(remark::Column::Time.lte(time))
+        .order_by_desc(remark::Column::Time)
+        .order_by_desc(remark::Column::Id)
+        .one(db)
+        .await?)
 }
 
 pub async fn select_program_by_path(
@@ -38,21 +64,676 @@ pub async fn select_program_by_path(
         .await?)
 }
 
-#[derive(FromQueryResult)]
-pub struct DailyStatusResult {
+pub async fn select_program_by_id(
+    db: &impl ConnectionTrait,
+    id: i64,
+) -> Result<Option<program::Model>> {
+    Ok(Program::find()
+        .filter(program::Column::DeletedAt.eq(0))
+        .filter(program::Column::Id.eq(id))
+        .one(db)
+        .await?)
+}
+
+pub async fn select_program_list_by_id(
+    db: &impl ConnectionTrait,
+    id_list: Vec<i64>,
+) -> Result<Vec<program::Model>> {
+    Ok(Program::find()
+        .filter(program::Column::DeletedAt.eq(0))
+        .filter(program::Column::Id.is_in(id_list))
+        .all(db)
+        .await?)
+}
+
+/// Aliases of the programs that are not removed, in the order they are tried.
+pub async fn select_program_alias_list(
+    db: &DatabaseConnection,
+) -> Result<Vec<(program_alias::Model, program::Model)>> {
+    Ok(ProgramAlias::find()
+        .find_also_related(Program)
+        .filter(program_alias::Column::DeletedAt.eq(0))
+        .filter(program::Column::DeletedAt.eq(0))
+        .order_by_asc(program_alias::Column::Sort)
+        .order_by_asc(program_alias::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .filter_map(|(alias, program)| program.map(|program| (alias, program)))
+        .collect())
+}
+
+/// Counts the aliases with the latest edit and the removals, which changes along with any of them.
+pub async fn select_program_alias_version(db: &DatabaseConnection) -> Result<(i64, i64, i64)> {
+    Ok(ProgramAlias::find()
+        .select_only()
+        .column_as(Expr::cust("COUNT(*)"), "count")
+        .column_as(Expr::cust("COALESCE(MAX(updated_at), 0)"), "updated_at")
+        .column_as(Expr::cust("COALESCE(SUM(deleted_at), 0)"), "deleted_at")
+        .into_tuple()
+        .one(db)
+        .await?
+        .unwrap_or_default())
+}
+
+pub struct InsertProgramAlias {
+    pub program_id: i64,
+    pub pattern: String,
+    pub kind: i64,
+}
+
+pub async fn create_program_alias(
+    db: &impl ConnectionTrait,
+    data: InsertProgramAlias,
+) -> Result<i64> {
+    let model = program_alias::ActiveModel {
+        program_id: Set(data.program_id),
+        pattern: Set(data.pattern),
+        kind: Set(data.kind),
+        ..Default::default()
+    };
+    Ok(model.insert(db).await?.id)
+}
+
+/// Points the activities, remarks, dimensions and aliases of the sources to the target.
+///
+/// A dimension that would list the target twice keeps its first row.
+pub async fn move_program_relation(
+    db: &impl ConnectionTrait,
+    source_id_list: Vec<i64>,
+    target_id: i64,
+) -> Result<()> {
+    let now = Utc::now().timestamp_millis();
+    Activity::update_many()
+        .col_expr(activity::Column::ProgramId, Expr::value(target_id))
+        .col_expr(activity::Column::UpdatedAt, Expr::value(now))
+        .filter(activity::Column::ProgramId.is_in(source_id_list.clone()))
+        .exec(db)
+        .await?;
+    Remark::update_many()
+        .col_expr(remark::Column::ProgramId, Expr::value(target_id))
+        .col_expr(remark::Column::UpdatedAt, Expr::value(now))
+        .filter(remark::Column::ProgramId.is_in(source_id_list.clone()))
+        .exec(db)
+        .await?;
+    ProgramAlias::update_many()
+        .col_expr(program_alias::Column::ProgramId, Expr::value(target_id))
+        .col_expr(program_alias::Column::UpdatedAt, Expr::value(now))
+        .filter(program_alias::Column::ProgramId.is_in(source_id_list.clone()))
+        .exec(db)
+        .await?;
+
+    let mut program_id_list = source_id_list;
+    program_id_list.push(target_id);
+    let dimension_program_list = DimensionProgram::find()
+        .filter(dimension_program::Column::DeletedAt.eq(0))
+        .filter(dimension_program::Column::ProgramId.is_in(program_id_list))
+        .order_by_asc(dimension_program::Column::Id)
+        .all(db)
+        .await?;
+    let mut dimension_id_list = vec![];
+    let mut duplicate_id_list = vec![];
+    // the row of the target is kept before the ones of the sources
+    for row in dimension_program_list
+        .iter()
+        .filter(|row| row.program_id == target_id)
+        .chain(
+            dimension_program_list
+                .iter()
+                .filter(|row| row.program_id != target_id),
+        )
+    {
+        if dimension_id_list.contains(&row.dimension_id) {
+            duplicate_id_list.push(row.id);
+        } else {
+            dimension_id_list.push(row.dimension_id);
+        }
+    }
+    DimensionProgram::update_many()
+        .col_expr(dimension_program::Column::DeletedAt, Expr::value(now))
+        .filter(dimension_program::Column::Id.is_in(duplicate_id_list))
+        .exec(db)
+        .await?;
+    DimensionProgram::update_many()
+        .col_expr(dimension_program::Column::ProgramId, Expr::value(target_id))
+        .col_expr(dimension_program::Column::UpdatedAt, Expr::value(now))
+        .filter(dimension_program::Column::DeletedAt.eq(0))
+        .filter(
+            dimension_program::Column::Id.is_in(
+                dimension_program_list
+                    .iter()
+                    .filter(|row| row.program_id != target_id)
+                    .map(|row| row.id),
+            ),
+        )
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn remove_program_list(db: &impl ConnectionTrait, id_list: Vec<i64>) -> Result<()> {
+    Program::update_many()
+        .col_expr(
+            program::Column::DeletedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(program::Column::Id.is_in(id_list))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub struct UpdateProgram {
+    pub name: Option<String>,
+    pub color: Option<String>,
+    pub path: Option<String>,
+    pub sort: Option<i64>,
+    pub hidden: Option<bool>,
+}
+
+/// Writes the fields that are set.
+pub async fn update_program(db: &impl ConnectionTrait, id: i64, data: UpdateProgram) -> Result<()> {
+    let mut query = Program::update_many()
+        .col_expr(
+            program::Column::UpdatedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(program::Column::Id.eq(id));
+    if let Some(name) = data.name {
+        query = query.col_expr(program::Column::Name, Expr::value(name));
+    }
+    if let Some(color) = data.color {
+        query = query.col_expr(program::Column::Color, Expr::value(color));
+    }
+    if let Some(path) = data.path {
+        query = query.col_expr(program::Column::Path, Expr::value(path));
+    }
+    if let Some(sort) = data.sort {
+        query = query.col_expr(program::Column::Sort, Expr::value(sort));
+    }
+    if let Some(hidden) = data.hidden {
+        query = query.col_expr(program::Column::Hidden, Expr::value(hidden));
+    }
+    query.exec(db).await?;
+    Ok(())
+}
+
+pub async fn remove_program_alias_by_program(
+    db: &impl ConnectionTrait,
+    program_id_list: Vec<i64>,
+) -> Result<()> {
+    ProgramAlias::update_many()
+        .col_expr(
+            program_alias::Column::DeletedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(program_alias::Column::DeletedAt.eq(0))
+        .filter(program_alias::Column::ProgramId.is_in(program_id_list))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub struct InsertProgram {
     pub name: String,
     pub color: String,
-    pub key: String,
+    pub path: String,
+    pub icon: String,
+    pub platform: String,
+}
+
+pub async fn create_program(
+    db: &impl ConnectionTrait,
+    data: InsertProgram,
+) -> Result<program::Model> {
+    let model = program::ActiveModel {
+        name: Set(data.name),
+        color: Set(data.color),
+        path: Set(data.path),
+        icon: Set(data.icon),
+        platform: Set(data.platform),
+        ..Default::default()
+    };
+    let model = model.insert(db).await?;
+    let id = model.id;
+    let mut model: program::ActiveModel = model.into();
+    model.sort = Set(id);
+    Ok(model.update(db).await?)
+}
+
+pub struct InsertActivity {
     pub start: i64,
     pub end: i64,
+    pub program_id: i64,
 }
 
-pub async fn select_note_for_daily_status(
+pub async fn create_activity(
     db: &DatabaseConnection,
+    data: InsertActivity,
+) -> Result<activity::Model> {
+    let model = activity::ActiveModel {
+        start: Set(data.start),
+        end: Set(data.end),
+        program_id: Set(data.program_id),
+        ..Default::default()
+    };
+    Ok(model.insert(db).await?)
+}
+
+pub async fn update_activity_range(
+    db: &DatabaseConnection,
+    id: i64,
     start: i64,
     end: i64,
-) -> Result<Vec<DailyStatusResult>> {
+) -> Result<()> {
+    Activity::update_many()
+        .col_expr(activity::Column::Start, Expr::value(start))
+        .col_expr(activity::Column::End, Expr::value(end))
+        .col_expr(
+            activity::Column::UpdatedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(activity::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn update_activity_end(db: &DatabaseConnection, id: i64, end: i64) -> Result<()> {
+    Activity::update_many()
+        .col_expr(activity::Column::End, Expr::value(end))
+        .col_expr(
+            activity::Column::UpdatedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(activity::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn select_activity_list_by_id(
+    db: &impl ConnectionTrait,
+    id_list: Vec<i64>,
+) -> Result<Vec<activity::Model>> {
+    Ok(Activity::find()
+        .filter(activity::Column::DeletedAt.eq(0))
+        .filter(activity::Column::Id.is_in(id_list))
+        .order_by_asc(activity::Column::Start)
+        .all(db)
+        .await?)
+}
+
+pub async fn select_activity_list(
+    db: &impl ConnectionTrait,
+    start: i64,
+    end: i64,
+) -> Result<Vec<activity::Model>> {
+    Ok(Activity::find()
+        .filter(activity::Column::DeletedAt.eq(0))
+        .filter(activity::Column::Start.lt(end))
+        .filter(activity::Column::End.gt(start))
+        .order_by_asc(activity::Column::Start)
+        .all(db)
+        .await?)
+}
+
+pub async fn select_activity_id_list_by_program(
+    db: &impl ConnectionTrait,
+    program_id_list: Vec<i64>,
+) -> Result<Vec<i64>> {
+    Ok(Activity::find()
+        .select_only()
+        .column(activity::Column::Id)
+        .filter(activity::Column::DeletedAt.eq(0))
+        .filter(activity::Column::ProgramId.is_in(program_id_list))
+        .into_tuple()
+        .all(db)
+        .await?)
+}
+
+/// Removes the activities still there at `now`, returns how many were removed.
+pub async fn remove_activity_list(
+    db: &impl ConnectionTrait,
+    id_list: Vec<i64>,
+    now: i64,
+) -> Result<u64> {
+    let result = Activity::update_many()
+        .col_expr(activity::Column::DeletedAt, Expr::value(now))
+        .filter(activity::Column::Id.is_in(id_list))
+        .filter(activity::Column::DeletedAt.eq(0))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+pub async fn create_activity_list(
+    db: &impl ConnectionTrait,
+    data_list: Vec<InsertActivity>,
+) -> Result<Vec<i64>> {
+    let mut id_list = vec![];
+    for data in data_list {
+        let model = activity::ActiveModel {
+            start: Set(data.start),
+            end: Set(data.end),
+            program_id: Set(data.program_id),
+            ..Default::default()
+        };
+        id_list.push(model.insert(db).await?.id);
+    }
+    Ok(id_list)
+}
+
+pub async fn select_plan_list(db: &DatabaseConnection) -> Result<Vec<plan::Model>> {
+    Ok(Plan::find()
+        .filter(plan::Column::DeletedAt.eq(0))
+        .order_by_asc(plan::Column::Sort)
+        .all(db)
+        .await?)
+}
+
+pub async fn select_plan_by_id(db: &impl ConnectionTrait, id: i64) -> Result<Option<plan::Model>> {
+    Ok(Plan::find()
+        .filter(plan::Column::DeletedAt.eq(0))
+        .filter(plan::Column::Id.eq(id))
+        .one(db)
+        .await?)
+}
+
+pub async fn select_label_list(db: &impl ConnectionTrait) -> Result<Vec<label::Model>> {
+    Ok(Label::find()
+        .filter(label::Column::DeletedAt.eq(0))
+        .order_by_asc(label::Column::Sort)
+        .all(db)
+        .await?)
+}
+
+pub async fn select_label_by_id(db: &DatabaseConnection, id: i64) -> Result<Option<label::Model>> {
+    Ok(Label::find()
+        .filter(label::Column::DeletedAt.eq(0))
+        .filter(label::Column::Id.eq(id))
+        .one(db)
+        .await?)
+}
+
+pub async fn select_label_by_name(
+    db: &DatabaseConnection,
+    name: String,
+) -> Result<Option<label::Model>> {
+    Ok(Label::find()
+        .filter(label::Column::DeletedAt.eq(0))
+        .filter(label::Column::Name.eq(name))
+        .one(db)
+        .await?)
+}
+
+pub async fn update_label_parent(
+    db: &impl ConnectionTrait,
+    id: i64,
+    parent_id: Option<i64>,
+) -> Result<()> {
+    Label::update_many()
+        .col_expr(label::Column::ParentId, Expr::value(parent_id))
+        .col_expr(
+            label::Column::UpdatedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(label::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn update_label_plan(
+    db: &impl ConnectionTrait,
+    id_list: Vec<i64>,
+    plan_id: i64,
+) -> Result<()> {
+    Label::update_many()
+        .col_expr(label::Column::PlanId, Expr::value(plan_id))
+        .col_expr(
+            label::Column::UpdatedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(label::Column::Id.is_in(id_list))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Moves the notes of the labels to the plan, also the removed ones.
+pub async fn update_note_plan(
+    db: &impl ConnectionTrait,
+    label_id_list: Vec<i64>,
+    plan_id: i64,
+) -> Result<()> {
+    Note::update_many()
+        .col_expr(note::Column::PlanId, Expr::value(plan_id))
+        .col_expr(
+            note::Column::UpdatedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(note::Column::LabelId.is_in(label_id_list))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub struct UpdateLabel {
+    pub name: Option<String>,
+    pub color: Option<String>,
+    pub sort: Option<i64>,
+    pub hidden: Option<bool>,
+}
+
+/// Writes the fields that are set.
+pub async fn update_label(db: &impl ConnectionTrait, id: i64, data: UpdateLabel) -> Result<()> {
+    let mut query = Label::update_many()
+        .col_expr(
+            label::Column::UpdatedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(label::Column::Id.eq(id));
+    if let Some(name) = data.name {
+        query = query.col_expr(label::Column::Name, Expr::value(name));
+    }
+    if let Some(color) = data.color {
+        query = query.col_expr(label::Column::Color, Expr::value(color));
+    }
+    if let Some(sort) = data.sort {
+        query = query.col_expr(label::Column::Sort, Expr::value(sort));
+    }
+    if let Some(hidden) = data.hidden {
+        query = query.col_expr(label::Column::Hidden, Expr::value(hidden));
+    }
+    query.exec(db).await?;
+    Ok(())
+}
+
+pub async fn remove_label_list(db: &impl ConnectionTrait, id_list: Vec<i64>) -> Result<()> {
+    Label::update_many()
+        .col_expr(
+            label::Column::DeletedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(label::Column::Id.is_in(id_list))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Moves the children of the label under `parent_id`.
+pub async fn update_label_children_parent(
+    db: &impl ConnectionTrait,
+    id: i64,
+    parent_id: Option<i64>,
+) -> Result<()> {
+    Label::update_many()
+        .col_expr(label::Column::ParentId, Expr::value(parent_id))
+        .col_expr(
+            label::Column::UpdatedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(label::Column::DeletedAt.eq(0))
+        .filter(label::Column::ParentId.eq(id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn remove_note_by_label(
+    db: &impl ConnectionTrait,
+    label_id_list: Vec<i64>,
+) -> Result<()> {
+    Note::update_many()
+        .col_expr(
+            note::Column::DeletedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(note::Column::DeletedAt.eq(0))
+        .filter(note::Column::LabelId.is_in(label_id_list))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn remove_time_block_by_label(
+    db: &impl ConnectionTrait,
+    label_id_list: Vec<i64>,
+) -> Result<()> {
+    TimeBlock::update_many()
+        .col_expr(
+            time_block::Column::DeletedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(time_block::Column::DeletedAt.eq(0))
+        .filter(time_block::Column::LabelId.is_in(label_id_list))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn remove_note_template_by_label(
+    db: &impl ConnectionTrait,
+    label_id_list: Vec<i64>,
+) -> Result<()> {
+    NoteTemplate::update_many()
+        .col_expr(
+            note_template::Column::DeletedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(note_template::Column::DeletedAt.eq(0))
+        .filter(note_template::Column::LabelId.is_in(label_id_list))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub struct InsertLabel {
+    pub name: String,
+    pub color: String,
+    pub plan_id: i64,
+}
+
+pub async fn create_label(db: &impl ConnectionTrait, data: InsertLabel) -> Result<label::Model> {
+    let model = label::ActiveModel {
+        name: Set(data.name),
+        color: Set(data.color),
+        plan_id: Set(data.plan_id),
+        ..Default::default()
+    };
+    let model = model.insert(db).await?;
+    let id = model.id;
+    let mut model: label::ActiveModel = model.into();
+    model.sort = Set(id);
+    Ok(model.update(db).await?)
+}
+
+pub async fn select_note_by_id(db: &DatabaseConnection, id: i64) -> Result<Option<note::Model>> {
+    Ok(Note::find()
+        .filter(note::Column::DeletedAt.eq(0))
+        .filter(note::Column::Id.eq(id))
+        .one(db)
+        .await?)
+}
+
+pub async fn select_note_list(
+    db: &DatabaseConnection,
+    start: i64,
+    end: i64,
+) -> Result<Vec<note::Model>> {
     Ok(Note::find()
+        .filter(note::Column::DeletedAt.eq(0))
+        .filter(note::Column::Start.lt(end))
+        .filter(note::Column::End.gt(start))
+        .order_by_asc(note::Column::Start)
+        .all(db)
+        .await?)
+}
+
+/// Notes in the range whose `column` is one of `id_list`, e.g. the notes of a label.
+pub async fn select_note_list_by(
+    db: &DatabaseConnection,
+    column: note::Column,
+    id_list: Vec<i64>,
+    start: i64,
+    end: i64,
+) -> Result<Vec<note::Model>> {
+    Ok(Note::find()
+        .filter(note::Column::DeletedAt.eq(0))
+        .filter(column.is_in(id_list))
+        .filter(note::Column::Start.lt(end))
+        .filter(note::Column::End.gt(start))
+        .order_by_asc(note::Column::Start)
+        .all(db)
+        .await?)
+}
+
+#[derive(Clone)]
+pub struct InsertNote {
+    pub start: i64,
+    pub end: i64,
+    pub plan_id: i64,
+    pub label_id: i64,
+    pub title: String,
+}
+
+pub async fn create_note(db: &impl ConnectionTrait, data: InsertNote) -> Result<note::Model> {
+    let model = note::ActiveModel {
+        start: Set(data.start),
+        end: Set(data.end),
+        plan_id: Set(data.plan_id),
+        label_id: Set(data.label_id),
+        title: Set(data.title),
+        ..Default::default()
+    };
+    Ok(model.insert(db).await?)
+}
+
+pub async fn update_note_end(db: &DatabaseConnection, id: i64, end: i64) -> Result<()> {
+    Note::update_many()
+        .col_expr(note::Column::End, Expr::value(end))
+        .col_expr(
+            note::Column::UpdatedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(note::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+#[derive(FromQueryResult, Clone)]
+pub struct DailyStatusResult {
+    pub name: String,
+    pub color: String,
+    pub key: String,
+    pub start: i64,
+    pub end: i64,
+}
+
+fn note_for_daily_status(start: i64, end: i64) -> Select<Note> {
+    Note::find()
         .select_only()
         .column(note::Column::Start)
         .column(note::Column::End)
@@ -61,9 +742,32 @@ pub async fn select_note_for_daily_status(
         .column_as(Expr::cust("CONCAT('label_', label.id)"), "key")
         .join(LeftJoin, note::Relation::Label.def())
         .filter(note::Column::DeletedAt.eq(0))
-        .filter(note::Column::Start.gt(start))
-        .filter(note::Column::End.lt(end))
+        .filter(note::Column::Start.lt(end))
+        .filter(note::Column::End.gt(start))
         .filter(label::Column::DeletedAt.eq(0))
+}
+
+fn activity_for_daily_status(start: i64, end: i64) -> Select<Activity> {
+    Activity::find()
+        .select_only()
+        .column(activity::Column::Start)
+        .column(activity::Column::End)
+        .column(program::Column::Name)
+        .column(program::Column::Color)
+        .column_as(Expr::cust("CONCAT('program_', program.id)"), "key")
+        .join(LeftJoin, activity::Relation::Program.def())
+        .filter(activity::Column::DeletedAt.eq(0))
+        .filter(activity::Column::Start.lt(end))
+        .filter(activity::Column::End.gt(start))
+        .filter(program::Column::DeletedAt.eq(0))
+}
+
+pub async fn select_note_for_daily_status(
+    db: &DatabaseConnection,
+    start: i64,
+    end: i64,
+) -> Result<Vec<DailyStatusResult>> {
+    Ok(note_for_daily_status(start, end)
         .into_model::<DailyStatusResult>()
         .all(db)
         .await?)
@@ -73,20 +777,752 @@ pub async fn select_activity_for_daily_status(
     db: &DatabaseConnection,
     start: i64,
     end: i64,
+) -> Result<Vec<DailyStatusResult>> {
+    Ok(activity_for_daily_status(start, end)
+        .into_model::<DailyStatusResult>()
+        .all(db)
+        .await?)
+}
+
+#[derive(FromQueryResult)]
+pub struct DailyStatusGroupResult {
+    pub date: String,
+    /// Empty for the time covered by any row.
+    pub key: String,
+    pub name: String,
+    pub color: String,
+    pub total: i64,
+}
+
+/// Sums notes and activities per day and key, `days` are `(date, start, end)` of each day.
+///
+/// Overlapping rows of a key are merged into islands first, so their time is only counted once.
+pub async fn select_daily_status(
+    db: &impl ConnectionTrait,
+    start: i64,
+    end: i64,
+    days: &[(String, i64, i64)],
+    busy: bool,
+) -> Result<Vec<DailyStatusGroupResult>> {
+    if days.is_empty() {
+        return Ok(vec![]);
+    }
+    let note = note_for_daily_status(start, end).build(DbBackend::Sqlite);
+    let activity = activity_for_daily_status(start, end).build(DbBackend::Sqlite);
+    let mut values = vec![];
+    for (date, start, end) in days {
+        values.extend([date.clone().into(), (*start).into(), (*end).into()]);
+    }
+    values.extend(note.values.map(|values| values.0).unwrap_or_default());
+    values.extend(activity.values.map(|values| values.0).unwrap_or_default());
+    values.push(busy.into());
+    let sql = format!(
+        r#"
+        WITH "day" ("date", "start", "end") AS (VALUES {days}),
+        "source" AS ({note} UNION ALL {activity}),
+        "span" AS (
+            SELECT "key", "name", "color", "start", "end" FROM "source"
+            UNION ALL
+            SELECT '', '', '', "start", "end" FROM "source" WHERE ?
+        ),
+        "marked" AS (
+            SELECT *, CASE WHEN "start" <= MAX("end") OVER (
+                PARTITION BY "key" ORDER BY "start", "end"
+                ROWS BETWEEN UNBOUNDED PRECEDING AND 1 PRECEDING
+            ) THEN 0 ELSE 1 END AS "is_new"
+            FROM "span"
+        ),
+        "island" AS (
+            SELECT "key", MIN("name") AS "name", MIN("color") AS "color",
+                MIN("start") AS "start", MAX("end") AS "end"
+            FROM (
+                SELECT *, SUM("is_new") OVER (
+                    PARTITION BY "key" ORDER BY "start", "end" ROWS UNBOUNDED PRECEDING
+                ) AS "island_id"
+                FROM "marked"
+            )
+            GROUP BY "key", "island_id"
+        )
+        SELECT "day"."date", "island"."key", "island"."name", "island"."color",
+            SUM(MIN("island"."end", "day"."end") - MAX("island"."start", "day"."start")) AS "total"
+        FROM "island"
+        INNER JOIN "day" ON "island"."start" < "day"."end" AND "island"."end" > "day"."start"
+        GROUP BY "day"."date", "island"."key"
+        ORDER BY MIN("island"."start")
+        "#,
+        days = vec!["(?, ?, ?)"; days.len()].join(", "),
+        note = note.sql,
+        activity = activity.sql,
+    );
+    Ok(
+        DailyStatusGroupResult::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            sql,
+            values,
+        ))
+        .all(db)
+        .await?,
+    )
+}
+
+pub async fn select_note_for_plan_status(
+    db: &DatabaseConnection,
+    start: i64,
+    end: i64,
+) -> Result<Vec<DailyStatusResult>> {
+    Ok(Note::find()
+        .select_only()
+        .column(note::Column::Start)
+        .column(note::Column::End)
+        .column(plan::Column::Name)
+        .column(plan::Column::Color)
+        .column_as(Expr::cust("CONCAT('plan_', plan.id)"), "key")
+        .join(InnerJoin, note::Relation::Plan.def())
+        .filter(note::Column::DeletedAt.eq(0))
+        .filter(note::Column::Start.lt(end))
+        .filter(note::Column::End.gt(start))
+        .filter(plan::Column::DeletedAt.eq(0))
+        .into_model::<DailyStatusResult>()
+        .all(db)
+        .await?)
+}
+
+/// A note is returned once for every dimension its label belongs to.
+pub async fn select_note_for_dimension_status(
+    db: &DatabaseConnection,
+    start: i64,
+    end: i64,
+) -> Result<Vec<DailyStatusResult>> {
+    Ok(Note::find()
+        .select_only()
+        .column(note::Column::Start)
+        .column(note::Column::End)
+        .column(dimension::Column::Name)
+        .column(dimension::Column::Color)
+        .column_as(Expr::cust("CONCAT('dimension_', dimension.id)"), "key")
+        .join(InnerJoin, note::Relation::Label.def())
+        .join(InnerJoin, label::Relation::DimensionLabel.def())
+        .join(InnerJoin, dimension_label::Relation::Dimension.def())
+        .filter(note::Column::DeletedAt.eq(0))
+        .filter(note::Column::Start.lt(end))
+        .filter(note::Column::End.gt(start))
+        .filter(label::Column::DeletedAt.eq(0))
+        .filter(dimension_label::Column::DeletedAt.eq(0))
+        .filter(dimension::Column::DeletedAt.eq(0))
+        .into_model::<DailyStatusResult>()
+        .all(db)
+        .await?)
+}
+
+/// An activity is returned once for every dimension its program belongs to.
+pub async fn select_activity_for_dimension_status(
+    db: &DatabaseConnection,
+    start: i64,
+    end: i64,
 ) -> Result<Vec<DailyStatusResult>> {
     Ok(Activity::find()
         .select_only()
         .column(activity::Column::Start)
         .column(activity::Column::End)
-        .column(program::Column::Name)
-        .column(program::Column::Color)
-        .column_as(Expr::cust("CONCAT('program_', program.id)"), "key")
-        .join(LeftJoin, activity::Relation::Program.def())
+        .column(dimension::Column::Name)
+        .column(dimension::Column::Color)
+        .column_as(Expr::cust("CONCAT('dimension_', dimension.id)"), "key")
+        .join(InnerJoin, activity::Relation::Program.def())
+        .join(InnerJoin, program::Relation::DimensionProgram.def())
+        .join(InnerJoin, dimension_program::Relation::Dimension.def())
         .filter(activity::Column::DeletedAt.eq(0))
-        .filter(activity::Column::Start.gt(start))
-        .filter(activity::Column::End.lt(end))
+        .filter(activity::Column::Start.lt(end))
+        .filter(activity::Column::End.gt(start))
         .filter(program::Column::DeletedAt.eq(0))
+        .filter(dimension_program::Column::DeletedAt.eq(0))
+        .filter(dimension::Column::DeletedAt.eq(0))
         .into_model::<DailyStatusResult>()
         .all(db)
         .await?)
 }
+
+pub async fn select_dimension_by_id(
+    db: &DatabaseConnection,
+    id: i64,
+) -> Result<Option<dimension::Model>> {
+    Ok(Dimension::find()
+        .filter(dimension::Column::DeletedAt.eq(0))
+        .filter(dimension::Column::Id.eq(id))
+        .one(db)
+        .await?)
+}
+
+pub async fn select_dimension_list(db: &DatabaseConnection) -> Result<Vec<dimension::Model>> {
+    Ok(Dimension::find()
+        .filter(dimension::Column::DeletedAt.eq(0))
+        .order_by_asc(dimension::Column::Sort)
+        .all(db)
+        .await?)
+}
+
+#[derive(FromQueryResult)]
+pub struct CalendarEventResult {
+    pub id: i64,
+    pub group_id: i64,
+    pub name: String,
+    pub start: i64,
+    pub end: i64,
+}
+
+pub async fn select_note_for_plan_calendar(
+    db: &DatabaseConnection,
+    plan_id: i64,
+    start: i64,
+    end: i64,
+) -> Result<Vec<CalendarEventResult>> {
+    Ok(Note::find()
+        .select_only()
+        .column(note::Column::Id)
+        .column_as(note::Column::LabelId, "group_id")
+        .column(label::Column::Name)
+        .column(note::Column::Start)
+        .column(note::Column::End)
+        .join(InnerJoin, note::Relation::Label.def())
+        .filter(note::Column::DeletedAt.eq(0))
+        .filter(note::Column::PlanId.eq(plan_id))
+        .filter(note::Column::Start.lt(end))
+        .filter(note::Column::End.gt(start))
+        .filter(label::Column::DeletedAt.eq(0))
+        .order_by_asc(note::Column::Start)
+        .into_model::<CalendarEventResult>()
+        .all(db)
+        .await?)
+}
+
+pub async fn select_note_for_dimension_calendar(
+    db: &DatabaseConnection,
+    dimension_id: i64,
+    start: i64,
+    end: i64,
+) -> Result<Vec<CalendarEventResult>> {
+    Ok(Note::find()
+        .select_only()
+        .column(note::Column::Id)
+        .column_as(note::Column::LabelId, "group_id")
+        .column(label::Column::Name)
+        .column(note::Column::Start)
+        .column(note::Column::End)
+        .join(InnerJoin, note::Relation::Label.def())
+        .join(InnerJoin, label::Relation::DimensionLabel.def())
+        .filter(note::Column::DeletedAt.eq(0))
+        .filter(note::Column::Start.lt(end))
+        .filter(note::Column::End.gt(start))
+        .filter(label::Column::DeletedAt.eq(0))
+        .filter(dimension_label::Column::DeletedAt.eq(0))
+        .filter(dimension_label::Column::DimensionId.eq(dimension_id))
+        .group_by(note::Column::Id)
+        .order_by_asc(note::Column::Start)
+        .into_model::<CalendarEventResult>()
+        .all(db)
+        .await?)
+}
+
+pub async fn select_activity_for_dimension_calendar(
+    db: &DatabaseConnection,
+    dimension_id: i64,
+    start: i64,
+    end: i64,
+) -> Result<Vec<CalendarEventResult>> {
+    Ok(Activity::find()
+        .select_only()
+        .column(activity::Column::Id)
+        .column_as(activity::Column::ProgramId, "group_id")
+        .column(program::Column::Name)
+        .column(activity::Column::Start)
+        .column(activity::Column::End)
+        .join(InnerJoin, activity::Relation::Program.def())
+        .join(InnerJoin, program::Relation::DimensionProgram.def())
+        .filter(activity::Column::DeletedAt.eq(0))
+        .filter(activity::Column::Start.lt(end))
+        .filter(activity::Column::End.gt(start))
+        .filter(program::Column::DeletedAt.eq(0))
+        .filter(dimension_program::Column::DeletedAt.eq(0))
+        .filter(dimension_program::Column::DimensionId.eq(dimension_id))
+        .group_by(activity::Column::Id)
+        .order_by_asc(activity::Column::Start)
+        .into_model::<CalendarEventResult>()
+        .all(db)
+        .await?)
+}
+
+pub async fn select_timezone_log_list(db: &DatabaseConnection) -> Result<Vec<timezone_log::Model>> {
+    Ok(TimezoneLog::find()
+        .filter(timezone_log::Column::DeletedAt.eq(0))
+        .order_by_asc(timezone_log::Column::Start)
+        .all(db)
+        .await?)
+}
+
+pub async fn create_timezone_log(
+    db: &DatabaseConnection,
+    timezone: String,
+    start: i64,
+) -> Result<timezone_log::Model> {
+    let model = timezone_log::ActiveModel {
+        timezone: Set(timezone),
+        start: Set(start),
+        ..Default::default()
+    };
+    Ok(model.insert(db).await?)
+}
+
+/// Ranges of notes and activities changed since the days they touch were last summarized.
+pub async fn select_daily_summary_dirty_list(
+    db: &impl ConnectionTrait,
+) -> Result<Vec<daily_summary_dirty::Model>> {
+    Ok(DailySummaryDirty::find()
+        .order_by_asc(daily_summary_dirty::Column::Id)
+        .all(db)
+        .await?)
+}
+
+pub async fn delete_daily_summary_dirty(db: &impl ConnectionTrait, last_id: i64) -> Result<()> {
+    DailySummaryDirty::delete_many()
+        .filter(daily_summary_dirty::Column::Id.lte(last_id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Marks the whole history as changed.
+pub async fn create_daily_summary_dirty_for_all(db: &impl ConnectionTrait) -> Result<()> {
+    db.execute_unprepared(
+        r#"
+        INSERT INTO "daily_summary_dirty" ("start", "end")
+        SELECT MIN("start"), MAX("end") FROM (
+            SELECT "start", "end" FROM "note" WHERE "deleted_at" = 0
+            UNION ALL
+            SELECT "start", "end" FROM "activity" WHERE "deleted_at" = 0
+        )
+        HAVING COUNT(*) > 0
+        "#,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Any row summarized with another clock.
+pub async fn select_stale_daily_summary(
+    db: &DatabaseConnection,
+    clock: String,
+) -> Result<Option<daily_summary::Model>> {
+    Ok(DailySummary::find()
+        .filter(daily_summary::Column::Clock.ne(clock))
+        .one(db)
+        .await?)
+}
+
+pub async fn delete_daily_summary_all(db: &impl ConnectionTrait) -> Result<()> {
+    DailySummary::delete_many().exec(db).await?;
+    Ok(())
+}
+
+pub async fn delete_daily_summary_by_date(
+    db: &impl ConnectionTrait,
+    date_list: Vec<String>,
+) -> Result<()> {
+    DailySummary::delete_many()
+        .filter(daily_summary::Column::Date.is_in(date_list))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub struct InsertDailySummary {
+    pub date: String,
+    pub start: i64,
+    pub end: i64,
+    pub label_id: Option<i64>,
+    pub program_id: Option<i64>,
+    pub total: i64,
+    pub clock: String,
+}
+
+pub async fn create_daily_summary_list(
+    db: &impl ConnectionTrait,
+    list: Vec<InsertDailySummary>,
+) -> Result<()> {
+    if list.is_empty() {
+        return Ok(());
+    }
+    DailySummary::insert_many(list.into_iter().map(|data| daily_summary::ActiveModel {
+        date: Set(data.date),
+        start: Set(data.start),
+        end: Set(data.end),
+        label_id: Set(data.label_id),
+        program_id: Set(data.program_id),
+        total: Set(data.total),
+        clock: Set(data.clock),
+        ..Default::default()
+    }))
+    .exec(db)
+    .await?;
+    Ok(())
+}
+
+/// Summarized days lying within the range, in the same shape as [`select_daily_status`].
+pub async fn select_daily_summary(
+    db: &DatabaseConnection,
+    start: i64,
+    end: i64,
+    busy: bool,
+) -> Result<Vec<DailyStatusGroupResult>> {
+    let mut query = DailySummary::find()
+        .select_only()
+        .column(daily_summary::Column::Date)
+        .column_as(
+            Expr::cust(
+                "CASE WHEN daily_summary.label_id IS NOT NULL THEN CONCAT('label_', daily_summary.label_id) \
+                WHEN daily_summary.program_id IS NOT NULL THEN CONCAT('program_', daily_summary.program_id) \
+                ELSE '' END",
+            ),
+            "key",
+        )
+        .column_as(
+            Expr::cust("COALESCE(label.name, program.name, '')"),
+            "name",
+        )
+        .column_as(
+            Expr::cust("COALESCE(label.color, program.color, '')"),
+            "color",
+        )
+        .column(daily_summary::Column::Total)
+        .join(LeftJoin, daily_summary::Relation::Label.def())
+        .join(LeftJoin, daily_summary::Relation::Program.def())
+        .filter(daily_summary::Column::Start.gte(start))
+        .filter(daily_summary::Column::End.lte(end));
+    if !busy {
+        query = query.filter(
+            daily_summary::Column::LabelId
+                .is_not_null()
+                .or(daily_summary::Column::ProgramId.is_not_null()),
+        );
+    }
+    Ok(query
+        .order_by_asc(daily_summary::Column::Start)
+        .order_by_asc(daily_summary::Column::Id)
+        .into_model::<DailyStatusGroupResult>()
+        .all(db)
+        .await?)
+}
+
+pub async fn select_goal_list(db: &DatabaseConnection) -> Result<Vec<goal::Model>> {
+    Ok(Goal::find()
+        .filter(goal::Column::DeletedAt.eq(0))
+        .order_by_asc(goal::Column::Sort)
+        .all(db)
+        .await?)
+}
+
+pub async fn select_usage_limit_list(db: &DatabaseConnection) -> Result<Vec<usage_limit::Model>> {
+    Ok(UsageLimit::find()
+        .filter(usage_limit::Column::DeletedAt.eq(0))
+        .order_by_asc(usage_limit::Column::Sort)
+        .all(db)
+        .await?)
+}
+
+/// Snoozes of any limit still in effect at `time`.
+pub async fn select_usage_limit_snooze_list(
+    db: &DatabaseConnection,
+    time: i64,
+) -> Result<Vec<usage_limit_snooze::Model>> {
+    Ok(UsageLimitSnooze::find()
+        .filter(usage_limit_snooze::Column::DeletedAt.eq(0))
+        .filter(usage_limit_snooze::Column::Start.lte(time))
+        .filter(usage_limit_snooze::Column::End.gt(time))
+        .all(db)
+        .await?)
+}
+
+pub async fn create_usage_limit_snooze(
+    db: &DatabaseConnection,
+    usage_limit_id: i64,
+    start: i64,
+    end: i64,
+) -> Result<usage_limit_snooze::Model> {
+    let model = usage_limit_snooze::ActiveModel {
+        usage_limit_id: Set(usage_limit_id),
+        start: Set(start),
+        end: Set(end),
+        ..Default::default()
+    };
+    Ok(model.insert(db).await?)
+}
+
+/// Time blocks of live labels that may occur in the range, recurring ones are not expanded.
+pub async fn select_time_block_list(
+    db: &DatabaseConnection,
+    start: i64,
+    end: i64,
+) -> Result<Vec<time_block::Model>> {
+    Ok(TimeBlock::find()
+        .join(InnerJoin, time_block::Relation::Label.def())
+        .filter(time_block::Column::DeletedAt.eq(0))
+        .filter(label::Column::DeletedAt.eq(0))
+        .filter(time_block::Column::Start.lt(end))
+        .filter(
+            time_block::Column::Rrule
+                .is_not_null()
+                .or(time_block::Column::End.gt(start)),
+        )
+        .order_by_asc(time_block::Column::Start)
+        .all(db)
+        .await?)
+}
+
+/// Templates of live labels with their label.
+pub async fn select_note_template_list(
+    db: &DatabaseConnection,
+) -> Result<Vec<(note_template::Model, label::Model)>> {
+    Ok(NoteTemplate::find()
+        .find_also_related(Label)
+        .filter(note_template::Column::DeletedAt.eq(0))
+        .filter(label::Column::DeletedAt.eq(0))
+        .order_by_asc(note_template::Column::Sort)
+        .all(db)
+        .await?
+        .into_iter()
+        .filter_map(|(template, label)| Some((template, label?)))
+        .collect())
+}
+
+pub async fn select_note_template_by_id(
+    db: &DatabaseConnection,
+    id: i64,
+) -> Result<Option<note_template::Model>> {
+    Ok(NoteTemplate::find()
+        .filter(note_template::Column::DeletedAt.eq(0))
+        .filter(note_template::Column::Id.eq(id))
+        .one(db)
+        .await?)
+}
+
+pub async fn update_note_template_materialized_until(
+    db: &impl ConnectionTrait,
+    id: i64,
+    materialized_until: i64,
+) -> Result<()> {
+    NoteTemplate::update_many()
+        .col_expr(
+            note_template::Column::MaterializedUntil,
+            Expr::value(materialized_until),
+        )
+        .filter(note_template::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn select_note_template_exception_list(
+    db: &DatabaseConnection,
+    note_template_id: i64,
+) -> Result<Vec<note_template_exception::Model>> {
+    Ok(NoteTemplateException::find()
+        .filter(note_template_exception::Column::DeletedAt.eq(0))
+        .filter(note_template_exception::Column::NoteTemplateId.eq(note_template_id))
+        .all(db)
+        .await?)
+}
+
+pub struct InsertNoteTemplateException {
+    pub note_template_id: i64,
+    pub occurrence: i64,
+    pub skip: bool,
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+}
+
+/// Replaces the exception of the occurrence.
+pub async fn create_note_template_exception(
+    db: &impl ConnectionTrait,
+    data: InsertNoteTemplateException,
+) -> Result<i64> {
+    let now = Utc::now().timestamp_millis();
+    NoteTemplateException::update_many()
+        .col_expr(note_template_exception::Column::DeletedAt, Expr::value(now))
+        .filter(note_template_exception::Column::DeletedAt.eq(0))
+        .filter(note_template_exception::Column::NoteTemplateId.eq(data.note_template_id))
+        .filter(note_template_exception::Column::Occurrence.eq(data.occurrence))
+        .exec(db)
+        .await?;
+    let model = note_template_exception::ActiveModel {
+        note_template_id: Set(data.note_template_id),
+        occurrence: Set(data.occurrence),
+        skip: Set(data.skip),
+        start: Set(data.start),
+        end: Set(data.end),
+        ..Default::default()
+    };
+    Ok(model.insert(db).await?.id)
+}
+
+/// The note materialized for the occurrence, also when it was removed.
+pub async fn select_note_by_occurrence(
+    db: &impl ConnectionTrait,
+    note_template_id: i64,
+    occurrence: i64,
+) -> Result<Option<note::Model>> {
+    Ok(Note::find()
+        .filter(note::Column::NoteTemplateId.eq(note_template_id))
+        .filter(note::Column::Occurrence.eq(occurrence))
+        .one(db)
+        .await?)
+}
+
+pub struct InsertTemplateNote {
+    pub start: i64,
+    pub end: i64,
+    pub plan_id: i64,
+    pub label_id: i64,
+    pub note_template_id: i64,
+    pub occurrence: i64,
+}
+
+/// Creates the notes, skipping occurrences that already have one.
+pub async fn create_template_note_list(
+    db: &impl ConnectionTrait,
+    list: Vec<InsertTemplateNote>,
+) -> Result<()> {
+    let model_list = list.into_iter().map(|data| note::ActiveModel {
+        start: Set(data.start),
+        end: Set(data.end),
+        plan_id: Set(data.plan_id),
+        label_id: Set(data.label_id),
+        note_template_id: Set(Some(data.note_template_id)),
+        occurrence: Set(Some(data.occurrence)),
+        ..Default::default()
+    });
+    Note::insert_many(model_list)
+        .on_conflict(
+            OnConflict::columns([note::Column::NoteTemplateId, note::Column::Occurrence])
+                .do_nothing()
+                .to_owned(),
+        )
+        .do_nothing()
+        .exec_without_returning(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn update_note_range(
+    db: &impl ConnectionTrait,
+    id: i64,
+    start: i64,
+    end: i64,
+) -> Result<()> {
+    Note::update_many()
+        .col_expr(note::Column::Start, Expr::value(start))
+        .col_expr(note::Column::End, Expr::value(end))
+        .col_expr(
+            note::Column::UpdatedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(note::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn remove_note(db: &impl ConnectionTrait, id: i64) -> Result<()> {
+    Note::update_many()
+        .col_expr(
+            note::Column::DeletedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(note::Column::Id.eq(id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn remove_box_list(db: &impl ConnectionTrait, id_list: Vec<i64>) -> Result<()> {
+    r#box::Entity::update_many()
+        .col_expr(
+            r#box::Column::DeletedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(r#box::Column::Id.is_in(id_list))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn remove_moment_by_box(db: &impl ConnectionTrait, box_id_list: Vec<i64>) -> Result<()> {
+    Moment::update_many()
+        .col_expr(
+            moment::Column::DeletedAt,
+            Expr::value(Utc::now().timestamp_millis()),
+        )
+        .filter(moment::Column::DeletedAt.eq(0))
+        .filter(moment::Column::BoxId.is_in(box_id_list))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn select_change_list(db: &impl ConnectionTrait) -> Result<Vec<change::Model>> {
+    Ok(Change::find()
+        .filter(change::Column::DeletedAt.eq(0))
+        .order_by_asc(change::Column::Id)
+        .all(db)
+        .await?)
+}
+
+pub async fn create_change(
+    db: &impl ConnectionTrait,
+    action: &str,
+    target_id: Option<i64>,
+) -> Result<i64> {
+    let model = change::ActiveModel {
+        action: Set(action.to_string()),
+        target_id: Set(target_id),
+        ..Default::default()
+    };
+    Ok(model.insert(db).await?.id)
+}
+
+pub struct InsertChangeEntry {
+    pub table_name: String,
+    pub row_id: i64,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub row_id_list: Option<String>,
+}
+
+pub async fn create_change_entry_list(
+    db: &impl ConnectionTrait,
+    change_id: i64,
+    list: Vec<InsertChangeEntry>,
+) -> Result<()> {
+    let model_list = list.into_iter().map(|data| change_entry::ActiveModel {
+        change_id: Set(change_id),
+        table_name: Set(data.table_name),
+        row_id: Set(data.row_id),
+        before: Set(data.before),
+        after: Set(data.after),
+        row_id_list: Set(data.row_id_list),
+        ..Default::default()
+    });
+    ChangeEntry::insert_many(model_list)
+        .exec_without_returning(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn select_change_entry_list(
+    db: &impl ConnectionTrait,
+    change_id_list: Vec<i64>,
+) -> Result<Vec<change_entry::Model>> {
+    Ok(ChangeEntry::find()
+        .filter(change_entry::Column::DeletedAt.eq(0))
+        .filter(change_entry::Column::ChangeId.is_in(change_id_list))
+        .order_by_asc(change_entry::Column::Id)
+        .all(db)
+        .await?)
+}
@@ -22,6 +22,9 @@ pub enum Error {
     #[error(transparent)]
     Tauri(#[from] tauri::Error),
 
+    #[error(transparent)]
+    Notification(#[from] tauri_plugin_notification::Error),
+
     #[error(transparent)]
     Reqwest(#[from] reqwest::Error),
 
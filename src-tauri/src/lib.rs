@@ -16,7 +16,18 @@ use std::{
 };
 
 use anyhow::anyhow;
-use database::{service, service::DailyStatus};
+use chrono::Utc;
+use database::{
+    bucket::BucketUnit,
+    journal::{self, ChangeResult},
+    service,
+    service::{
+        BucketStatus, ConflictPolicy, DailyStatus, DailySummaryMismatch, DaySetting, FocusLevel,
+        FocusStatus, GoalStatus, LabelPatch, LimitStatus, NoteOccurrence, NoteScope,
+        ProgramBreakdown, ProgramPatch, RollupLevel, TimeBlockReport, WorkSession,
+    },
+    sql::{InsertLabel, InsertNote, InsertRemark},
+};
 use lazy_static::lazy_static;
 use parse_changelog::Changelog;
 use reqwest::StatusCode;
@@ -36,8 +47,10 @@ use zip_extensions::{zip_create_from_directory, zip_extract};
 
 pub use error::Result;
 use module::{
-    autostart,
+    autostart, limit,
     obsidian::{self, ObsidianGroup, ObsidianNote, SearchItem},
+    reminder::{self, BreakSetting, ReminderText},
+    summary, template,
 };
 
 lazy_static! {
@@ -75,10 +88,79 @@ fn get_autostart_bin() -> String {
 }
 
 pub async fn get_db(app: &AppHandle) -> DatabaseConnection {
+    try_get_db(app).await.unwrap()
+}
+
+/// The database, once the frontend has loaded it.
+pub async fn try_get_db(app: &AppHandle) -> Option<DatabaseConnection> {
     let instances = app.state::<DbInstances>();
     let instances = instances.inner().0.lock().await;
-    let db = instances.get("sqlite:data.db").unwrap();
-    db.clone()
+    instances.get("sqlite:data.db").cloned()
+}
+
+pub fn get_day_setting(app: &AppHandle) -> Result<DaySetting> {
+    let stores = app.state::<StoreCollection<Wry>>();
+    let setting = with_store(app.clone(), stores, "config.json", |store| {
+        Ok(DaySetting {
+            timezone: store
+                .get("timezone")
+                .and_then(|value| value.as_str())
+                .map(String::from),
+            day_start_hour: store
+                .get("dayStartHour")
+                .and_then(|value| value.as_u64())
+                .unwrap_or(0) as u32,
+            travel_mode: store
+                .get("travelMode")
+                .and_then(|value| value.as_bool())
+                .unwrap_or(false),
+        })
+    })
+    .map_err(|e| anyhow!(e.to_string()))?;
+    Ok(setting)
+}
+
+pub fn get_break_setting(app: &AppHandle) -> Result<BreakSetting> {
+    let stores = app.state::<StoreCollection<Wry>>();
+    let setting = with_store(app.clone(), stores, "config.json", |store| {
+        let minute = |key: &str, default: u64| {
+            store
+                .get(key)
+                .and_then(|value| value.as_u64())
+                .unwrap_or(default) as i64
+                * 60
+                * 1000
+        };
+        Ok(BreakSetting {
+            enabled: store
+                .get("breakReminder")
+                .and_then(|value| value.as_bool())
+                .unwrap_or(false),
+            work_duration: minute("breakReminderWorkMinute", 50),
+            break_duration: minute("breakReminderBreakMinute", 5),
+            excluded_label_list: store
+                .get("breakReminderExcludedLabels")
+                .and_then(|value| value.as_array())
+                .map(|list| list.iter().filter_map(|id| id.as_i64()).collect())
+                .unwrap_or_default(),
+        })
+    })
+    .map_err(|e| anyhow!(e.to_string()))?;
+    Ok(setting)
+}
+
+pub fn get_note_conflict_policy(app: &AppHandle) -> Result<ConflictPolicy> {
+    let stores = app.state::<StoreCollection<Wry>>();
+    let policy = with_store(app.clone(), stores, "config.json", |store| {
+        Ok(
+            match store.get("noteConflict").and_then(|value| value.as_str()) {
+                Some("resolve") => ConflictPolicy::Resolve,
+                _ => ConflictPolicy::Reject,
+            },
+        )
+    })
+    .map_err(|e| anyhow!(e.to_string()))?;
+    Ok(policy)
 }
 
 fn start_server(app_handle: &AppHandle, server_port: u16) {
@@ -119,6 +201,90 @@ pub fn run() {
             sql: include_str!("../../prisma/migrations/20250105032822_/migration.sql"),
             kind: MigrationKind::Up,
         },
+        Migration {
+            version: 6,
+            description: "add timezone log",
+            sql: include_str!("../../prisma/migrations/20250210093012_/migration.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 7,
+            description: "add time index",
+            sql: include_str!("../../prisma/migrations/20250214120533_/migration.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 8,
+            description: "add daily summary",
+            sql: include_str!("../../prisma/migrations/20250218150244_/migration.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 9,
+            description: "add goal",
+            sql: include_str!("../../prisma/migrations/20250222083015_/migration.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 10,
+            description: "add usage limit",
+            sql: include_str!("../../prisma/migrations/20250225101147_/migration.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 11,
+            description: "add time block",
+            sql: include_str!("../../prisma/migrations/20250301094512_/migration.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 12,
+            description: "add note template",
+            sql: include_str!("../../prisma/migrations/20250304081236_/migration.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 13,
+            description: "add program alias",
+            sql: include_str!("../../prisma/migrations/20250311090245_/migration.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 14,
+            description: "add label parent",
+            sql: include_str!("../../prisma/migrations/20250314083520_/migration.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 15,
+            description: "add change journal",
+            sql: include_str!("../../prisma/migrations/20250318091204_/migration.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 16,
+            description: "add note title",
+            sql: include_str!("../../prisma/migrations/20250320074512_/migration.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 17,
+            description: "seed daily summary",
+            sql: include_str!("../../prisma/migrations/20250322091530_/migration.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 18,
+            description: "store time block recurrence as rrule",
+            sql: include_str!("../../prisma/migrations/20250324102210_/migration.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 19,
+            description: "journal id remaps",
+            sql: include_str!("../../prisma/migrations/20250327021540_/migration.sql"),
+            kind: MigrationKind::Up,
+        },
     ];
 
     #[tauri::command]
@@ -234,9 +400,433 @@ pub fn run() {
         app: tauri::AppHandle,
         start: i64,
         end: i64,
+        busy: Option<bool>,
     ) -> Result<HashMap<String, DailyStatus>> {
         let db = get_db(&app).await;
-        Ok(service::get_active_status_calendar_map(&db, start, end).await?)
+        let clock = service::get_clock(&db, &get_day_setting(&app)?).await?;
+        Ok(
+            service::get_active_status_calendar_map(&db, start, end, busy.unwrap_or(false), &clock)
+                .await?,
+        )
+    }
+
+    #[tauri::command]
+    async fn get_bucket_status_list(
+        app: tauri::AppHandle,
+        start: i64,
+        end: i64,
+        unit: BucketUnit,
+        busy: Option<bool>,
+    ) -> Result<Vec<BucketStatus>> {
+        let db = get_db(&app).await;
+        let clock = service::get_clock(&db, &get_day_setting(&app)?).await?;
+        Ok(
+            service::get_bucket_status_list(&db, start, end, unit, busy.unwrap_or(false), &clock)
+                .await?,
+        )
+    }
+
+    #[tauri::command]
+    async fn get_rollup_status_list(
+        app: tauri::AppHandle,
+        start: i64,
+        end: i64,
+        level: RollupLevel,
+        unit: BucketUnit,
+    ) -> Result<Vec<BucketStatus>> {
+        let db = get_db(&app).await;
+        let clock = service::get_clock(&db, &get_day_setting(&app)?).await?;
+        Ok(service::get_rollup_status_list(&db, start, end, level, unit, &clock).await?)
+    }
+
+    #[tauri::command]
+    async fn get_work_session_list(
+        app: tauri::AppHandle,
+        start: i64,
+        end: i64,
+        idle_threshold: i64,
+    ) -> Result<Vec<WorkSession>> {
+        let db = get_db(&app).await;
+        Ok(service::get_work_session_list(&db, start, end, idle_threshold).await?)
+    }
+
+    #[tauri::command]
+    async fn get_focus_status_list(
+        app: tauri::AppHandle,
+        start: i64,
+        end: i64,
+        level: FocusLevel,
+        unit: BucketUnit,
+        idle_threshold: i64,
+    ) -> Result<Vec<FocusStatus>> {
+        let db = get_db(&app).await;
+        let clock = service::get_clock(&db, &get_day_setting(&app)?).await?;
+        Ok(
+            service::get_focus_status_list(&db, start, end, level, unit, idle_threshold, &clock)
+                .await?,
+        )
+    }
+
+    #[tauri::command]
+    async fn get_program_breakdown(
+        app: tauri::AppHandle,
+        scope: NoteScope,
+        start: Option<i64>,
+        end: Option<i64>,
+    ) -> Result<ProgramBreakdown> {
+        let db = get_db(&app).await;
+        Ok(service::get_program_breakdown(&db, scope, start, end).await?)
+    }
+
+    #[tauri::command]
+    async fn get_goal_status_list(app: tauri::AppHandle) -> Result<Vec<GoalStatus>> {
+        let db = get_db(&app).await;
+        let clock = service::get_clock(&db, &get_day_setting(&app)?).await?;
+        Ok(service::get_goal_status_list(&db, &clock).await?)
+    }
+
+    #[tauri::command]
+    async fn get_limit_status_list(app: tauri::AppHandle) -> Result<Vec<LimitStatus>> {
+        let db = get_db(&app).await;
+        let clock = service::get_clock(&db, &get_day_setting(&app)?).await?;
+        Ok(service::get_limit_status_list(&db, &clock).await?)
+    }
+
+    #[tauri::command]
+    async fn snooze_usage_limit(app: tauri::AppHandle, id: i64, duration: i64) -> Result<()> {
+        let db = get_db(&app).await;
+        Ok(service::snooze_usage_limit(&db, id, duration).await?)
+    }
+
+    #[tauri::command]
+    async fn get_time_block_report_list(
+        app: tauri::AppHandle,
+        start: i64,
+        end: i64,
+        unit: BucketUnit,
+    ) -> Result<Vec<TimeBlockReport>> {
+        let db = get_db(&app).await;
+        let clock = service::get_clock(&db, &get_day_setting(&app)?).await?;
+        Ok(service::get_time_block_report_list(&db, start, end, unit, &clock).await?)
+    }
+
+    #[tauri::command]
+    async fn get_note_occurrence_list(
+        app: tauri::AppHandle,
+        note_template_id: i64,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<NoteOccurrence>> {
+        let db = get_db(&app).await;
+        let clock = service::get_clock(&db, &get_day_setting(&app)?).await?;
+        Ok(service::get_note_occurrence_list(&db, note_template_id, start, end, &clock).await?)
+    }
+
+    #[tauri::command]
+    async fn skip_note_occurrence(
+        app: tauri::AppHandle,
+        note_template_id: i64,
+        occurrence: i64,
+    ) -> Result<()> {
+        let db = get_db(&app).await;
+        let clock = service::get_clock(&db, &get_day_setting(&app)?).await?;
+        Ok(service::skip_note_occurrence(&db, note_template_id, occurrence, &clock).await?)
+    }
+
+    #[tauri::command]
+    async fn override_note_occurrence(
+        app: tauri::AppHandle,
+        note_template_id: i64,
+        occurrence: i64,
+        start: i64,
+        end: i64,
+    ) -> Result<()> {
+        let db = get_db(&app).await;
+        let clock = service::get_clock(&db, &get_day_setting(&app)?).await?;
+        Ok(
+            service::override_note_occurrence(
+                &db,
+                note_template_id,
+                occurrence,
+                start,
+                end,
+                &clock,
+            )
+            .await?,
+        )
+    }
+
+    #[tauri::command]
+    async fn create_note(
+        app: tauri::AppHandle,
+        plan_id: i64,
+        label_id: i64,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<i64>> {
+        let db = get_db(&app).await;
+        let now = Utc::now().timestamp_millis();
+        let span_list = service::check_note(
+            &db,
+            None,
+            (start, end),
+            now,
+            get_note_conflict_policy(&app)?,
+        )
+        .await?
+        .map_err(anyhow::Error::from)?;
+        let note_list = service::create_note(
+            &db,
+            InsertNote {
+                start,
+                end,
+                plan_id,
+                label_id,
+                title: String::new(),
+            },
+            span_list,
+        )
+        .await?;
+        Ok(note_list.into_iter().map(|note| note.id).collect())
+    }
+
+    #[tauri::command]
+    async fn update_note(app: tauri::AppHandle, id: i64, start: i64, end: i64) -> Result<Vec<i64>> {
+        let db = get_db(&app).await;
+        let now = Utc::now().timestamp_millis();
+        let span_list = service::check_note(
+            &db,
+            Some(id),
+            (start, end),
+            now,
+            get_note_conflict_policy(&app)?,
+        )
+        .await?
+        .map_err(anyhow::Error::from)?;
+        Ok(service::update_note(&db, id, span_list).await?)
+    }
+
+    #[tauri::command]
+    async fn extend_note(app: tauri::AppHandle, id: i64) -> Result<()> {
+        let db = get_db(&app).await;
+        let now = Utc::now().timestamp_millis();
+        service::extend_running_note(&db, id, now, get_note_conflict_policy(&app)?)
+            .await?
+            .map_err(anyhow::Error::from)?;
+        Ok(())
+    }
+
+    #[tauri::command]
+    async fn split_activity(app: tauri::AppHandle, id: i64, at: i64) -> Result<Vec<i64>> {
+        let db = get_db(&app).await;
+        let now = Utc::now().timestamp_millis();
+        Ok(service::split_activity(&db, id, at, now).await?)
+    }
+
+    #[tauri::command]
+    async fn merge_activity(app: tauri::AppHandle, id_list: Vec<i64>) -> Result<Vec<i64>> {
+        let db = get_db(&app).await;
+        let now = Utc::now().timestamp_millis();
+        Ok(service::merge_activity(&db, id_list, now).await?)
+    }
+
+    #[tauri::command]
+    async fn trim_activity(
+        app: tauri::AppHandle,
+        id: i64,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<i64>> {
+        let db = get_db(&app).await;
+        let now = Utc::now().timestamp_millis();
+        Ok(service::trim_activity(&db, id, start, end, now).await?)
+    }
+
+    #[tauri::command]
+    async fn reassign_activity(
+        app: tauri::AppHandle,
+        id: i64,
+        program_id: i64,
+    ) -> Result<Vec<i64>> {
+        let db = get_db(&app).await;
+        let now = Utc::now().timestamp_millis();
+        Ok(service::reassign_activity(&db, id, program_id, now).await?)
+    }
+
+    #[tauri::command]
+    async fn merge_program(
+        app: tauri::AppHandle,
+        source_id_list: Vec<i64>,
+        target_id: i64,
+    ) -> Result<()> {
+        let db = get_db(&app).await;
+        Ok(service::merge_program(&db, source_id_list, target_id).await?)
+    }
+
+    #[tauri::command]
+    async fn resolve_program_path(app: tauri::AppHandle, path: String) -> Result<Option<i64>> {
+        let db = get_db(&app).await;
+        let program = service::resolve_program(&db, &path).await?;
+        Ok(program.map(|program| program.id))
+    }
+
+    #[tauri::command]
+    async fn create_program_alias(
+        app: tauri::AppHandle,
+        program_id: i64,
+        pattern: String,
+        kind: i64,
+    ) -> Result<i64> {
+        let db = get_db(&app).await;
+        Ok(service::create_program_alias(&db, program_id, pattern, kind).await?)
+    }
+
+    #[tauri::command]
+    async fn move_label(
+        app: tauri::AppHandle,
+        id: i64,
+        plan_id: i64,
+        parent_id: Option<i64>,
+    ) -> Result<()> {
+        let db = get_db(&app).await;
+        Ok(service::move_label(&db, id, plan_id, parent_id).await?)
+    }
+
+    #[tauri::command]
+    async fn create_label(
+        app: tauri::AppHandle,
+        name: String,
+        color: String,
+        plan_id: i64,
+    ) -> Result<i64> {
+        let db = get_db(&app).await;
+        let label = service::create_label(
+            &db,
+            InsertLabel {
+                name,
+                color,
+                plan_id,
+            },
+        )
+        .await?;
+        Ok(label.id)
+    }
+
+    #[tauri::command]
+    async fn update_label_list(app: tauri::AppHandle, patch_list: Vec<LabelPatch>) -> Result<()> {
+        let db = get_db(&app).await;
+        Ok(service::update_label_list(&db, patch_list).await?)
+    }
+
+    #[tauri::command]
+    async fn remove_label(app: tauri::AppHandle, id_list: Vec<i64>, relation: bool) -> Result<()> {
+        let db = get_db(&app).await;
+        Ok(service::remove_label(&db, id_list, relation).await?)
+    }
+
+    #[tauri::command]
+    async fn update_program_list(
+        app: tauri::AppHandle,
+        patch_list: Vec<ProgramPatch>,
+    ) -> Result<()> {
+        let db = get_db(&app).await;
+        Ok(service::update_program_list(&db, patch_list).await?)
+    }
+
+    #[tauri::command]
+    async fn remove_program(
+        app: tauri::AppHandle,
+        id_list: Vec<i64>,
+        relation: bool,
+    ) -> Result<()> {
+        let db = get_db(&app).await;
+        Ok(service::remove_program(&db, id_list, relation).await?)
+    }
+
+    #[tauri::command]
+    async fn remove_note(app: tauri::AppHandle, id: i64) -> Result<()> {
+        let db = get_db(&app).await;
+        Ok(service::remove_note(&db, id).await?)
+    }
+
+    #[tauri::command]
+    async fn remove_box(app: tauri::AppHandle, id_list: Vec<i64>, relation: bool) -> Result<()> {
+        let db = get_db(&app).await;
+        Ok(service::remove_box(&db, id_list, relation).await?)
+    }
+
+    #[tauri::command]
+    async fn create_remark(
+        app: tauri::AppHandle,
+        title: String,
+        desc: String,
+        arg: String,
+        program_id: i64,
+        time: i64,
+    ) -> Result<i64> {
+        let db = get_db(&app).await;
+        let remark = service::create_remark(
+            &db,
+            InsertRemark {
+                title,
+                desc,
+                arg,
+                program_id,
+                time,
+            },
+        )
+        .await?;
+        Ok(remark.id)
+    }
+
+    #[tauri::command]
+    async fn undo_change(app: tauri::AppHandle) -> Result<Option<i64>> {
+        let db = get_db(&app).await;
+        Ok(journal::undo_change(&db).await?)
+    }
+
+    #[tauri::command]
+    async fn redo_change(app: tauri::AppHandle) -> Result<Option<i64>> {
+        let db = get_db(&app).await;
+        Ok(journal::redo_change(&db).await?)
+    }
+
+    #[tauri::command]
+    async fn get_change_list(
+        app: tauri::AppHandle,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<ChangeResult>> {
+        let db = get_db(&app).await;
+        Ok(journal::get_change_list(&db, start, end).await?)
+    }
+
+    #[tauri::command]
+    fn postpone_break_reminder(minutes: i64) {
+        reminder::postpone(minutes);
+    }
+
+    #[tauri::command]
+    fn update_break_reminder_text(title: String, body: String) {
+        reminder::set_text(ReminderText { title, body });
+    }
+
+    #[tauri::command]
+    async fn rebuild_daily_summary(app: tauri::AppHandle) -> Result<()> {
+        let db = get_db(&app).await;
+        let clock = service::get_clock(&db, &get_day_setting(&app)?).await?;
+        Ok(service::rebuild_daily_summary(&db, &clock).await?)
+    }
+
+    #[tauri::command]
+    async fn check_daily_summary(
+        app: tauri::AppHandle,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<DailySummaryMismatch>> {
+        let db = get_db(&app).await;
+        let clock = service::get_clock(&db, &get_day_setting(&app)?).await?;
+        Ok(service::check_daily_summary(&db, start, end, &clock).await?)
     }
 
     #[tauri::command]
@@ -297,6 +887,7 @@ pub fn run() {
                 .build(),
         )
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
@@ -327,6 +918,44 @@ pub fn run() {
             is_api_service_active,
             open_with_detached,
             get_active_status_calendar_map,
+            get_bucket_status_list,
+            get_rollup_status_list,
+            get_work_session_list,
+            get_focus_status_list,
+            get_program_breakdown,
+            get_goal_status_list,
+            get_limit_status_list,
+            snooze_usage_limit,
+            postpone_break_reminder,
+            update_break_reminder_text,
+            get_time_block_report_list,
+            get_note_occurrence_list,
+            skip_note_occurrence,
+            override_note_occurrence,
+            create_note,
+            update_note,
+            extend_note,
+            split_activity,
+            merge_activity,
+            trim_activity,
+            reassign_activity,
+            merge_program,
+            resolve_program_path,
+            create_program_alias,
+            move_label,
+            create_label,
+            update_label_list,
+            remove_label,
+            update_program_list,
+            remove_program,
+            remove_note,
+            remove_box,
+            create_remark,
+            undo_change,
+            redo_change,
+            get_change_list,
+            rebuild_daily_summary,
+            check_daily_summary,
             read_obsidian,
             get_obsidian_group,
             search_obsidian,
@@ -407,6 +1036,10 @@ pub fn run() {
                 .restore_state(StateFlags::MAXIMIZED | StateFlags::POSITION | StateFlags::SIZE)?;
 
             start_server(&app_handle, server_port);
+            limit::start(app_handle.clone());
+            reminder::start(app_handle.clone());
+            summary::start(app_handle.clone());
+            template::start(app_handle.clone());
 
             Ok(())
         })
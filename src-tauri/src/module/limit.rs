@@ -0,0 +1,72 @@
+//! Checks usage limits while activity is recorded and warns when a budget runs low.
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use serde_json::json;
+use tauri::{AppHandle, Emitter};
+
+use crate::{
+    database::service::{self, LimitLevel},
+    get_day_setting,
+    server::event,
+    try_get_db, Result,
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        // highest level announced for a limit in a period, by limit and period start
+        let mut announced = HashMap::new();
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = check(&app, &mut announced).await {
+                log::error!("failed to check usage limits: {}", e);
+            }
+        }
+    });
+}
+
+async fn check(app: &AppHandle, announced: &mut HashMap<(i64, i64), LimitLevel>) -> Result<()> {
+    // the database is loaded by the frontend
+    let Some(db) = try_get_db(app).await else {
+        return Ok(());
+    };
+    let clock = service::get_clock(&db, &get_day_setting(app)?).await?;
+    let status_list = service::get_limit_status_list(&db, &clock).await?;
+    let current = status_list
+        .iter()
+        .map(|status| (status.id, status.period_start))
+        .collect::<HashSet<_>>();
+    for status in status_list {
+        let key = (status.id, status.period_start);
+        let Some(level) = status.level else {
+            continue;
+        };
+        // announced again once the snooze is over
+        if status.snoozed_until.is_some() {
+            announced.remove(&key);
+            continue;
+        }
+        if announced
+            .get(&key)
+            .is_some_and(|announced| *announced >= level)
+        {
+            continue;
+        }
+        announced.insert(key, level);
+        let event = match level {
+            LimitLevel::Warning => "limit-warning",
+            LimitLevel::Exceeded => "limit-exceeded",
+        };
+        app.emit(event, &status)?;
+        event::publish(event, json!(status));
+    }
+    // periods that are over, or limits that were removed, are not announced anymore
+    announced.retain(|key, _| current.contains(key));
+    Ok(())
+}
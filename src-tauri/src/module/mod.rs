@@ -1,2 +1,6 @@
 pub mod autostart;
+pub mod limit;
 pub mod obsidian;
+pub mod reminder;
+pub mod summary;
+pub mod template;
@@ -0,0 +1,112 @@
+//! Reminds to take a break after a long stretch of continuous activity.
+
+use std::{sync::Mutex, time::Duration};
+
+use chrono::Utc;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use serde_json::json;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::{database::service, get_break_setting, server::event, try_get_db, Result};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+const MINUTE: i64 = 60 * 1000;
+
+lazy_static! {
+    static ref POSTPONED_UNTIL: Mutex<Option<i64>> = Mutex::new(None);
+    static ref TEXT: Mutex<Option<ReminderText>> = Mutex::new(None);
+}
+
+/// Notification text in the language of the frontend, `{minute}` in the body is the active time.
+#[derive(Clone)]
+pub struct ReminderText {
+    pub title: String,
+    pub body: String,
+}
+
+pub struct BreakSetting {
+    pub enabled: bool,
+    /// Milliseconds of continuous activity before a reminder.
+    pub work_duration: i64,
+    /// Milliseconds without activity that count as a break.
+    pub break_duration: i64,
+    /// Labels whose running notes silence the reminder, e.g. meetings.
+    pub excluded_label_list: Vec<i64>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct BreakReminder {
+    work_start: i64,
+    active_time: i64,
+}
+
+pub fn set_text(text: ReminderText) {
+    *TEXT.lock().unwrap() = Some(text);
+}
+
+/// Delays the next reminder by `minutes`, until a break is taken.
+pub fn postpone(minutes: i64) {
+    *POSTPONED_UNTIL.lock().unwrap() = Some(Utc::now().timestamp_millis() + minutes * MINUTE);
+}
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut reminded_at = None;
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = check(&app, &mut reminded_at).await {
+                log::error!("failed to check break reminder: {}", e);
+            }
+        }
+    });
+}
+
+async fn check(app: &AppHandle, reminded_at: &mut Option<i64>) -> Result<()> {
+    let setting = get_break_setting(app)?;
+    if !setting.enabled {
+        return Ok(());
+    }
+    // the database is loaded by the frontend
+    let Some(db) = try_get_db(app).await else {
+        return Ok(());
+    };
+    let now = Utc::now().timestamp_millis();
+    let Some(work_start) = service::get_work_start(&db, now, setting.break_duration).await? else {
+        *reminded_at = None;
+        *POSTPONED_UNTIL.lock().unwrap() = None;
+        return Ok(());
+    };
+    let since = reminded_at.map_or(work_start, |reminded_at| reminded_at.max(work_start));
+    let postponed_until = *POSTPONED_UNTIL.lock().unwrap();
+    let due = match postponed_until {
+        Some(postponed_until) if postponed_until > since => postponed_until,
+        _ => since + setting.work_duration,
+    };
+    if now < due || service::is_label_running(&db, &setting.excluded_label_list, now).await? {
+        return Ok(());
+    }
+    *reminded_at = Some(now);
+    let reminder = BreakReminder {
+        work_start,
+        active_time: now - work_start,
+    };
+    app.emit("break-reminder", &reminder)?;
+    event::publish("break-reminder", json!(reminder));
+    let text = TEXT.lock().unwrap().clone();
+    if let Some(text) = text {
+        app.notification()
+            .builder()
+            .title(text.title)
+            .body(
+                text.body
+                    .replace("{minute}", &(reminder.active_time / MINUTE).to_string()),
+            )
+            .show()?;
+    }
+    Ok(())
+}
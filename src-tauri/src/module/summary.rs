@@ -0,0 +1,32 @@
+//! Keeps the daily summary up to date with the notes and activities changed since the last run,
+//! so the calendar only reads it.
+
+use std::time::Duration;
+
+use tauri::AppHandle;
+
+use crate::{database::service, get_day_setting, try_get_db, Result};
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = refresh(&app).await {
+                log::error!("failed to refresh daily summary: {}", e);
+            }
+        }
+    });
+}
+
+async fn refresh(app: &AppHandle) -> Result<()> {
+    // the database is loaded by the frontend
+    let Some(db) = try_get_db(app).await else {
+        return Ok(());
+    };
+    let clock = service::get_clock(&db, &get_day_setting(app)?).await?;
+    service::refresh_daily_summary(&db, &clock).await?;
+    Ok(())
+}
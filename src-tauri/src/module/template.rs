@@ -0,0 +1,45 @@
+//! Creates the notes of recurring note templates as their occurrences start.
+//!
+//! The same loop logs time zone changes, so travel mode has the history once it is turned on.
+
+use std::time::Duration;
+
+use chrono::Utc;
+use tauri::AppHandle;
+
+use crate::{database::service, get_day_setting, try_get_db, Result};
+
+const MATERIALIZE_INTERVAL: Duration = Duration::from_secs(60);
+
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(MATERIALIZE_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = record_timezone(&app).await {
+                log::error!("failed to record timezone: {}", e);
+            }
+            if let Err(e) = materialize(&app).await {
+                log::error!("failed to materialize note templates: {}", e);
+            }
+        }
+    });
+}
+
+async fn record_timezone(app: &AppHandle) -> Result<()> {
+    let Some(db) = try_get_db(app).await else {
+        return Ok(());
+    };
+    service::record_timezone(&db).await?;
+    Ok(())
+}
+
+async fn materialize(app: &AppHandle) -> Result<()> {
+    // the database is loaded by the frontend
+    let Some(db) = try_get_db(app).await else {
+        return Ok(());
+    };
+    let clock = service::get_clock(&db, &get_day_setting(app)?).await?;
+    service::materialize_note_templates(&db, &clock, Utc::now().timestamp_millis()).await?;
+    Ok(())
+}
@@ -0,0 +1,378 @@
+//! The subset of the aw-server REST API used by ActivityWatch watchers.
+//!
+//! Buckets live in memory, heartbeats are merged with `pulsetime` like aw-server does,
+//! and merged window or editor events are written as `activity` rows.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use chrono::Utc;
+use futures_util::lock::Mutex;
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    database::{
+        service,
+        sql::{self, InsertActivity},
+    },
+    get_db,
+    server::{
+        ensure_loopback,
+        error::CustomError,
+        time::{format_timestamp, parse_timestamp},
+        AppState,
+    },
+};
+
+const BUCKET_WINDOW: &str = "currentwindow";
+const BUCKET_AFK: &str = "afkstatus";
+const BUCKET_EDITOR: &str = "app.editor.activity";
+
+#[derive(Serialize, Clone)]
+struct Bucket {
+    id: String,
+    name: Option<String>,
+    #[serde(rename = "type")]
+    kind: String,
+    client: String,
+    hostname: String,
+    created: String,
+    data: Map<String, Value>,
+    last_updated: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+struct Event {
+    #[serde(default)]
+    id: Option<i64>,
+    timestamp: String,
+    duration: f64,
+    data: Map<String, Value>,
+}
+
+struct LastEvent {
+    start: i64,
+    end: i64,
+    data: Map<String, Value>,
+    activity_id: Option<i64>,
+}
+
+struct BucketState {
+    bucket: Bucket,
+    last: Option<LastEvent>,
+}
+
+/// Each bucket has a lock of its own, held while its heartbeat is written so the next one
+/// merges into the activity it left. Heartbeats of other buckets are not held up by it.
+#[derive(Default)]
+pub struct BucketStore {
+    buckets: Mutex<HashMap<String, Arc<Mutex<BucketState>>>>,
+    afk: AtomicBool,
+}
+
+#[derive(Deserialize)]
+struct CreateBucketRequest {
+    name: Option<String>,
+    #[serde(rename = "type")]
+    kind: String,
+    client: String,
+    hostname: String,
+}
+
+#[derive(Deserialize)]
+struct HeartbeatQuery {
+    pulsetime: f64,
+}
+
+fn guess_bucket_type(bucket_id: &str) -> &'static str {
+    if bucket_id.starts_with("aw-watcher-afk") {
+        BUCKET_AFK
+    } else if bucket_id.starts_with("aw-watcher-window") {
+        BUCKET_WINDOW
+    } else {
+        BUCKET_EDITOR
+    }
+}
+
+/// Resolves the program an event is recorded against, `None` for events that are not activities.
+fn program_of(bucket: &Bucket, data: &Map<String, Value>) -> Option<String> {
+    match bucket.kind.as_str() {
+        BUCKET_WINDOW => data
+            .get("app")
+            .and_then(|app| app.as_str())
+            .map(String::from),
+        BUCKET_EDITOR => Some(bucket.client.clone()),
+        _ => None,
+    }
+}
+
+#[get("/0/info")]
+pub async fn info(req: HttpRequest) -> actix_web::Result<impl Responder> {
+    ensure_loopback(&req)?;
+    let hostname = std::env::var("HOSTNAME")
+        .or(std::env::var("COMPUTERNAME"))
+        .unwrap_or("unknown".to_string());
+    Ok(HttpResponse::Ok().json(json!({
+        "hostname": hostname,
+        "version": format!("v{} (shion)", env!("CARGO_PKG_VERSION")),
+        "testing": false,
+        "device_id": hostname,
+    })))
+}
+
+#[get("/0/buckets/")]
+pub async fn get_buckets(
+    req: HttpRequest,
+    store: web::Data<BucketStore>,
+) -> actix_web::Result<impl Responder> {
+    ensure_loopback(&req)?;
+    let mut buckets = HashMap::new();
+    for (id, state) in store.buckets.lock().await.iter() {
+        buckets.insert(id.clone(), state.lock().await.bucket.clone());
+    }
+    Ok(HttpResponse::Ok().json(buckets))
+}
+
+#[get("/0/buckets/{bucket_id}")]
+pub async fn get_bucket(
+    req: HttpRequest,
+    path: web::Path<String>,
+    store: web::Data<BucketStore>,
+) -> actix_web::Result<impl Responder> {
+    ensure_loopback(&req)?;
+    let state = store
+        .buckets
+        .lock()
+        .await
+        .get(&path.into_inner())
+        .cloned()
+        .ok_or(CustomError::NotFound)?;
+    let bucket = state.lock().await.bucket.clone();
+    Ok(HttpResponse::Ok().json(bucket))
+}
+
+#[post("/0/buckets/{bucket_id}")]
+pub async fn create_bucket(
+    req: HttpRequest,
+    path: web::Path<String>,
+    request: web::Json<CreateBucketRequest>,
+    store: web::Data<BucketStore>,
+) -> actix_web::Result<impl Responder> {
+    ensure_loopback(&req)?;
+    let id = path.into_inner();
+    let mut buckets = store.buckets.lock().await;
+    if buckets.contains_key(&id) {
+        return Ok(HttpResponse::NotModified().finish());
+    }
+    let request = request.into_inner();
+    let bucket = Bucket {
+        id: id.clone(),
+        name: request.name,
+        kind: request.kind,
+        client: request.client,
+        hostname: request.hostname,
+        created: Utc::now().to_rfc3339(),
+        data: Map::new(),
+        last_updated: None,
+    };
+    buckets.insert(id, Arc::new(Mutex::new(BucketState { bucket, last: None })));
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Merges the heartbeat into the last event of the bucket when their data is the same and it
+/// starts within `pulsetime` of its end, and writes the merged event as an activity.
+async fn record_heartbeat(
+    db: &DatabaseConnection,
+    store: &BucketStore,
+    id: String,
+    event: Event,
+    pulsetime: i64,
+) -> Result<Event, CustomError> {
+    let start = parse_timestamp(&event.timestamp)?;
+    let end = start + (event.duration * 1000.0) as i64;
+
+    // watchers keep sending heartbeats after a restart without creating the bucket again
+    let state = store
+        .buckets
+        .lock()
+        .await
+        .entry(id.clone())
+        .or_insert_with(|| {
+            Arc::new(Mutex::new(BucketState {
+                bucket: Bucket {
+                    id: id.clone(),
+                    name: None,
+                    kind: guess_bucket_type(&id).to_string(),
+                    client: id.clone(),
+                    hostname: "unknown".to_string(),
+                    created: Utc::now().to_rfc3339(),
+                    data: Map::new(),
+                    last_updated: None,
+                },
+                last: None,
+            }))
+        })
+        .clone();
+    let mut state = state.lock().await;
+    let is_afk_bucket = state.bucket.kind == BUCKET_AFK;
+    if store.afk.load(Ordering::Relaxed) && !is_afk_bucket {
+        return Ok(event);
+    }
+    state.bucket.last_updated = Some(Utc::now().to_rfc3339());
+
+    let merged = match &mut state.last {
+        Some(last)
+            if last.data == event.data && start >= last.start && start <= last.end + pulsetime =>
+        {
+            last.end = last.end.max(end);
+            true
+        }
+        _ => false,
+    };
+
+    if !merged {
+        state.last = Some(LastEvent {
+            start,
+            end,
+            data: event.data,
+            activity_id: None,
+        });
+    }
+
+    let program = program_of(&state.bucket, &state.last.as_ref().unwrap().data);
+    let last = state.last.as_mut().unwrap();
+    let response = Event {
+        id: None,
+        timestamp: format_timestamp(last.start),
+        duration: (last.end - last.start) as f64 / 1000.0,
+        data: last.data.clone(),
+    };
+
+    if is_afk_bucket {
+        let afk = response
+            .data
+            .get("status")
+            .and_then(|status| status.as_str())
+            == Some("afk");
+        store.afk.store(afk, Ordering::Relaxed);
+        return Ok(response);
+    }
+
+    let Some(program) = program else {
+        return Ok(response);
+    };
+
+    if let Some(activity_id) = last.activity_id {
+        sql::update_activity_end(db, activity_id, last.end).await?;
+    } else {
+        let program = service::get_or_create_program(db, program.clone(), program).await?;
+        let activity = sql::create_activity(
+            db,
+            InsertActivity {
+                start: last.start,
+                end: last.end,
+                program_id: program.id,
+            },
+        )
+        .await?;
+        last.activity_id = Some(activity.id);
+    }
+    Ok(response)
+}
+
+#[post("/0/buckets/{bucket_id}/heartbeat")]
+pub async fn heartbeat(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<HeartbeatQuery>,
+    request: web::Json<Event>,
+    app_state: web::Data<AppState>,
+    store: web::Data<BucketStore>,
+) -> actix_web::Result<impl Responder> {
+    ensure_loopback(&req)?;
+    let app_handle = app_state.app.lock().unwrap().clone();
+    let db = get_db(&app_handle).await;
+    let pulsetime = (query.pulsetime * 1000.0) as i64;
+    let response = record_heartbeat(
+        &db,
+        &store,
+        path.into_inner(),
+        request.into_inner(),
+        pulsetime,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::fixture;
+
+    const BASE: i64 = 1_700_000_000_000;
+    const WINDOW: &str = "aw-watcher-window_host";
+    const AFK: &str = "aw-watcher-afk_host";
+
+    async fn send(
+        db: &DatabaseConnection,
+        store: &BucketStore,
+        bucket: &str,
+        offset: i64,
+        duration: f64,
+        data: &Value,
+    ) -> Event {
+        let event = Event {
+            id: None,
+            timestamp: format_timestamp(BASE + offset),
+            duration,
+            data: data.as_object().unwrap().clone(),
+        };
+        record_heartbeat(db, store, bucket.to_string(), event, 30_000)
+            .await
+            .unwrap()
+    }
+
+    async fn activity_list(db: &DatabaseConnection) -> Vec<(i64, i64)> {
+        sql::select_activity_list(db, 0, i64::MAX)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|activity| (activity.start - BASE, activity.end - BASE))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_merge() {
+        let db = fixture::setup().await;
+        let store = BucketStore::default();
+        let code = json!({ "app": "code", "title": "main.rs" });
+        send(&db, &store, WINDOW, 0, 0.0, &code).await;
+        // within the pulsetime of 30s the event grows
+        let merged = send(&db, &store, WINDOW, 10_000, 5.0, &code).await;
+        assert_eq!(merged.duration, 15.0);
+        assert_eq!(activity_list(&db).await, vec![(0, 15_000)]);
+
+        // past it, or with other data, a new event starts
+        send(&db, &store, WINDOW, 60_000, 0.0, &code).await;
+        let other = json!({ "app": "code", "title": "lib.rs" });
+        send(&db, &store, WINDOW, 61_000, 1.0, &other).await;
+        assert_eq!(
+            activity_list(&db).await,
+            vec![(0, 15_000), (60_000, 60_000), (61_000, 62_000)]
+        );
+
+        // window events are dropped while away
+        let afk = json!({ "status": "afk" });
+        send(&db, &store, AFK, 62_000, 0.0, &afk).await;
+        send(&db, &store, WINDOW, 63_000, 1.0, &other).await;
+        assert_eq!(activity_list(&db).await.len(), 3);
+    }
+}
@@ -0,0 +1,441 @@
+//! Read-only iCalendar feeds with one calendar per plan and per dimension.
+//!
+//! `/calendar/{kind}/{id}` serves a plain `.ics` feed, `/caldav/` exposes the same calendars as a
+//! minimal CalDAV collection (PROPFIND, REPORT and GET) so calendar apps can subscribe to them.
+
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    slice,
+};
+
+use actix_web::{
+    get,
+    http::{header::ContentType, Method, StatusCode},
+    route, web, HttpRequest, HttpResponse, Responder,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+
+use crate::{
+    database::service::{self, Calendar, CalendarEvent, CalendarKind},
+    get_db,
+    server::{ensure_loopback, error::CustomError, AppState},
+};
+
+/// Range listed when the client does not ask for one, events named by href are served at any time.
+const DEFAULT_RANGE_DAYS: i64 = 90;
+
+const ICS_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+#[derive(Deserialize)]
+struct RangeQuery {
+    start: Option<i64>,
+    end: Option<i64>,
+}
+
+fn parse_kind(kind: &str) -> Result<CalendarKind, CustomError> {
+    match kind {
+        "plan" => Ok(CalendarKind::Plan),
+        "dimension" => Ok(CalendarKind::Dimension),
+        _ => Err(CustomError::NotFound),
+    }
+}
+
+fn default_range() -> (i64, i64) {
+    let now = Utc::now().timestamp_millis();
+    (
+        now - chrono::Duration::days(DEFAULT_RANGE_DAYS).num_milliseconds(),
+        now,
+    )
+}
+
+fn format_ics_time(timestamp: i64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(timestamp)
+        .unwrap_or_default()
+        .format(ICS_TIME_FORMAT)
+        .to_string()
+}
+
+fn parse_ics_time(text: &str) -> Option<i64> {
+    NaiveDateTime::parse_from_str(text, ICS_TIME_FORMAT)
+        .ok()
+        .map(|time| time.and_utc().timestamp_millis())
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Folds content lines longer than 75 octets as required by RFC 5545.
+fn fold_line(line: &str) -> String {
+    let mut result = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            result.push_str("\r\n ");
+            width = 1;
+        }
+        result.push(c);
+        width += c.len_utf8();
+    }
+    result.push_str("\r\n");
+    result
+}
+
+fn render_event(event: &CalendarEvent, stamp: &str) -> String {
+    [
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}@shion", event.uid),
+        format!("DTSTAMP:{}", stamp),
+        format!("DTSTART:{}", format_ics_time(event.start)),
+        format!("DTEND:{}", format_ics_time(event.end)),
+        format!("SUMMARY:{}", escape_text(&event.summary)),
+        "TRANSP:TRANSPARENT".to_string(),
+        "END:VEVENT".to_string(),
+    ]
+    .iter()
+    .map(|line| fold_line(line))
+    .collect()
+}
+
+fn render_calendar(calendar: &Calendar, events: &[CalendarEvent]) -> String {
+    let stamp = format_ics_time(Utc::now().timestamp_millis());
+    let mut result = [
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//shion//shion//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(&calendar.name)),
+        format!("X-APPLE-CALENDAR-COLOR:{}", calendar.color),
+    ]
+    .iter()
+    .map(|line| fold_line(line))
+    .collect::<String>();
+    for event in events {
+        result.push_str(&render_event(event, &stamp));
+    }
+    result.push_str(&fold_line("END:VCALENDAR"));
+    result
+}
+
+fn etag(event: &CalendarEvent) -> String {
+    let mut hasher = DefaultHasher::new();
+    (event.start, event.end, &event.summary).hash(&mut hasher);
+    format!("\"{:x}\"", hasher.finish())
+}
+
+fn ctag(events: &[CalendarEvent]) -> String {
+    let mut hasher = DefaultHasher::new();
+    for event in events {
+        (&event.uid, event.start, event.end, &event.summary).hash(&mut hasher);
+    }
+    format!("{:x}", hasher.finish())
+}
+
+/// Returns the text content of every element with the given local name, ignoring namespaces.
+fn xml_values(body: &str, name: &str) -> Vec<String> {
+    let mut values = vec![];
+    let mut rest = body;
+    while let Some(index) = rest.find('<') {
+        rest = &rest[index + 1..];
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[..end];
+        let local_name = tag.split_whitespace().next().unwrap_or("");
+        let local_name = local_name.rsplit(':').next().unwrap_or(local_name);
+        rest = &rest[end + 1..];
+        if local_name == name && !tag.starts_with('/') && !tag.ends_with('/') {
+            if let Some(close) = rest.find('<') {
+                values.push(rest[..close].trim().to_string());
+            }
+        }
+    }
+    values
+}
+
+/// Reads the `time-range` filter of a calendar-query report.
+fn time_range(body: &str) -> Option<(i64, i64)> {
+    let index = body.find("time-range")?;
+    let tag = &body[index..index + body[index..].find('>')?];
+    let attribute = |name: &str| {
+        let start = tag.find(&format!("{}=\"", name))? + name.len() + 2;
+        let end = start + tag[start..].find('"')?;
+        parse_ics_time(&tag[start..end])
+    };
+    let (default_start, default_end) = default_range();
+    Some((
+        attribute("start").unwrap_or(default_start),
+        attribute("end").unwrap_or(default_end),
+    ))
+}
+
+fn collection_response(href: &str, calendar: &Calendar, ctag: &str) -> String {
+    format!(
+        r#"<d:response><d:href>{}</d:href><d:propstat><d:prop><d:resourcetype><d:collection/><c:calendar/></d:resourcetype><d:displayname>{}</d:displayname><ic:calendar-color>{}</ic:calendar-color><cs:getctag>{}</cs:getctag><c:supported-calendar-component-set><c:comp name="VEVENT"/></c:supported-calendar-component-set><d:current-user-privilege-set><d:privilege><d:read/></d:privilege></d:current-user-privilege-set></d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>"#,
+        escape_xml(href),
+        escape_xml(&calendar.name),
+        escape_xml(&calendar.color),
+        ctag
+    )
+}
+
+fn event_response(href: &str, event: &CalendarEvent, calendar_data: Option<String>) -> String {
+    let calendar_data = calendar_data
+        .map(|data| format!("<c:calendar-data>{}</c:calendar-data>", escape_xml(&data)))
+        .unwrap_or_default();
+    format!(
+        r#"<d:response><d:href>{}{}.ics</d:href><d:propstat><d:prop><d:getetag>{}</d:getetag><d:getcontenttype>text/calendar; charset=utf-8; component=VEVENT</d:getcontenttype>{}</d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>"#,
+        escape_xml(href),
+        event.uid,
+        escape_xml(&etag(event)),
+        calendar_data
+    )
+}
+
+fn multistatus(responses: Vec<String>) -> HttpResponse {
+    HttpResponse::build(StatusCode::MULTI_STATUS)
+        .content_type(ContentType::xml())
+        .body(format!(
+            r#"<?xml version="1.0" encoding="utf-8"?><d:multistatus xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav" xmlns:cs="http://calendarserver.org/ns/" xmlns:ic="http://apple.com/ns/ical/">{}</d:multistatus>"#,
+            responses.join("")
+        ))
+}
+
+fn dav_options() -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header(("DAV", "1, calendar-access"))
+        .insert_header(("Allow", "OPTIONS, GET, PROPFIND, REPORT"))
+        .finish()
+}
+
+async fn load_calendar(
+    db: &DatabaseConnection,
+    kind: CalendarKind,
+    id: i64,
+) -> Result<Calendar, CustomError> {
+    service::get_calendar(db, kind, id)
+        .await?
+        .ok_or(CustomError::NotFound)
+}
+
+#[get("/calendar/{kind}/{id}")]
+pub async fn get_calendar_feed(
+    req: HttpRequest,
+    path: web::Path<(String, i64)>,
+    query: web::Query<RangeQuery>,
+    app_state: web::Data<AppState>,
+) -> actix_web::Result<impl Responder> {
+    ensure_loopback(&req)?;
+    let (kind, id) = path.into_inner();
+    let kind = parse_kind(&kind)?;
+    let (default_start, default_end) = default_range();
+    let start = query.start.unwrap_or(default_start);
+    let end = query.end.unwrap_or(default_end);
+    let app_handle = app_state.app.lock().unwrap().clone();
+    let db = get_db(&app_handle).await;
+    let calendar = load_calendar(&db, kind, id).await?;
+    let events = service::get_calendar_events(&db, kind, id, start, end)
+        .await
+        .map_err(CustomError::from)?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .body(render_calendar(&calendar, &events)))
+}
+
+#[route("/caldav/", method = "OPTIONS", method = "PROPFIND")]
+pub async fn caldav_root(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+) -> actix_web::Result<impl Responder> {
+    ensure_loopback(&req)?;
+    if req.method() == Method::OPTIONS {
+        return Ok(dav_options());
+    }
+    let root = req.path().to_string();
+    let mut responses = vec![format!(
+        r#"<d:response><d:href>{}</d:href><d:propstat><d:prop><d:resourcetype><d:collection/></d:resourcetype><d:displayname>shion</d:displayname></d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>"#,
+        escape_xml(&root)
+    )];
+    let depth = req
+        .headers()
+        .get("Depth")
+        .and_then(|depth| depth.to_str().ok())
+        .unwrap_or("0");
+    if depth != "0" {
+        let app_handle = app_state.app.lock().unwrap().clone();
+        let db = get_db(&app_handle).await;
+        let (plans, dimensions) = futures_util::try_join!(
+            service::get_calendar_list(&db, CalendarKind::Plan),
+            service::get_calendar_list(&db, CalendarKind::Dimension)
+        )
+        .map_err(CustomError::from)?;
+        for (kind, list) in [("plan", plans), ("dimension", dimensions)] {
+            for (id, calendar) in list {
+                let href = format!("{}{}/{}/", root, kind, id);
+                responses.push(collection_response(&href, &calendar, ""));
+            }
+        }
+    }
+    Ok(multistatus(responses))
+}
+
+#[route(
+    "/caldav/{kind}/{id}/",
+    method = "OPTIONS",
+    method = "PROPFIND",
+    method = "REPORT"
+)]
+pub async fn caldav_collection(
+    req: HttpRequest,
+    path: web::Path<(String, i64)>,
+    body: String,
+    app_state: web::Data<AppState>,
+) -> actix_web::Result<impl Responder> {
+    ensure_loopback(&req)?;
+    if req.method() == Method::OPTIONS {
+        return Ok(dav_options());
+    }
+    let (kind, id) = path.into_inner();
+    let kind = parse_kind(&kind)?;
+    let href = req.path().to_string();
+    let is_report = req.method().as_str() == "REPORT";
+    let (start, end) = if is_report {
+        time_range(&body).unwrap_or(default_range())
+    } else {
+        default_range()
+    };
+
+    let app_handle = app_state.app.lock().unwrap().clone();
+    let db = get_db(&app_handle).await;
+    let calendar = load_calendar(&db, kind, id).await?;
+
+    if is_report {
+        let uid_list = xml_values(&body, "href")
+            .iter()
+            .filter_map(|href| {
+                href.rsplit('/')
+                    .next()?
+                    .strip_suffix(".ics")
+                    .map(String::from)
+            })
+            .collect::<Vec<_>>();
+        // a multiget names its events, they are found whenever they happened
+        let events = if uid_list.is_empty() {
+            service::get_calendar_events(&db, kind, id, start, end)
+                .await
+                .map_err(CustomError::from)?
+        } else {
+            let mut events = vec![];
+            for uid in &uid_list {
+                if let Some(event) = service::get_calendar_event(&db, kind, id, uid)
+                    .await
+                    .map_err(CustomError::from)?
+                {
+                    events.push(event);
+                }
+            }
+            events
+        };
+        let responses = events
+            .iter()
+            .map(|event| {
+                let data = render_calendar(&calendar, slice::from_ref(event));
+                event_response(&href, event, Some(data))
+            })
+            .collect();
+        return Ok(multistatus(responses));
+    }
+
+    let events = service::get_calendar_events(&db, kind, id, start, end)
+        .await
+        .map_err(CustomError::from)?;
+
+    let mut responses = vec![collection_response(&href, &calendar, &ctag(&events))];
+    let depth = req
+        .headers()
+        .get("Depth")
+        .and_then(|depth| depth.to_str().ok())
+        .unwrap_or("0");
+    if depth != "0" {
+        responses.extend(
+            events
+                .iter()
+                .map(|event| event_response(&href, event, None)),
+        );
+    }
+    Ok(multistatus(responses))
+}
+
+#[get("/caldav/{kind}/{id}/{event}")]
+pub async fn caldav_event(
+    req: HttpRequest,
+    path: web::Path<(String, i64, String)>,
+    app_state: web::Data<AppState>,
+) -> actix_web::Result<impl Responder> {
+    ensure_loopback(&req)?;
+    let (kind, id, event) = path.into_inner();
+    let kind = parse_kind(&kind)?;
+    let uid = event.strip_suffix(".ics").ok_or(CustomError::NotFound)?;
+    let app_handle = app_state.app.lock().unwrap().clone();
+    let db = get_db(&app_handle).await;
+    let calendar = load_calendar(&db, kind, id).await?;
+    let event = service::get_calendar_event(&db, kind, id, uid)
+        .await
+        .map_err(CustomError::from)?
+        .ok_or(CustomError::NotFound)?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .insert_header(("ETag", etag(&event)))
+        .body(render_calendar(&calendar, &[event])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_line() {
+        let line = "SUMMARY:".to_string() + &"a".repeat(100);
+        let folded = fold_line(&line);
+        assert!(folded.split("\r\n").all(|line| line.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), line + "\r\n");
+    }
+
+    #[test]
+    fn test_escape_text() {
+        assert_eq!(escape_text("a,b;c\\d\ne"), "a\\,b\\;c\\\\d\\ne");
+    }
+
+    #[test]
+    fn test_xml_values() {
+        let body = r#"<C:calendar-multiget xmlns:D="DAV:"><D:prop><D:getetag/></D:prop><D:href>/api/caldav/plan/1/note-1.ics</D:href><href>/api/caldav/plan/1/note-2.ics</href></C:calendar-multiget>"#;
+        assert_eq!(
+            xml_values(body, "href"),
+            vec![
+                "/api/caldav/plan/1/note-1.ics",
+                "/api/caldav/plan/1/note-2.ics"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_time_range() {
+        let body = r#"<C:time-range start="20240101T000000Z" end="20240102T000000Z"/>"#;
+        assert_eq!(
+            time_range(body),
+            Some((1704067200000, 1704067200000 + 86400000))
+        );
+    }
+}
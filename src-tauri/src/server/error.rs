@@ -17,6 +17,14 @@ pub enum CustomError {
     NotFound,
 }
 
+impl From<tauri_plugin_shion_sql::Error> for CustomError {
+    fn from(e: tauri_plugin_shion_sql::Error) -> Self {
+        CustomError::Database {
+            message: e.to_string(),
+        }
+    }
+}
+
 impl ResponseError for CustomError {
     fn error_response(&self) -> HttpResponse {
         HttpResponse::build(self.status_code()).json(json!({
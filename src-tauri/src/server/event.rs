@@ -0,0 +1,37 @@
+//! Server-sent events for clients that follow changes made through the api.
+
+use actix_web::{get, web::Bytes, HttpResponse, Responder};
+use futures_util::stream;
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+use tokio::sync::broadcast::{self, error::RecvError, Sender};
+
+/// Events beyond this are dropped for subscribers that fall behind.
+const CAPACITY: usize = 64;
+
+lazy_static! {
+    static ref BUS: Sender<String> = broadcast::channel(CAPACITY).0;
+}
+
+/// Sends an event to every connected subscriber, does nothing if there is none.
+pub fn publish(event: &str, payload: Value) {
+    let data = json!({ "event": event, "payload": payload });
+    let _ = BUS.send(format!("event: {}\ndata: {}\n\n", event, data));
+}
+
+#[get("/events")]
+pub async fn events() -> actix_web::Result<impl Responder> {
+    let stream = stream::unfold(BUS.subscribe(), |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(message) => return Some((Ok::<_, actix_web::Error>(Bytes::from(message)), rx)),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(stream))
+}
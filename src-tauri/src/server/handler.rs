@@ -1,24 +1,26 @@
 use actix_web::{get, post, web, Responder};
 use chrono::Utc;
 use futures_util::TryFutureExt;
-use serde::{Deserialize, Serialize};
+use serde_json::json;
+use shion_api::{AuthRequest, CreateRemarkRequest, FocusQuery, StartNoteRequest, StatusQuery};
 
 use crate::{
-    database::sql::{self, InsertRemark},
-    get_db,
+    database::{
+        bucket::BucketUnit,
+        service::{self, FocusLevel},
+        sql::{self, InsertNote, InsertRemark},
+    },
+    get_day_setting, get_db, get_note_conflict_policy,
     server::{
         error::CustomError,
+        event,
+        timer::TimerStore,
         token::{create_jwt, Claims},
         AppState, StopHandle,
     },
     success,
 };
 
-#[derive(Deserialize, Serialize, Clone)]
-struct AuthRequest {
-    path: String,
-}
-
 #[post("/auth")]
 pub async fn auth(
     request: web::Json<AuthRequest>,
@@ -28,7 +30,7 @@ pub async fn auth(
     let secret = app_state.secret.lock().unwrap();
     let db = get_db(&app_handle).await;
     let path = request.path.clone();
-    let program = sql::select_program_by_path(&db, path)
+    let program = service::resolve_program(&db, &path)
         .await
         .map_err(|e| CustomError::Database {
             message: e.to_string(),
@@ -40,14 +42,6 @@ pub async fn auth(
     success!(token)
 }
 
-#[derive(Deserialize, Serialize)]
-struct CreateRemarkRequest {
-    title: String,
-    desc: String,
-    arg: Option<String>,
-    time: Option<i64>,
-}
-
 #[post("/remark/create")]
 pub async fn create_remark(
     request: web::Json<CreateRemarkRequest>,
@@ -64,14 +58,167 @@ pub async fn create_remark(
     };
     let app_handle = app_state.app.lock().unwrap();
     let db = get_db(&app_handle).await;
-    sql::create_remark(&db, remark)
+    service::create_remark(&db, remark)
         .map_err(|e| CustomError::Database {
             message: e.to_string(),
         })
         .await?;
+    event::publish(
+        "remark.create",
+        json!({ "title": request.title, "programId": id }),
+    );
     success!()
 }
 
+#[post("/note/start")]
+pub async fn start_note(
+    request: web::Json<StartNoteRequest>,
+    app_state: web::Data<AppState>,
+    store: web::Data<TimerStore>,
+) -> actix_web::Result<impl Responder> {
+    let app_handle = app_state.app.lock().unwrap().clone();
+    let db = get_db(&app_handle).await;
+    let label = match sql::select_label_by_name(&db, request.label.clone())
+        .await
+        .map_err(|e| CustomError::Database {
+            message: e.to_string(),
+        })? {
+        Some(label) => label,
+        None => {
+            let plan_id = request.plan_id.ok_or(CustomError::BadRequest {
+                message: format!(
+                    "label {} not found, a plan is required to create it",
+                    request.label
+                ),
+            })?;
+            sql::select_plan_by_id(&db, plan_id)
+                .await
+                .map_err(|e| CustomError::Database {
+                    message: e.to_string(),
+                })?
+                .ok_or(CustomError::BadRequest {
+                    message: format!("plan {} not found", plan_id),
+                })?;
+            service::get_or_create_label(&db, request.label.clone(), plan_id)
+                .await
+                .map_err(|e| CustomError::Database {
+                    message: e.to_string(),
+                })?
+        }
+    };
+    let now = Utc::now().timestamp_millis();
+    let policy = get_note_conflict_policy(&app_handle).map_err(|e| CustomError::BadRequest {
+        message: e.to_string(),
+    })?;
+    let span_list = service::check_note(&db, None, (now, now), now, policy)
+        .await
+        .map_err(|e| CustomError::Database {
+            message: e.to_string(),
+        })?
+        .map_err(|e| CustomError::BadRequest {
+            message: e.to_string(),
+        })?;
+    let note_list = service::create_note(
+        &db,
+        InsertNote {
+            start: now,
+            end: now,
+            plan_id: label.plan_id,
+            label_id: label.id,
+            title: String::new(),
+        },
+        span_list,
+    )
+    .await
+    .map_err(|e| CustomError::Database {
+        message: e.to_string(),
+    })?;
+    let id = note_list[0].id;
+    TimerStore::track(store, &db, id, policy).await?;
+    success!(id)
+}
+
+#[post("/note/stop")]
+pub async fn stop_note(
+    app_state: web::Data<AppState>,
+    store: web::Data<TimerStore>,
+) -> actix_web::Result<impl Responder> {
+    let app_handle = app_state.app.lock().unwrap().clone();
+    let db = get_db(&app_handle).await;
+    let id = store.stop(&db, None).await?;
+    success!(id)
+}
+
+#[get("/status")]
+pub async fn status(
+    query: web::Query<StatusQuery>,
+    app_state: web::Data<AppState>,
+) -> actix_web::Result<impl Responder> {
+    let app_handle = app_state.app.lock().unwrap().clone();
+    let db = get_db(&app_handle).await;
+    let setting = get_day_setting(&app_handle).map_err(|e| CustomError::BadRequest {
+        message: e.to_string(),
+    })?;
+    let clock = service::get_clock(&db, &setting)
+        .await
+        .map_err(|e| CustomError::BadRequest {
+            message: e.to_string(),
+        })?;
+    let status = service::get_active_status_calendar_map(
+        &db,
+        query.start,
+        query.end,
+        query.busy.unwrap_or(false),
+        &clock,
+    )
+    .await
+    .map_err(|e| CustomError::Database {
+        message: e.to_string(),
+    })?;
+    success!(status)
+}
+
+#[get("/focus")]
+pub async fn focus(
+    query: web::Query<FocusQuery>,
+    app_state: web::Data<AppState>,
+) -> actix_web::Result<impl Responder> {
+    let app_handle = app_state.app.lock().unwrap().clone();
+    let db = get_db(&app_handle).await;
+    let setting = get_day_setting(&app_handle).map_err(|e| CustomError::BadRequest {
+        message: e.to_string(),
+    })?;
+    let clock = service::get_clock(&db, &setting)
+        .await
+        .map_err(|e| CustomError::BadRequest {
+            message: e.to_string(),
+        })?;
+    let unit = match query.hourly {
+        Some(true) => BucketUnit::Hour,
+        _ => BucketUnit::Day,
+    };
+    let level = match query.dimension {
+        Some(true) => FocusLevel::Dimension,
+        _ => FocusLevel::Program,
+    };
+    let list = service::get_focus_status_list(
+        &db,
+        query.start,
+        query.end,
+        level,
+        unit,
+        query
+            .idle_threshold
+            .unwrap_or(service::DEFAULT_IDLE_THRESHOLD),
+        &clock,
+    )
+    .await
+    .map_err(|e| CustomError::Database {
+        message: e.to_string(),
+    })?;
+    success!(list)
+}
+
 #[post("/stop")]
 pub async fn stop(stop_handle: web::Data<StopHandle>) -> actix_web::Result<impl Responder> {
     stop_handle.stop(false).await;
@@ -6,7 +6,7 @@ use actix_web::{
 };
 use futures_util::future::LocalBoxFuture;
 
-use crate::server::{error::CustomError, token::validate_jwt};
+use crate::server::{error::CustomError, is_route_match, token::validate_jwt};
 
 // There are two steps in middleware processing.
 // 1. Middleware initialization, middleware factory gets called with
@@ -61,7 +61,7 @@ where
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let path = req.path().to_string();
-        if self.allows.contains(&path) {
+        if self.allows.iter().any(|route| is_route_match(route, &path)) {
             let fut = self.service.call(req);
             return Box::pin(async move { fut.await });
         }
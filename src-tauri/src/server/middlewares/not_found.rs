@@ -4,7 +4,7 @@ use actix_web::{
     middleware::Next,
 };
 
-use crate::server::{error::CustomError, APP_ROUTES};
+use crate::server::{error::CustomError, is_route_match, APP_ROUTES};
 
 pub async fn not_found(
     req: ServiceRequest,
@@ -12,7 +12,7 @@ pub async fn not_found(
 ) -> actix_web::Result<ServiceResponse<impl MessageBody>> {
     let path = req.path().to_string();
 
-    if !APP_ROUTES.contains(&path) {
+    if !APP_ROUTES.iter().any(|route| is_route_match(route, &path)) {
         return Err(CustomError::NotFound.into());
     }
 
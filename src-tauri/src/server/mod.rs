@@ -4,27 +4,97 @@ use actix_cors::Cors;
 use actix_web::{
     dev::ServerHandle,
     middleware::{self, from_fn},
-    web, App, HttpServer,
+    web, App, HttpRequest, HttpServer,
 };
 use dotenv_codegen::dotenv;
 use lazy_static::lazy_static;
 use tauri::AppHandle;
 
+mod activitywatch;
+mod calendar;
 mod error;
+pub mod event;
 mod handler;
 mod middlewares;
 mod response;
+mod time;
+mod timer;
+mod toggl;
 mod token;
+mod wakatime;
 
+use error::CustomError;
 use middlewares::{auth::Auth, global_error::error_handler, not_found::not_found};
 
+enum Access {
+    /// Served without a token, third-party routes check the client is on this machine instead.
+    Public,
+    Token,
+}
+
+/// Every route under `/api`, with who may call it.
+const ROUTES: &[(&str, Access)] = &[
+    ("/auth", Access::Public),
+    ("/stop", Access::Public),
+    ("/ping", Access::Public),
+    ("/remark/create", Access::Token),
+    ("/note/start", Access::Token),
+    ("/note/stop", Access::Token),
+    ("/status", Access::Token),
+    ("/focus", Access::Token),
+    ("/events", Access::Token),
+    ("/0/info", Access::Public),
+    ("/0/buckets/", Access::Public),
+    ("/0/buckets/{bucket_id}", Access::Public),
+    ("/0/buckets/{bucket_id}/heartbeat", Access::Public),
+    ("/v1/users/current/heartbeats", Access::Public),
+    ("/v1/users/current/heartbeats.bulk", Access::Public),
+    ("/v9/me", Access::Public),
+    ("/v9/me/time_entries", Access::Public),
+    ("/v9/me/time_entries/current", Access::Public),
+    ("/v9/workspaces/{workspace_id}/projects", Access::Public),
+    ("/v9/workspaces/{workspace_id}/tags", Access::Public),
+    ("/v9/workspaces/{workspace_id}/time_entries", Access::Public),
+    (
+        "/v9/workspaces/{workspace_id}/time_entries/{time_entry_id}/stop",
+        Access::Public,
+    ),
+    ("/calendar/{kind}/{id}", Access::Public),
+    ("/caldav/", Access::Public),
+    ("/caldav/{kind}/{id}/", Access::Public),
+    ("/caldav/{kind}/{id}/{event}", Access::Public),
+];
+
 lazy_static! {
-    static ref APP_ROUTES: Vec<String> = vec!["/auth", "/stop", "/ping", "/remark/create"]
+    static ref APP_ROUTES: Vec<String> = ROUTES
         .iter()
-        .map(|route| "/api".to_string() + route)
+        .map(|(route, _)| "/api".to_string() + route)
+        .collect();
+    static ref PUBLIC_ROUTES: Vec<String> = ROUTES
+        .iter()
+        .filter(|(_, access)| matches!(access, Access::Public))
+        .map(|(route, _)| "/api".to_string() + route)
         .collect();
 }
 
+/// Matches a request path against a route, where `{name}` segments match any single segment.
+pub fn is_route_match(route: &str, path: &str) -> bool {
+    let route = route.split('/');
+    let path = path.split('/');
+    route.clone().count() == path.clone().count()
+        && route
+            .zip(path)
+            .all(|(r, p)| r == p || (r.starts_with('{') && r.ends_with('}') && !p.is_empty()))
+}
+
+/// Routes without token auth are only served to clients on this machine.
+pub fn ensure_loopback(req: &HttpRequest) -> Result<(), CustomError> {
+    match req.peer_addr() {
+        Some(addr) if addr.ip().is_loopback() => Ok(()),
+        _ => Err(CustomError::Unauthorized),
+    }
+}
+
 pub struct AppState {
     app: Mutex<AppHandle>,
     secret: Mutex<String>,
@@ -41,21 +111,24 @@ pub async fn init(app: AppHandle, port: u16) -> std::io::Result<()> {
 
     let stop_handle = web::Data::new(StopHandle::default());
 
+    let bucket_store = web::Data::new(activitywatch::BucketStore::default());
+    let session_store = web::Data::new(wakatime::SessionStore::default());
+    let timer_store = web::Data::new(timer::TimerStore::default());
+
     let server = HttpServer::new({
         let stop_handle = stop_handle.clone();
         move || {
             App::new()
                 .app_data(tauri_app.clone())
                 .app_data(stop_handle.clone())
+                .app_data(bucket_store.clone())
+                .app_data(session_store.clone())
+                .app_data(timer_store.clone())
                 .wrap(middleware::Logger::default())
                 .wrap(error_handler())
                 .wrap(Auth {
                     secret: secret.clone(),
-                    allows: vec![
-                        "/api/auth".to_string(),
-                        "/api/stop".to_string(),
-                        "/api/ping".to_string(),
-                    ],
+                    allows: PUBLIC_ROUTES.clone(),
                 })
                 .wrap(from_fn(not_found))
                 .wrap(Cors::permissive())
@@ -64,7 +137,30 @@ pub async fn init(app: AppHandle, port: u16) -> std::io::Result<()> {
                         .service(handler::auth)
                         .service(handler::create_remark)
                         .service(handler::stop)
-                        .service(handler::ping),
+                        .service(handler::ping)
+                        .service(handler::start_note)
+                        .service(handler::stop_note)
+                        .service(handler::status)
+                        .service(handler::focus)
+                        .service(event::events)
+                        .service(activitywatch::info)
+                        .service(activitywatch::get_buckets)
+                        .service(activitywatch::get_bucket)
+                        .service(activitywatch::create_bucket)
+                        .service(activitywatch::heartbeat)
+                        .service(wakatime::create_heartbeat)
+                        .service(wakatime::create_heartbeats)
+                        .service(toggl::me)
+                        .service(toggl::get_projects)
+                        .service(toggl::get_tags)
+                        .service(toggl::get_time_entries)
+                        .service(toggl::get_current_time_entry)
+                        .service(toggl::create_time_entry)
+                        .service(toggl::stop_time_entry)
+                        .service(calendar::get_calendar_feed)
+                        .service(calendar::caldav_root)
+                        .service(calendar::caldav_collection)
+                        .service(calendar::caldav_event),
                 )
         }
     })
@@ -0,0 +1,26 @@
+//! RFC 3339 timestamps of the third-party APIs, as milliseconds in the database.
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+
+use super::error::CustomError;
+
+/// Parses an RFC 3339 time, or a `YYYY-MM-DD` date as its local midnight.
+pub fn parse_timestamp(timestamp: &str) -> Result<i64, CustomError> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(timestamp) {
+        return Ok(time.timestamp_millis());
+    }
+    NaiveDate::parse_from_str(timestamp, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .and_then(|time| Local.from_local_datetime(&time).earliest())
+        .map(|time| time.timestamp_millis())
+        .ok_or(CustomError::BadRequest {
+            message: format!("invalid time: {}", timestamp),
+        })
+}
+
+pub fn format_timestamp(timestamp: i64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(timestamp)
+        .unwrap_or_default()
+        .to_rfc3339()
+}
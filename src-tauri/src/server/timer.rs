@@ -0,0 +1,164 @@
+//! Notes started through the api, persisted the same way as the frontend timer.
+
+use std::time::Duration;
+
+use actix_web::{rt, web};
+use chrono::Utc;
+use futures_util::lock::Mutex;
+use sea_orm::DatabaseConnection;
+use serde_json::json;
+
+use crate::{
+    database::service::{self, ConflictPolicy},
+    server::{error::CustomError, event},
+};
+
+/// Same interval the frontend timer uses to persist a running note.
+const TIMER_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+pub struct TimerStore {
+    /// The running note and the policy its end is extended with.
+    running: Mutex<Option<(i64, ConflictPolicy)>>,
+}
+
+impl TimerStore {
+    pub async fn running(&self) -> Option<i64> {
+        self.running.lock().await.map(|(id, _)| id)
+    }
+
+    /// Marks the note as running, stopping the previous one.
+    pub async fn track(
+        store: web::Data<TimerStore>,
+        db: &DatabaseConnection,
+        id: i64,
+        policy: ConflictPolicy,
+    ) -> Result<(), CustomError> {
+        let mut running = store.running.lock().await;
+        if let Some((previous, previous_policy)) = running.replace((id, policy)) {
+            stop_note(db, previous, previous_policy).await?;
+        }
+        drop(running);
+
+        event::publish("note.start", json!({ "id": id }));
+        spawn_timer(db.clone(), store, id, policy);
+        Ok(())
+    }
+
+    /// Stops the running note, or the given one when `id` is set and it is running.
+    pub async fn stop(
+        &self,
+        db: &DatabaseConnection,
+        id: Option<i64>,
+    ) -> Result<Option<i64>, CustomError> {
+        let mut running = self.running.lock().await;
+        match *running {
+            Some((current, policy)) if id.is_none() || id == Some(current) => {
+                stop_note(db, current, policy).await?;
+                *running = None;
+                Ok(Some(current))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+async fn stop_note(
+    db: &DatabaseConnection,
+    id: i64,
+    policy: ConflictPolicy,
+) -> Result<(), CustomError> {
+    // a note in the way keeps the end of the last extension
+    if let Err(conflict) = extend_note(db, id, policy).await? {
+        log::warn!("running note {} stopped early: {}", id, conflict);
+    }
+    event::publish("note.stop", json!({ "id": id }));
+    Ok(())
+}
+
+async fn extend_note(
+    db: &DatabaseConnection,
+    id: i64,
+    policy: ConflictPolicy,
+) -> Result<Result<(), service::NoteConflict>, CustomError> {
+    service::extend_running_note(db, id, Utc::now().timestamp_millis(), policy)
+        .await
+        .map_err(CustomError::from)
+}
+
+/// Keeps the end of a running note up to date until it is stopped.
+fn spawn_timer(
+    db: DatabaseConnection,
+    store: web::Data<TimerStore>,
+    id: i64,
+    policy: ConflictPolicy,
+) {
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(TIMER_INTERVAL);
+        loop {
+            interval.tick().await;
+            if store.running().await != Some(id) {
+                break;
+            }
+            match extend_note(&db, id, policy).await {
+                Ok(Ok(())) => {}
+                // the note cannot grow into the note in its way, it is stopped instead
+                Ok(Err(_)) => {
+                    if let Err(e) = store.stop(&db, Some(id)).await {
+                        log::error!("failed to stop running note {}: {}", id, e);
+                    }
+                    break;
+                }
+                Err(e) => log::error!("failed to update running note {}: {}", id, e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::ConnectionTrait;
+
+    use super::*;
+    use crate::database::{fixture, sql};
+
+    #[actix_web::test]
+    async fn test_track_and_stop() {
+        let db = fixture::setup().await;
+        let now = Utc::now().timestamp_millis();
+        let minute = 60 * 1000;
+        db.execute_unprepared(&format!(
+            r#"
+            INSERT INTO "plan" ("name", "color") VALUES ('plan', '#000000');
+            INSERT INTO "label" ("name", "color", "plan_id") VALUES ('label', '#000000', 1);
+            INSERT INTO "note" ("start", "end", "plan_id", "label_id")
+                VALUES ({0}, {0}, 1, 1), ({1}, {2}, 1, 1);
+            "#,
+            now - 20 * minute,
+            now - 5 * minute,
+            now - 4 * minute,
+        ))
+        .await
+        .unwrap();
+        let store = web::Data::new(TimerStore::default());
+        let end = |id: i64| {
+            let db = db.clone();
+            async move { sql::select_note_by_id(&db, id).await.unwrap().unwrap().end }
+        };
+
+        TimerStore::track(store.clone(), &db, 1, ConflictPolicy::Resolve)
+            .await
+            .unwrap();
+        // starting another note stops the first, which grows up to the note in its way
+        TimerStore::track(store.clone(), &db, 2, ConflictPolicy::Resolve)
+            .await
+            .unwrap();
+        assert_eq!(store.running().await, Some(2));
+        assert_eq!(end(1).await, now - 5 * minute);
+
+        assert_eq!(store.stop(&db, Some(1)).await.unwrap(), None);
+        assert_eq!(store.stop(&db, None).await.unwrap(), Some(2));
+        assert_eq!(store.running().await, None);
+        assert!(end(2).await >= now);
+    }
+}
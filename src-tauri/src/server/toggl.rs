@@ -0,0 +1,431 @@
+//! Toggl Track v9 compatible time entry endpoints.
+//!
+//! Time entries are `note` rows: projects map to plans, the first tag maps to the label and the
+//! description to the title.
+//! Shion has a single user and workspace, so every workspace id resolves to the same data.
+//! Like other third-party routes these are only served to clients on this machine.
+
+use std::collections::HashMap;
+
+use actix_web::{get, patch, post, web, HttpRequest, HttpResponse, Responder};
+use chrono::Utc;
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    database::{
+        models::{label, note},
+        service::{self, ConflictPolicy},
+        sql::{self, InsertNote},
+    },
+    get_db, get_note_conflict_policy,
+    server::{
+        ensure_loopback,
+        error::CustomError,
+        time::{format_timestamp, parse_timestamp},
+        timer::TimerStore,
+        AppState,
+    },
+};
+
+const WORKSPACE_ID: i64 = 1;
+const USER_ID: i64 = 1;
+
+#[derive(Serialize)]
+struct TimeEntry {
+    id: i64,
+    workspace_id: i64,
+    wid: i64,
+    project_id: i64,
+    pid: i64,
+    user_id: i64,
+    uid: i64,
+    task_id: Option<i64>,
+    billable: bool,
+    start: String,
+    stop: Option<String>,
+    duration: i64,
+    duronly: bool,
+    description: String,
+    tags: Vec<String>,
+    tag_ids: Vec<i64>,
+    at: String,
+    server_deleted_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CreateTimeEntryRequest {
+    start: String,
+    stop: Option<String>,
+    duration: Option<i64>,
+    description: Option<String>,
+    project_id: Option<i64>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    tag_ids: Vec<i64>,
+}
+
+#[derive(Deserialize)]
+struct TimeEntryQuery {
+    start_date: Option<String>,
+    end_date: Option<String>,
+}
+
+fn to_time_entry(note: &note::Model, label: Option<&label::Model>, running: bool) -> TimeEntry {
+    let label_name = label.map(|label| label.name.clone()).unwrap_or_default();
+    TimeEntry {
+        id: note.id,
+        workspace_id: WORKSPACE_ID,
+        wid: WORKSPACE_ID,
+        project_id: note.plan_id,
+        pid: note.plan_id,
+        user_id: USER_ID,
+        uid: USER_ID,
+        task_id: None,
+        billable: false,
+        start: format_timestamp(note.start),
+        stop: (!running).then(|| format_timestamp(note.end)),
+        duration: if running {
+            -1
+        } else {
+            (note.end - note.start) / 1000
+        },
+        duronly: false,
+        // entries created before notes had a title are described by their label
+        description: if note.title.is_empty() {
+            label_name.clone()
+        } else {
+            note.title.clone()
+        },
+        tags: vec![label_name],
+        tag_ids: vec![note.label_id],
+        at: format_timestamp(note.updated_at),
+        server_deleted_at: None,
+    }
+}
+
+async fn load_time_entry(
+    db: &DatabaseConnection,
+    note: &note::Model,
+    running: bool,
+) -> Result<TimeEntry, CustomError> {
+    let label = sql::select_label_by_id(db, note.label_id).await?;
+    Ok(to_time_entry(note, label.as_ref(), running))
+}
+
+async fn resolve_label(
+    db: &DatabaseConnection,
+    request: &CreateTimeEntryRequest,
+) -> Result<label::Model, CustomError> {
+    if let Some(id) = request.tag_ids.first() {
+        return sql::select_label_by_id(db, *id)
+            .await?
+            .ok_or(CustomError::BadRequest {
+                message: format!("tag {} not found", id),
+            });
+    }
+    let name = request
+        .tags
+        .first()
+        .or(request.description.as_ref())
+        .filter(|name| !name.is_empty())
+        .ok_or(CustomError::BadRequest {
+            message: "a tag or description is required".to_string(),
+        })?;
+    if let Some(label) = sql::select_label_by_name(db, name.clone()).await? {
+        return Ok(label);
+    }
+    let plan_id = request.project_id.ok_or(CustomError::BadRequest {
+        message: "a project is required to create a tag".to_string(),
+    })?;
+    sql::select_plan_by_id(db, plan_id)
+        .await?
+        .ok_or(CustomError::BadRequest {
+            message: format!("project {} not found", plan_id),
+        })?;
+    service::get_or_create_label(db, name.clone(), plan_id)
+        .await
+        .map_err(CustomError::from)
+}
+
+#[get("/v9/me")]
+pub async fn me(req: HttpRequest) -> actix_web::Result<impl Responder> {
+    ensure_loopback(&req)?;
+    Ok(HttpResponse::Ok().json(json!({
+        "id": USER_ID,
+        "fullname": "shion",
+        "default_workspace_id": WORKSPACE_ID,
+        "timezone": iana_time_zone::get_timezone().unwrap_or("UTC".to_string()),
+    })))
+}
+
+#[get("/v9/workspaces/{workspace_id}/projects")]
+pub async fn get_projects(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+) -> actix_web::Result<impl Responder> {
+    ensure_loopback(&req)?;
+    let app_handle = app_state.app.lock().unwrap().clone();
+    let db = get_db(&app_handle).await;
+    let plans = sql::select_plan_list(&db)
+        .await
+        .map_err(CustomError::from)?
+        .into_iter()
+        .map(|plan| {
+            json!({
+                "id": plan.id,
+                "workspace_id": WORKSPACE_ID,
+                "name": plan.name,
+                "color": plan.color,
+                "active": true,
+            })
+        })
+        .collect::<Vec<_>>();
+    Ok(HttpResponse::Ok().json(plans))
+}
+
+#[get("/v9/workspaces/{workspace_id}/tags")]
+pub async fn get_tags(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+) -> actix_web::Result<impl Responder> {
+    ensure_loopback(&req)?;
+    let app_handle = app_state.app.lock().unwrap().clone();
+    let db = get_db(&app_handle).await;
+    let labels = sql::select_label_list(&db)
+        .await
+        .map_err(CustomError::from)?
+        .into_iter()
+        .map(|label| {
+            json!({
+                "id": label.id,
+                "workspace_id": WORKSPACE_ID,
+                "name": label.name,
+            })
+        })
+        .collect::<Vec<_>>();
+    Ok(HttpResponse::Ok().json(labels))
+}
+
+#[get("/v9/me/time_entries")]
+pub async fn get_time_entries(
+    req: HttpRequest,
+    query: web::Query<TimeEntryQuery>,
+    app_state: web::Data<AppState>,
+    store: web::Data<TimerStore>,
+) -> actix_web::Result<impl Responder> {
+    ensure_loopback(&req)?;
+    let now = Utc::now().timestamp_millis();
+    let start = match &query.start_date {
+        Some(start_date) => parse_timestamp(start_date)?,
+        // Toggl returns the last 9 days by default
+        None => now - chrono::Duration::days(9).num_milliseconds(),
+    };
+    let end = match &query.end_date {
+        Some(end_date) => parse_timestamp(end_date)?,
+        None => now,
+    };
+    let app_handle = app_state.app.lock().unwrap().clone();
+    let db = get_db(&app_handle).await;
+    let (notes, labels) = futures_util::try_join!(
+        sql::select_note_list(&db, start, end),
+        sql::select_label_list(&db)
+    )
+    .map_err(CustomError::from)?;
+    let labels = labels
+        .into_iter()
+        .map(|label| (label.id, label))
+        .collect::<HashMap<_, _>>();
+    let running = store.running().await;
+    let entries = notes
+        .iter()
+        .rev()
+        .map(|note| to_time_entry(note, labels.get(&note.label_id), running == Some(note.id)))
+        .collect::<Vec<_>>();
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+#[get("/v9/me/time_entries/current")]
+pub async fn get_current_time_entry(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    store: web::Data<TimerStore>,
+) -> actix_web::Result<impl Responder> {
+    ensure_loopback(&req)?;
+    let Some(id) = store.running().await else {
+        return Ok(HttpResponse::Ok().json(Value::Null));
+    };
+    let app_handle = app_state.app.lock().unwrap().clone();
+    let db = get_db(&app_handle).await;
+    let note = sql::select_note_by_id(&db, id)
+        .await
+        .map_err(CustomError::from)?;
+    match note {
+        Some(note) => Ok(HttpResponse::Ok().json(load_time_entry(&db, &note, true).await?)),
+        None => Ok(HttpResponse::Ok().json(Value::Null)),
+    }
+}
+
+/// Creates the note of a time entry, a running one is kept up to date until it is stopped.
+async fn create_entry(
+    db: &DatabaseConnection,
+    store: web::Data<TimerStore>,
+    request: &CreateTimeEntryRequest,
+    policy: ConflictPolicy,
+) -> Result<TimeEntry, CustomError> {
+    let start = parse_timestamp(&request.start)?;
+    let end = match (&request.stop, request.duration) {
+        (Some(stop), _) => Some(parse_timestamp(stop)?),
+        (None, Some(duration)) if duration >= 0 => Some(start + duration * 1000),
+        _ => None,
+    };
+    if end.is_some_and(|end| end < start) {
+        return Err(CustomError::BadRequest {
+            message: "stop must not be before start".to_string(),
+        });
+    }
+
+    let label = resolve_label(db, request).await?;
+    // a label found by its tag or name may belong to another plan than the project sent
+    if let Some(project_id) = request.project_id.filter(|id| *id != label.plan_id) {
+        return Err(CustomError::BadRequest {
+            message: format!("tag {} is not in project {}", label.id, project_id),
+        });
+    }
+    let now = Utc::now().timestamp_millis();
+    let span = (start, end.unwrap_or(now.max(start)));
+    let span_list = service::check_note(db, None, span, now, policy)
+        .await?
+        .map_err(|e| CustomError::BadRequest {
+            message: e.to_string(),
+        })?;
+    let running = end.is_none();
+    if running && span_list.last().map(|(_, end)| *end) != Some(span.1) {
+        // the timer would keep growing the entry into the notes after it
+        return Err(CustomError::BadRequest {
+            message: "a running entry must not overlap later notes".to_string(),
+        });
+    }
+    let note_list = service::create_note(
+        db,
+        InsertNote {
+            start: span.0,
+            end: span.1,
+            plan_id: label.plan_id,
+            label_id: label.id,
+            title: request.description.clone().unwrap_or_default(),
+        },
+        span_list,
+    )
+    .await?;
+    // the entry is reported as its last part, the one a timer keeps running
+    let note = note_list.last().unwrap();
+
+    if running {
+        // Toggl stops the running entry when a new one starts
+        TimerStore::track(store, db, note.id, policy).await?;
+    }
+    Ok(to_time_entry(note, Some(&label), running))
+}
+
+#[post("/v9/workspaces/{workspace_id}/time_entries")]
+pub async fn create_time_entry(
+    req: HttpRequest,
+    request: web::Json<CreateTimeEntryRequest>,
+    app_state: web::Data<AppState>,
+    store: web::Data<TimerStore>,
+) -> actix_web::Result<impl Responder> {
+    ensure_loopback(&req)?;
+    let app_handle = app_state.app.lock().unwrap().clone();
+    let db = get_db(&app_handle).await;
+    let policy = get_note_conflict_policy(&app_handle).map_err(|e| CustomError::BadRequest {
+        message: e.to_string(),
+    })?;
+    let entry = create_entry(&db, store, &request, policy).await?;
+    Ok(HttpResponse::Ok().json(entry))
+}
+
+/// Stops the running note of the time entry.
+async fn stop_entry(
+    db: &DatabaseConnection,
+    store: &TimerStore,
+    id: i64,
+) -> Result<TimeEntry, CustomError> {
+    if store.stop(db, Some(id)).await?.is_none() {
+        return Err(CustomError::BadRequest {
+            message: "time entry is not running".to_string(),
+        });
+    }
+    let note = sql::select_note_by_id(db, id)
+        .await?
+        .ok_or(CustomError::NotFound)?;
+    load_time_entry(db, &note, false).await
+}
+
+#[patch("/v9/workspaces/{workspace_id}/time_entries/{time_entry_id}/stop")]
+pub async fn stop_time_entry(
+    req: HttpRequest,
+    path: web::Path<(i64, i64)>,
+    app_state: web::Data<AppState>,
+    store: web::Data<TimerStore>,
+) -> actix_web::Result<impl Responder> {
+    ensure_loopback(&req)?;
+    let (_, id) = path.into_inner();
+    let app_handle = app_state.app.lock().unwrap().clone();
+    let db = get_db(&app_handle).await;
+    Ok(HttpResponse::Ok().json(stop_entry(&db, &store, id).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::ConnectionTrait;
+
+    use super::*;
+    use crate::database::fixture;
+
+    fn request(project_id: i64) -> CreateTimeEntryRequest {
+        let start = Utc::now().timestamp_millis() - 10 * 60 * 1000;
+        CreateTimeEntryRequest {
+            start: format_timestamp(start),
+            stop: None,
+            duration: None,
+            description: Some("review".to_string()),
+            project_id: Some(project_id),
+            tags: vec![],
+            tag_ids: vec![1],
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_start_and_stop_time_entry() {
+        let db = fixture::setup().await;
+        db.execute_unprepared(
+            r#"
+            INSERT INTO "plan" ("name", "color") VALUES ('plan', '#000000'), ('other', '#000000');
+            INSERT INTO "label" ("name", "color", "plan_id") VALUES ('label', '#000000', 1);
+            "#,
+        )
+        .await
+        .unwrap();
+        let store = web::Data::new(TimerStore::default());
+
+        // the tag belongs to the first plan
+        let result = create_entry(&db, store.clone(), &request(2), ConflictPolicy::Reject).await;
+        assert!(matches!(result, Err(CustomError::BadRequest { .. })));
+
+        let entry = create_entry(&db, store.clone(), &request(1), ConflictPolicy::Reject)
+            .await
+            .unwrap();
+        assert_eq!(entry.stop, None);
+        assert_eq!(entry.project_id, 1);
+        assert_eq!(store.running().await, Some(entry.id));
+
+        let entry = stop_entry(&db, &store, entry.id).await.unwrap();
+        assert!(entry.stop.is_some());
+        assert_eq!(store.running().await, None);
+        let result = stop_entry(&db, &store, entry.id).await;
+        assert!(matches!(result, Err(CustomError::BadRequest { .. })));
+    }
+}
@@ -0,0 +1,336 @@
+//! WakaTime-compatible heartbeat endpoints, so editor plugins can use Shion as their api url.
+//!
+//! Heartbeats of the same project are merged into coding sessions stored as `activity` rows
+//! of a program per project, with a remark for the project and every file worked on.
+
+use std::collections::HashMap;
+
+use actix_web::{http::StatusCode, post, web, HttpRequest, HttpResponse, Responder};
+use futures_util::lock::Mutex;
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    database::{
+        service,
+        sql::{self, InsertActivity, InsertRemark},
+    },
+    get_db,
+    server::{ensure_loopback, error::CustomError, AppState},
+};
+
+/// Prefix of the program paths, `wakatime/<project>`.
+const PROGRAM_PATH: &str = "wakatime";
+
+/// Same as the default keystroke timeout of WakaTime.
+const SESSION_TIMEOUT: i64 = 15 * 60 * 1000;
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Heartbeat {
+    entity: String,
+    #[serde(rename = "type", default)]
+    kind: Option<String>,
+    time: f64,
+    project: Option<String>,
+    language: Option<String>,
+    #[serde(default)]
+    is_write: bool,
+}
+
+struct Session {
+    program_id: i64,
+    end: i64,
+    activity_id: i64,
+}
+
+#[derive(Default)]
+pub struct SessionStore {
+    inner: Mutex<HashMap<String, Session>>,
+}
+
+fn remark_desc(heartbeat: &Heartbeat, saved: bool) -> String {
+    let mut detail = heartbeat.language.iter().cloned().collect::<Vec<_>>();
+    if saved {
+        detail.push("saved".to_string());
+    }
+    if detail.is_empty() {
+        heartbeat.entity.clone()
+    } else {
+        format!("{} ({})", heartbeat.entity, detail.join(", "))
+    }
+}
+
+/// Writes a remark when the heartbeat moves to another entity than the remark before it in
+/// time, or saves it for the first time. Late heartbeats follow the same rule.
+async fn create_remark(
+    db: &DatabaseConnection,
+    heartbeat: &Heartbeat,
+    project: &str,
+    program_id: i64,
+    time: i64,
+) -> Result<(), CustomError> {
+    let desc = remark_desc(heartbeat, heartbeat.is_write);
+    let saved_desc = remark_desc(heartbeat, true);
+    let previous = sql::select_last_remark(db, program_id, time).await?;
+    if previous.is_some_and(|previous| previous.desc == desc || previous.desc == saved_desc) {
+        return Ok(());
+    }
+    let is_file = heartbeat.kind.as_deref().unwrap_or("file") == "file";
+    let remark = InsertRemark {
+        title: project.to_string(),
+        desc,
+        arg: if is_file {
+            heartbeat.entity.clone()
+        } else {
+            "".to_string()
+        },
+        program_id,
+        time,
+    };
+    service::create_remark(db, remark).await?;
+    Ok(())
+}
+
+/// The session among the `(id, start, end)` activities that a late heartbeat at `time` belongs
+/// to, with its range grown to cover it, or `None` when it is too far from all of them.
+fn place_late_heartbeat(activity_list: &[(i64, i64, i64)], time: i64) -> Option<(i64, i64, i64)> {
+    activity_list
+        .iter()
+        .map(|&(id, start, end)| {
+            let distance = (start - time).max(time - end).max(0);
+            (distance, id, start.min(time), end.max(time))
+        })
+        .filter(|(distance, ..)| *distance <= SESSION_TIMEOUT)
+        .min_by_key(|(distance, ..)| *distance)
+        .map(|(_, id, start, end)| (id, start, end))
+}
+
+/// Merges a heartbeat sent late by the plugin's offline queue into the session around it. One
+/// too far from every session is dropped, alone it covers no time.
+async fn record_late(
+    db: &DatabaseConnection,
+    heartbeat: &Heartbeat,
+    project: &str,
+    program_id: i64,
+    time: i64,
+) -> Result<(), CustomError> {
+    let activity_list =
+        sql::select_activity_list(db, time - SESSION_TIMEOUT, time + SESSION_TIMEOUT)
+            .await?
+            .into_iter()
+            .filter(|activity| activity.program_id == program_id)
+            .map(|activity| (activity.id, activity.start, activity.end))
+            .collect::<Vec<_>>();
+    let Some((id, start, end)) = place_late_heartbeat(&activity_list, time) else {
+        return Ok(());
+    };
+    if !activity_list.contains(&(id, start, end)) {
+        sql::update_activity_range(db, id, start, end).await?;
+    }
+    create_remark(db, heartbeat, project, program_id, time).await
+}
+
+async fn record(
+    db: &DatabaseConnection,
+    store: &SessionStore,
+    mut heartbeats: Vec<Heartbeat>,
+) -> Result<(), CustomError> {
+    heartbeats.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    let mut sessions = store.inner.lock().await;
+    for heartbeat in heartbeats {
+        let time = (heartbeat.time * 1000.0) as i64;
+        let project = heartbeat.project.clone().unwrap_or("unknown".to_string());
+
+        match sessions.get_mut(&project) {
+            Some(session) if time >= session.end && time <= session.end + SESSION_TIMEOUT => {
+                session.end = time;
+                sql::update_activity_end(db, session.activity_id, time).await?;
+                create_remark(db, &heartbeat, &project, session.program_id, time).await?;
+            }
+            Some(session) if time < session.end => {
+                record_late(db, &heartbeat, &project, session.program_id, time).await?;
+            }
+            _ => {
+                let program = service::get_or_create_program(
+                    db,
+                    project.clone(),
+                    format!("{}/{}", PROGRAM_PATH, project),
+                )
+                .await?;
+                let activity = sql::create_activity(
+                    db,
+                    InsertActivity {
+                        start: time,
+                        end: time,
+                        program_id: program.id,
+                    },
+                )
+                .await?;
+                create_remark(db, &heartbeat, &project, program.id, time).await?;
+                sessions.insert(
+                    project,
+                    Session {
+                        program_id: program.id,
+                        end: time,
+                        activity_id: activity.id,
+                    },
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+#[post("/v1/users/current/heartbeats")]
+pub async fn create_heartbeat(
+    req: HttpRequest,
+    request: web::Json<Heartbeat>,
+    app_state: web::Data<AppState>,
+    store: web::Data<SessionStore>,
+) -> actix_web::Result<impl Responder> {
+    ensure_loopback(&req)?;
+    let heartbeat = request.into_inner();
+    let app_handle = app_state.app.lock().unwrap().clone();
+    let db = get_db(&app_handle).await;
+    record(&db, &store, vec![heartbeat.clone()]).await?;
+    Ok(HttpResponse::Created().json(json!({ "data": heartbeat })))
+}
+
+#[post("/v1/users/current/heartbeats.bulk")]
+pub async fn create_heartbeats(
+    req: HttpRequest,
+    request: web::Json<Vec<Heartbeat>>,
+    app_state: web::Data<AppState>,
+    store: web::Data<SessionStore>,
+) -> actix_web::Result<impl Responder> {
+    ensure_loopback(&req)?;
+    let heartbeats = request.into_inner();
+    let app_handle = app_state.app.lock().unwrap().clone();
+    let db = get_db(&app_handle).await;
+    record(&db, &store, heartbeats.clone()).await?;
+    let responses = heartbeats
+        .into_iter()
+        .map(|heartbeat| json!([{ "data": heartbeat }, StatusCode::CREATED.as_u16()]))
+        .collect::<Vec<_>>();
+    Ok(HttpResponse::Created().json(json!({ "responses": responses })))
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{EntityTrait, QueryOrder};
+
+    use super::*;
+    use crate::database::{fixture, models::remark};
+
+    const BASE: i64 = 1_700_000_000_000;
+
+    fn heartbeat(entity: &str, offset: i64, is_write: bool) -> Heartbeat {
+        Heartbeat {
+            entity: entity.to_string(),
+            kind: None,
+            time: (BASE + offset) as f64 / 1000.0,
+            project: Some("shion".to_string()),
+            language: None,
+            is_write,
+        }
+    }
+
+    async fn activity_list(db: &DatabaseConnection) -> Vec<(i64, i64)> {
+        sql::select_activity_list(db, 0, i64::MAX)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|activity| (activity.start - BASE, activity.end - BASE))
+            .collect()
+    }
+
+    async fn remark_list(db: &DatabaseConnection) -> Vec<(i64, String)> {
+        remark::Entity::find()
+            .order_by_asc(remark::Column::Time)
+            .all(db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|remark| (remark.time - BASE, remark.desc))
+            .collect()
+    }
+
+    #[test]
+    fn test_place_late_heartbeat() {
+        let activity_list = [(1, 0, 60_000), (2, 3_600_000, 3_700_000)];
+        // inside a session
+        assert_eq!(
+            place_late_heartbeat(&activity_list, 30_000),
+            Some((1, 0, 60_000))
+        );
+        // just before a session grows its start
+        assert_eq!(
+            place_late_heartbeat(&activity_list, 3_000_000),
+            Some((2, 3_000_000, 3_700_000))
+        );
+        // the closest session wins
+        assert_eq!(
+            place_late_heartbeat(&activity_list, 120_000),
+            Some((1, 0, 120_000))
+        );
+        assert_eq!(place_late_heartbeat(&activity_list, 2_000_000), None);
+        assert_eq!(place_late_heartbeat(&[], 0), None);
+    }
+
+    #[tokio::test]
+    async fn test_record_late_heartbeat() {
+        let db = fixture::setup().await;
+        let store = SessionStore::default();
+        let minute = 60_000;
+        record(
+            &db,
+            &store,
+            vec![
+                heartbeat("a.rs", 0, false),
+                heartbeat("a.rs", minute, true),
+                heartbeat("b.rs", 2 * minute, false),
+                heartbeat("b.rs", 3 * minute, false),
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(activity_list(&db).await, vec![(0, 3 * minute)]);
+        let remark = |offset: i64, desc: &str| (offset, desc.to_string());
+        assert_eq!(
+            remark_list(&db).await,
+            vec![
+                remark(0, "a.rs"),
+                remark(minute, "a.rs (saved)"),
+                remark(2 * minute, "b.rs")
+            ]
+        );
+
+        // inside the session only the remark rule applies, a.rs is already saved before it
+        record(&db, &store, vec![heartbeat("a.rs", 90_000, false)])
+            .await
+            .unwrap();
+        record(&db, &store, vec![heartbeat("c.rs", 30_000, false)])
+            .await
+            .unwrap();
+        // before the session it grows the start, too far from it it is dropped
+        record(&db, &store, vec![heartbeat("c.rs", -minute, false)])
+            .await
+            .unwrap();
+        record(&db, &store, vec![heartbeat("d.rs", -60 * minute, false)])
+            .await
+            .unwrap();
+        assert_eq!(activity_list(&db).await, vec![(-minute, 3 * minute)]);
+        assert_eq!(
+            remark_list(&db).await,
+            vec![
+                remark(-minute, "c.rs"),
+                remark(0, "a.rs"),
+                remark(30_000, "c.rs"),
+                remark(minute, "a.rs (saved)"),
+                remark(2 * minute, "b.rs")
+            ]
+        );
+    }
+}
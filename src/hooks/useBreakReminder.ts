@@ -0,0 +1,21 @@
+import { invoke } from '@tauri-apps/api/core'
+
+interface BreakReminder {
+  workStart: number
+  activeTime: number
+}
+
+const POSTPONE_MINUTE = 10
+
+export function useBreakReminder() {
+  const { t } = useI18n()
+  const confirm = useConfirmModal()
+
+  useTauriListen<BreakReminder>('break-reminder', ({ payload }) => {
+    confirm.require({
+      title: t('breakReminder.title'),
+      content: `${t('breakReminder.body', { minute: Math.floor(payload.activeTime / 60000) })} ${t('breakReminder.postpone', { minute: POSTPONE_MINUTE })}`,
+      onConfirm: () => invoke('postpone_break_reminder', { minutes: POSTPONE_MINUTE }),
+    })
+  })
+}
@@ -1,7 +1,7 @@
 import { onStatusChanged } from 'tauri-plugin-shion-watcher-api'
 
 import type { Maybe } from '@/interfaces'
-import { db } from '@/modules/database'
+import { createRemark } from '@/modules/database/models/remark'
 import { Timer } from '@/utils/timer'
 
 interface CurrentVideoResponse {
@@ -50,7 +50,7 @@ export function useDandanplay() {
       const { Playing, AnimeTitle, EpisodeTitle } = response
       current = response
       if (changed && Playing && AnimeTitle && EpisodeTitle) {
-        await db.remark.insert({
+        await createRemark({
           title: AnimeTitle,
           desc: EpisodeTitle,
           time: Date.now(),
@@ -1,3 +1,4 @@
+import { invoke } from '@tauri-apps/api/core'
 import { db } from '@/modules/database'
 import type { InsertNote, SelectLabel, SelectPlan } from '@/modules/database'
 
@@ -6,8 +7,8 @@ type NoteForm = Pick<InsertNote, 'planId' | 'labelId'> & Partial<Pick<InsertNote
 export function useNoteCreate() {
   const { t } = useI18n()
   const { parseFieldsError } = useDatabase()
-  const { start: startTimer, setText } = useTimerStore()
-  const { success } = useNotify()
+  const { start: startTimer, finish: finishTimer, setText } = useTimerStore()
+  const { success, error: notifyError } = useNotify()
 
   const { open, close, setModelValue } = useFormModal<NoteForm, {
     planList: Array<SelectPlan>
@@ -97,10 +98,13 @@ export function useNoteCreate() {
             setText(labelList.find(i => i.id == v.labelId)!.name)
             let noteId = 0
             try {
-              const data = await handleCreate(v)
-              noteId = data.lastInsertId
+              const idList = await handleCreate(v)
+              noteId = idList[idList.length - 1]
             }
             catch (error) {
+              // conflicts with other notes are reported by the backend as text
+              if (typeof error == 'string')
+                return notifyError({ text: error })
               return setErrors(parseFieldsError(error))
             }
             close()
@@ -108,9 +112,20 @@ export function useNoteCreate() {
               success({})
               return
             }
-            startTimer(() => db.note.update(noteId, {
-              end: Date.now(),
-            }))
+            let stopped = false
+            startTimer(async () => {
+              if (stopped)
+                return
+              try {
+                await invoke('extend_note', { id: noteId })
+              }
+              catch (error) {
+                // the note cannot grow into the note after it, it ends where it was last extended
+                stopped = true
+                notifyError({ text: String(error) })
+                await finishTimer()
+              }
+            })
           },
         },
       }
@@ -125,7 +140,7 @@ export function useNoteCreate() {
   function handleCreate(note: NoteForm) {
     const now = Date.now()
     const { planId, labelId, direct, start, end } = note
-    return db.note.insert({
+    return invoke<number[]>('create_note', {
       planId,
       labelId,
       start: direct ? start! : now,
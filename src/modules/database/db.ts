@@ -34,6 +34,11 @@ import { Remark } from './models/remark'
 import { Dimension } from './models/dimension'
 import { DimensionLabel } from './models/dimensionLabel'
 import { DimensionProgram } from './models/dimensionProgram'
+import { Goal } from './models/goal'
+import { UsageLimit } from './models/usageLimit'
+import { TimeBlock } from './models/timeBlock'
+import { NoteTemplate } from './models/noteTemplate'
+import { ProgramAlias } from './models/programAlias'
 
 export type { QueryResult } from 'tauri-plugin-shion-sql-api'
 
@@ -263,6 +268,11 @@ const remark = new Remark(kysely, program)
 const dimension = new Dimension(kysely, label, program)
 const dimensionLabel = new DimensionLabel(kysely)
 const dimensionProgram = new DimensionProgram(kysely)
+const goal = new Goal(kysely)
+const usageLimit = new UsageLimit(kysely)
+const timeBlock = new TimeBlock(kysely)
+const noteTemplate = new NoteTemplate(kysely)
+const programAlias = new ProgramAlias(kysely)
 const models = {
   program,
   activity,
@@ -279,6 +289,11 @@ const models = {
   dimension,
   dimensionLabel,
   dimensionProgram,
+  goal,
+  usageLimit,
+  timeBlock,
+  noteTemplate,
+  programAlias,
 }
 
 export type Models = typeof models
@@ -313,6 +328,16 @@ export type DatabaseExecutor<T> = Pick<Database, 'execute' | 'select' | 'close'
   transaction(cb: () => Promise<unknown>): Promise<unknown>
 }
 
+/** Parses a `(code: N) message` error from the sql plugin or a backend command. */
+export function parseDatabaseError(err: string) {
+  const match = err.match(/\(code: (\d+)\) (.+)/)
+  const code = match?.[1] || SqliteErrorEnum.RAW
+  const detail = match?.[2].trim() || ''
+  const fields = findSqliteMessageFields(detail)
+
+  return new DatabaseError(detail, Number(code), fields)
+}
+
 export function findSqliteMessageFields(message: string) {
   const regex = /[a-zA-Z0-9]+\.([a-zA-Z0-9_]+)/g
   let match: RegExpExecArray | null
@@ -3,8 +3,8 @@ import type { Insertable, SelectType, Updateable } from 'kysely'
 import Database from 'tauri-plugin-shion-sql-api'
 
 import type { DatabaseExecutor } from './db'
-import { DatabaseError, SqliteErrorEnum, createKyselyDatabaseWithModels, findSqliteMessageFields } from './db'
-import type { Activity, Box, Dimension, DimensionLabel, DimensionProgram, Domain, History, Label, Moment, Note, Overview, Plan, Program, Remark } from './transform-types'
+import { createKyselyDatabaseWithModels, parseDatabaseError } from './db'
+import type { Activity, Box, Dimension, DimensionLabel, DimensionProgram, Domain, Goal, History, Label, Moment, Note, NoteTemplate, Overview, Plan, Program, ProgramAlias, Remark, TimeBlock, UsageLimit } from './transform-types'
 export { DatabaseError } from './db'
 
 class Executor implements DatabaseExecutor<Database> {
@@ -21,12 +21,7 @@ class Executor implements DatabaseExecutor<Database> {
   handleError(err: string) {
     error(err)
 
-    const match = err.match(/\(code: (\d+)\) (.+)/)
-    const code = match?.[1] || SqliteErrorEnum.RAW
-    const detail = match?.[2].trim() || ''
-    const fields = findSqliteMessageFields(detail)
-
-    return new DatabaseError(detail, Number(code), fields)
+    return parseDatabaseError(err)
   }
 
   close() {
@@ -103,6 +98,11 @@ export type SelectRemark = DeepSelectable<Remark>
 export type SelectDimension = DeepSelectable<Dimension>
 export type SelectDimensionLabel = DeepSelectable<DimensionLabel>
 export type SelectDimensionProgram = DeepSelectable<DimensionProgram>
+export type SelectGoal = DeepSelectable<Goal>
+export type SelectUsageLimit = DeepSelectable<UsageLimit>
+export type SelectTimeBlock = DeepSelectable<TimeBlock>
+export type SelectNoteTemplate = DeepSelectable<NoteTemplate>
+export type SelectProgramAlias = DeepSelectable<ProgramAlias>
 
 export type InsertPlan = Insertable<Plan>
 export type InsertNote = Insertable<Note>
@@ -113,6 +113,11 @@ export type InsertBox = Insertable<Box>
 export type InsertHistory = Insertable<History>
 export type InsertRemark = Insertable<Remark>
 export type InsertDimension = Insertable<Dimension>
+export type InsertGoal = Insertable<Goal>
+export type InsertUsageLimit = Insertable<UsageLimit>
+export type InsertTimeBlock = Insertable<TimeBlock>
+export type InsertNoteTemplate = Insertable<NoteTemplate>
+export type InsertProgramAlias = Insertable<ProgramAlias>
 
 export type UpdateOverview = Updateable<Overview>
 
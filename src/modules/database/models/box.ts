@@ -1,7 +1,12 @@
 import type { Insertable } from 'kysely'
 import { sql } from 'kysely'
 import type { Box as TransformBox } from '../transform-types'
-import { Model, get, set } from './model'
+import { Model, get, invokeJournaled, set } from './model'
+
+/** With `relation`, the moments of the boxes are removed too. */
+export function removeBox(idList: number[], relation: boolean) {
+  return invokeJournaled<void>('remove_box', { idList, relation })
+}
 
 export class Box extends Model<TransformBox> {
   table = 'box' as const
@@ -15,19 +20,6 @@ export class Box extends Model<TransformBox> {
     })
   }
 
-  removeRelation(id: number) {
-    return this.transaction().execute(async (trx) => {
-      await trx.box.remove(id)
-      await trx.moment.removeBy({
-        boxId: id,
-      })
-    })
-  }
-
-  batchRemoveRelation(idList: number[]) {
-    return this.transaction().execute(trx => Promise.all(idList.map(id => trx.box.removeRelation(id))))
-  }
-
   @get()
   select(value?: { id?: number }) {
     const query = this.selectByLooseType(value)
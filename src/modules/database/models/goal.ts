@@ -0,0 +1,25 @@
+import type { Goal as TransformGoal } from '../transform-types'
+import { Model, get } from './model'
+
+export enum GoalTargetType {
+  LABEL,
+  PLAN,
+  PROGRAM,
+  DIMENSION,
+}
+
+export enum GoalPeriod {
+  DAY,
+  WEEK,
+  MONTH,
+}
+
+export class Goal extends Model<TransformGoal> {
+  table = 'goal' as const
+
+  @get()
+  select(value?: { id?: number }) {
+    const query = this.selectByLooseType(value)
+    return query.selectAll(this.table).orderBy(['goal.sort'])
+  }
+}
@@ -1,7 +1,29 @@
 import type { Insertable } from 'kysely'
 import { sql } from 'kysely'
 import type { Label as TransformLabel } from '../transform-types'
-import { Model, get, set } from './model'
+import { Model, get, invokeJournaled, set } from './model'
+
+export interface LabelPatch {
+  id: number
+  name?: string
+  color?: string
+  planId?: number
+  sort?: number
+  hidden?: boolean
+}
+
+export function createLabel(value: { name: string; color: string; planId: number }) {
+  return invokeJournaled<number>('create_label', value)
+}
+
+export function updateLabelList(patchList: LabelPatch[]) {
+  return invokeJournaled<void>('update_label_list', { patchList })
+}
+
+/** With `relation`, children move up to the parent and notes, time blocks and templates are removed too. */
+export function removeLabel(idList: number[], relation: boolean) {
+  return invokeJournaled<void>('remove_label', { idList, relation })
+}
 
 export class Label extends Model<TransformLabel> {
   table = 'label' as const
@@ -15,12 +37,27 @@ export class Label extends Model<TransformLabel> {
     })
   }
 
+  // children of a removed label move up to its parent
+  liftChildren(id: number) {
+    return this.kysely.updateTable(this.table).set(eb => ({
+      parentId: eb.selectFrom('label as l').select('l.parentId').where('l.id', '=', id),
+      updatedAt: Date.now(),
+    })).where('parentId', '=', id).where('deletedAt', '=', 0)
+  }
+
   removeRelation(id: number) {
     return this.transaction().execute(async (trx) => {
       await trx.label.remove(id)
+      await trx.label.liftChildren(id)
       await trx.note.removeBy({
         labelId: id,
       })
+      await trx.timeBlock.removeBy({
+        labelId: id,
+      })
+      await trx.noteTemplate.removeBy({
+        labelId: id,
+      })
     })
   }
 
@@ -65,6 +102,7 @@ export class Label extends Model<TransformLabel> {
               'label.sort',
               'label.hidden',
               'label.planId',
+              'label.parentId',
               'label.deletedAt',
               'label.createdAt',
               'label.updatedAt',
@@ -1,7 +1,15 @@
+import { type InvokeArgs, invoke } from '@tauri-apps/api/core'
 import { type Insertable, type Kysely, type Updateable } from 'kysely'
 import type { DB } from '../transform-types'
 import type { Executor } from '../db'
-import { TransactionBuilder } from '../db'
+import { TransactionBuilder, parseDatabaseError } from '../db'
+
+/** Calls a backend command that journals its writes, so they can be undone. */
+export function invokeJournaled<T>(command: string, args: InvokeArgs) {
+  return invoke<T>(command, args).catch((err) => {
+    throw parseDatabaseError(String(err))
+  })
+}
 
 type TableName = keyof DB
 
@@ -2,10 +2,14 @@ import { type Kysely } from 'kysely'
 import { jsonBuildObject } from 'kysely/helpers/sqlite'
 
 import type { DB, Note as TransformNote } from '../transform-types'
-import { Model, get, injectModel } from './model'
+import { Model, get, injectModel, invokeJournaled } from './model'
 import { Label } from './label'
 import { Plan } from './plan'
 
+export function removeNote(id: number) {
+  return invokeJournaled<void>('remove_note', { id })
+}
+
 @injectModel({
   relation: {
     label: Label,
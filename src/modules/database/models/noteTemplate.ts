@@ -0,0 +1,20 @@
+import type { NoteTemplate as TransformNoteTemplate } from '../transform-types'
+import { Model, get } from './model'
+
+export class NoteTemplate extends Model<TransformNoteTemplate> {
+  table = 'noteTemplate' as const
+
+  removeBy(value: { labelId?: number }) {
+    let query = this.baseRemove()
+    if (value.labelId)
+      query = query.where('labelId', '=', value.labelId)
+
+    return query
+  }
+
+  @get()
+  select(value?: { id?: number }) {
+    const query = this.selectByLooseType(value)
+    return query.selectAll(this.table).orderBy(['noteTemplate.sort'])
+  }
+}
@@ -2,9 +2,27 @@ import { sql } from 'kysely'
 import type { Program as PluginProgram } from 'tauri-plugin-shion-watcher-api'
 
 import type { Program as TransformProgram } from '../transform-types'
-import { Model, get, set } from './model'
+import { Model, get, invokeJournaled, set } from './model'
 import { upload } from '@/modules/upload'
 
+export interface ProgramPatch {
+  id: number
+  name?: string
+  color?: string
+  path?: string
+  sort?: number
+  hidden?: boolean
+}
+
+export function updateProgramList(patchList: ProgramPatch[]) {
+  return invokeJournaled<void>('update_program_list', { patchList })
+}
+
+/** With `relation`, the activities and aliases of the programs are removed too. */
+export function removeProgram(idList: number[], relation: boolean) {
+  return invokeJournaled<void>('remove_program', { idList, relation })
+}
+
 export class Program extends Model<TransformProgram> {
   table = 'program' as const
 
@@ -33,6 +51,9 @@ export class Program extends Model<TransformProgram> {
       await trx.activity.removeBy({
         programId: id,
       })
+      await trx.programAlias.removeBy({
+        programId: id,
+      })
     })
   }
 
@@ -0,0 +1,38 @@
+import { invoke } from '@tauri-apps/api/core'
+import type { Insertable } from 'kysely'
+import type { ProgramAlias as TransformProgramAlias } from '../transform-types'
+import { Model, get, set } from './model'
+
+export enum ProgramAliasKind {
+  GLOB,
+  REGEX,
+}
+
+/** Aliases are created by the backend, which rejects a pattern it cannot compile. */
+export function createProgramAlias(value: { programId: number; pattern: string; kind: ProgramAliasKind }) {
+  return invoke<number>('create_program_alias', value)
+}
+
+export class ProgramAlias extends Model<TransformProgramAlias> {
+  table = 'programAlias' as const
+
+  insert(@set _value: Insertable<TransformProgramAlias>): never {
+    throw new Error('use createProgramAlias, the pattern has to be checked')
+  }
+
+  removeBy(value: { programId?: number }) {
+    let query = this.baseRemove()
+    if (value.programId)
+      query = query.where('programId', '=', value.programId)
+
+    return query
+  }
+
+  @get()
+  select(value?: { id?: number; programId?: number }) {
+    let query = this.selectByLooseType(value)
+    if (value?.programId)
+      query = query.where('programId', '=', value.programId)
+    return query.selectAll(this.table).orderBy(['programAlias.sort', 'programAlias.id'])
+  }
+}
@@ -2,9 +2,13 @@ import { type Kysely, sql } from 'kysely'
 import { jsonBuildObject } from 'kysely/helpers/sqlite'
 
 import type { DB, Remark as TransformRemark } from '../transform-types'
-import { Model, get, injectModel } from './model'
+import { Model, get, injectModel, invokeJournaled } from './model'
 import { Program } from './program'
 
+export function createRemark(value: { title: string; desc: string; arg: string; programId: number; time: number }) {
+  return invokeJournaled<number>('create_remark', value)
+}
+
 @injectModel({
   relation: {
     program: Program,
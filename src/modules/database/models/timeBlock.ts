@@ -0,0 +1,20 @@
+import type { TimeBlock as TransformTimeBlock } from '../transform-types'
+import { Model, get } from './model'
+
+export class TimeBlock extends Model<TransformTimeBlock> {
+  table = 'timeBlock' as const
+
+  removeBy(value: { labelId?: number }) {
+    let query = this.baseRemove()
+    if (value.labelId)
+      query = query.where('labelId', '=', value.labelId)
+
+    return query
+  }
+
+  @get()
+  select(value?: { id?: number }) {
+    const query = this.selectByLooseType(value)
+    return query.selectAll(this.table).orderBy(['timeBlock.start'])
+  }
+}
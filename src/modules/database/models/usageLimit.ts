@@ -0,0 +1,12 @@
+import type { UsageLimit as TransformUsageLimit } from '../transform-types'
+import { Model, get } from './model'
+
+export class UsageLimit extends Model<TransformUsageLimit> {
+  table = 'usageLimit' as const
+
+  @get()
+  select(value?: { id?: number }) {
+    const query = this.selectByLooseType(value)
+    return query.selectAll(this.table).orderBy(['usageLimit.sort'])
+  }
+}
@@ -2,6 +2,8 @@ import type { ColumnType } from 'kysely'
 
 import type * as origin from './types'
 import type { WidgetType } from './models/overview'
+import type { GoalPeriod, GoalTargetType } from './models/goal'
+import type { ProgramAliasKind } from './models/programAlias'
 import type { Replace } from '@/interfaces'
 
 type TotalTime = ColumnType<number, never, never>
@@ -34,6 +36,24 @@ export type DimensionLabel = origin.DimensionLabel
 
 export type DimensionProgram = origin.DimensionProgram
 
+export type Goal = Replace<origin.Goal, {
+  targetType: GoalTargetType
+  period: GoalPeriod
+}>
+
+export type UsageLimit = Replace<origin.UsageLimit, {
+  targetType: GoalTargetType.PROGRAM | GoalTargetType.DIMENSION
+  period: GoalPeriod.DAY | GoalPeriod.WEEK
+}>
+
+export type NoteTemplate = origin.NoteTemplate
+
+export type ProgramAlias = Replace<origin.ProgramAlias, {
+  kind: ProgramAliasKind
+}>
+
+export type TimeBlock = origin.TimeBlock
+
 export type Overview = Replace<origin.Overview, {
   type: WidgetType
   data: {
@@ -62,4 +82,9 @@ export interface DB {
   dimension: Dimension
   dimensionLabel: DimensionLabel
   dimensionProgram: DimensionProgram
+  goal: Goal
+  usageLimit: UsageLimit
+  timeBlock: TimeBlock
+  noteTemplate: NoteTemplate
+  programAlias: ProgramAlias
 }
@@ -22,6 +22,44 @@ export interface Box {
   createdAt: Generated<number>
   updatedAt: Generated<number>
 }
+export interface Change {
+  id: Generated<number>
+  action: string
+  targetId: number | null
+  deletedAt: Generated<number>
+  createdAt: Generated<number>
+  updatedAt: Generated<number>
+}
+export interface ChangeEntry {
+  id: Generated<number>
+  changeId: number
+  tableName: string
+  rowId: number
+  before: string | null
+  after: string | null
+  rowIdList: string | null
+  deletedAt: Generated<number>
+  createdAt: Generated<number>
+  updatedAt: Generated<number>
+}
+export interface DailySummary {
+  id: Generated<number>
+  date: string
+  start: number
+  end: number
+  labelId: number | null
+  programId: number | null
+  total: number
+  clock: string
+  createdAt: Generated<number>
+  updatedAt: Generated<number>
+}
+export interface DailySummaryDirty {
+  id: Generated<number>
+  start: number
+  end: number
+  createdAt: Generated<number>
+}
 export interface Dimension {
   id: Generated<number>
   name: string
@@ -58,6 +96,19 @@ export interface Domain {
   createdAt: Generated<number>
   updatedAt: Generated<number>
 }
+export interface Goal {
+  id: Generated<number>
+  targetType: number
+  targetId: number
+  period: number
+  duration: number
+  atMost: Generated<number>
+  start: number
+  sort: Generated<number>
+  deletedAt: Generated<number>
+  createdAt: Generated<number>
+  updatedAt: Generated<number>
+}
 export interface History {
   id: Generated<number>
   title: string
@@ -74,6 +125,7 @@ export interface Label {
   color: string
   sort: Generated<number>
   planId: number
+  parentId: number | null
   hidden: Generated<number>
   deletedAt: Generated<number>
   createdAt: Generated<number>
@@ -101,6 +153,32 @@ export interface Note {
   end: number
   planId: number
   labelId: number
+  title: Generated<string>
+  noteTemplateId: number | null
+  occurrence: number | null
+  deletedAt: Generated<number>
+  createdAt: Generated<number>
+  updatedAt: Generated<number>
+}
+export interface NoteTemplate {
+  id: Generated<number>
+  labelId: number
+  start: number
+  end: number
+  rrule: string | null
+  materializedUntil: Generated<number>
+  sort: Generated<number>
+  deletedAt: Generated<number>
+  createdAt: Generated<number>
+  updatedAt: Generated<number>
+}
+export interface NoteTemplateException {
+  id: Generated<number>
+  noteTemplateId: number
+  occurrence: number
+  skip: Generated<number>
+  start: number | null
+  end: number | null
   deletedAt: Generated<number>
   createdAt: Generated<number>
   updatedAt: Generated<number>
@@ -140,6 +218,16 @@ export interface Program {
   createdAt: Generated<number>
   updatedAt: Generated<number>
 }
+export interface ProgramAlias {
+  id: Generated<number>
+  programId: number
+  pattern: string
+  kind: Generated<number>
+  sort: Generated<number>
+  deletedAt: Generated<number>
+  createdAt: Generated<number>
+  updatedAt: Generated<number>
+}
 export interface Remark {
   id: Generated<number>
   title: string
@@ -151,20 +239,72 @@ export interface Remark {
   createdAt: Generated<number>
   updatedAt: Generated<number>
 }
+export interface TimeBlock {
+  id: Generated<number>
+  labelId: number
+  start: number
+  end: number
+  rrule: string | null
+  sort: Generated<number>
+  deletedAt: Generated<number>
+  createdAt: Generated<number>
+  updatedAt: Generated<number>
+}
+export interface TimezoneLog {
+  id: Generated<number>
+  timezone: string
+  start: number
+  deletedAt: Generated<number>
+  createdAt: Generated<number>
+  updatedAt: Generated<number>
+}
+export interface UsageLimit {
+  id: Generated<number>
+  targetType: number
+  targetId: number
+  period: number
+  duration: number
+  warningPercent: Generated<number>
+  sort: Generated<number>
+  deletedAt: Generated<number>
+  createdAt: Generated<number>
+  updatedAt: Generated<number>
+}
+export interface UsageLimitSnooze {
+  id: Generated<number>
+  usageLimitId: number
+  start: number
+  end: number
+  deletedAt: Generated<number>
+  createdAt: Generated<number>
+  updatedAt: Generated<number>
+}
 export interface DB {
   activity: Activity
   box: Box
+  change: Change
+  changeEntry: ChangeEntry
+  dailySummary: DailySummary
+  dailySummaryDirty: DailySummaryDirty
   dimension: Dimension
   dimensionLabel: DimensionLabel
   dimensionProgram: DimensionProgram
   domain: Domain
+  goal: Goal
   history: History
   label: Label
   link: Link
   moment: Moment
   note: Note
+  noteTemplate: NoteTemplate
+  noteTemplateException: NoteTemplateException
   overview: Overview
   plan: Plan
   program: Program
+  programAlias: ProgramAlias
   remark: Remark
+  timeBlock: TimeBlock
+  timezoneLog: TimezoneLog
+  usageLimit: UsageLimit
+  usageLimitSnooze: UsageLimitSnooze
 }
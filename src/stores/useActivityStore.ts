@@ -1,4 +1,5 @@
 import { getProgramByPath, onStatusChanged, resume, suspend } from 'tauri-plugin-shion-watcher-api'
+import { invoke } from '@tauri-apps/api/core'
 import { debug } from '@tauri-apps/plugin-log'
 import { format } from 'date-fns'
 
@@ -127,9 +128,19 @@ export const useActivityStore = defineStore('activity', () => {
 
   watch(() => monitor.whiteList.length, restart)
 
+  // a path matching an alias is recorded as the program of the alias
+  async function findProgram(path: string) {
+    const program = monitor.whiteList.find(i => i.path == path)
+    if (program)
+      return program
+
+    const id = await invoke<number | null>('resolve_program_path', { path })
+    return monitor.whiteList.find(i => i.id == id)
+  }
+
   onStatusChanged(async ({ payload }) => {
     const { active, path, time } = payload
-    const exist = monitor.whiteList.find(i => i.path == path)
+    const exist = await findProgram(path)
     if (!exist) {
       const isStartsWith = config.value.watcherWhitelist.find(folder => path.startsWith(folder))
       if (isStartsWith) {
@@ -143,10 +154,10 @@ export const useActivityStore = defineStore('activity', () => {
     }
 
     if (active)
-      watcher.activate(path, time)
+      watcher.activate(exist.path, time)
 
     else
-      watcher.inactivate(path, time)
+      watcher.inactivate(exist.path, time)
   })
 
   onAppSuspend(handleSuspend)
@@ -28,6 +28,14 @@ interface Config {
   lastExport: number
   serverPort: number
   colorMode: ColorMode
+  dayStartHour: number
+  timezone: string
+  travelMode: boolean
+  breakReminder: boolean
+  breakReminderWorkMinute: number
+  breakReminderBreakMinute: number
+  breakReminderExcludedLabels: Array<number>
+  noteConflict: 'reject' | 'resolve'
   announcement: {
     lastVisited: number
   }
@@ -67,6 +75,14 @@ export const useConfigStore = defineStore('config', () => {
       lastExport: 0,
       serverPort: 15785,
       colorMode: ColorMode.Light,
+      dayStartHour: 0,
+      timezone: '',
+      travelMode: false,
+      breakReminder: false,
+      breakReminderWorkMinute: 50,
+      breakReminderBreakMinute: 5,
+      breakReminderExcludedLabels: [],
+      noteConflict: 'reject',
       announcement: {
         lastVisited: 0,
       },
@@ -126,6 +142,10 @@ export const useConfigStore = defineStore('config', () => {
           quit: t('tray.quit'),
         },
       })
+      invoke('update_break_reminder_text', {
+        title: t('breakReminder.title'),
+        body: t('breakReminder.body', { minute: '{minute}' }),
+      })
     }
   })
 